@@ -0,0 +1,371 @@
+use crate::compiler::{
+    encode::{short_immediate, try_encode},
+    instruction_builder::InstructionBuilder,
+    instruction_type::InstructionType,
+    register::Register,
+};
+
+/// Errors produced while assembling source text, each tagged with a 1-indexed line
+#[derive(Debug, Clone, PartialEq)]
+pub enum AssembleError {
+    UnknownMnemonic { line: usize, mnemonic: String },
+    BadRegister { line: usize, operand: String },
+    BadImmediate { line: usize, operand: String },
+    ImmediateOutOfRange { line: usize, value: i64 },
+    WrongOperandCount { line: usize, expected: usize, found: usize },
+}
+
+impl AssembleError {
+    /// Line number the error was reported on
+    pub fn line(&self) -> usize {
+        match self {
+            AssembleError::UnknownMnemonic { line, .. }
+            | AssembleError::BadRegister { line, .. }
+            | AssembleError::BadImmediate { line, .. }
+            | AssembleError::ImmediateOutOfRange { line, .. }
+            | AssembleError::WrongOperandCount { line, .. } => *line,
+        }
+    }
+}
+
+impl std::fmt::Display for AssembleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            AssembleError::UnknownMnemonic { line, mnemonic } => {
+                write!(f, "line {}: unknown mnemonic '{}'", line, mnemonic)
+            }
+            AssembleError::BadRegister { line, operand } => {
+                write!(f, "line {}: bad register '{}'", line, operand)
+            }
+            AssembleError::BadImmediate { line, operand } => {
+                write!(f, "line {}: bad immediate '{}'", line, operand)
+            }
+            AssembleError::ImmediateOutOfRange { line, value } => {
+                write!(f, "line {}: immediate {} out of range", line, value)
+            }
+            AssembleError::WrongOperandCount { line, expected, found } => {
+                write!(f, "line {}: expected {} operands, found {}", line, expected, found)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AssembleError {}
+
+/// Assemble MIPS-like source text (one instruction per line, `;` comments) into bytecode
+///
+/// The accepted syntax mirrors the output of `disassemble`, e.g. `LI R1, 10`,
+/// `BEQ R1, R2, +8` or `JMP 0x14`.
+pub fn assemble(src: &str) -> Result<Vec<u32>, AssembleError> {
+    let mut bytecode = Vec::new();
+
+    for (index, raw_line) in src.lines().enumerate() {
+        let code = raw_line.split(';').next().unwrap_or("").trim();
+        if code.is_empty() {
+            continue;
+        }
+
+        let instr = assemble_line(index + 1, code)?;
+        let bits = try_encode(instr).map_err(|_| AssembleError::ImmediateOutOfRange {
+            line: index + 1,
+            value: short_immediate(&instr).map_or(0, i64::from),
+        })?;
+        bytecode.push(bits);
+    }
+
+    Ok(bytecode)
+}
+
+fn assemble_line(line: usize, code: &str) -> Result<InstructionType, AssembleError> {
+    let (mnemonic, rest) = match code.find(char::is_whitespace) {
+        Some(split) => (&code[..split], code[split..].trim()),
+        None => (code, ""),
+    };
+    let operands: Vec<&str> = if rest.is_empty() {
+        Vec::new()
+    } else {
+        rest.split(',').map(str::trim).collect()
+    };
+    let ops = Operands { line, operands: &operands };
+
+    let instr = match mnemonic.to_ascii_uppercase().as_str() {
+        // R-Type
+        "ADD" => { ops.expect(3)?; InstructionBuilder::add(ops.reg(0)?, ops.reg(1)?, ops.reg(2)?) }
+        "SUB" => { ops.expect(3)?; InstructionBuilder::sub(ops.reg(0)?, ops.reg(1)?, ops.reg(2)?) }
+        "MUL" => { ops.expect(3)?; InstructionBuilder::mul(ops.reg(0)?, ops.reg(1)?, ops.reg(2)?) }
+        "MULH" => { ops.expect(3)?; InstructionBuilder::mul_high(ops.reg(0)?, ops.reg(1)?, ops.reg(2)?) }
+        "MULHU" => { ops.expect(3)?; InstructionBuilder::mul_high_unsigned(ops.reg(0)?, ops.reg(1)?, ops.reg(2)?) }
+        "DIV" => { ops.expect(3)?; InstructionBuilder::div(ops.reg(0)?, ops.reg(1)?, ops.reg(2)?) }
+        "DIVMOD" => { ops.expect(3)?; InstructionBuilder::divmod(ops.reg(0)?, ops.reg(1)?, ops.reg(2)?) }
+        "FADD" => { ops.expect(3)?; InstructionBuilder::float_add(ops.freg(0)?, ops.freg(1)?, ops.freg(2)?) }
+        "FSUB" => { ops.expect(3)?; InstructionBuilder::float_sub(ops.freg(0)?, ops.freg(1)?, ops.freg(2)?) }
+        "FMUL" => { ops.expect(3)?; InstructionBuilder::float_mul(ops.freg(0)?, ops.freg(1)?, ops.freg(2)?) }
+        "FDIV" => { ops.expect(3)?; InstructionBuilder::float_div(ops.freg(0)?, ops.freg(1)?, ops.freg(2)?) }
+        "ITOF" => { ops.expect(2)?; InstructionBuilder::int_to_float(ops.freg(0)?, ops.reg(1)?) }
+        "FTOI" => { ops.expect(2)?; InstructionBuilder::float_to_int(ops.reg(0)?, ops.freg(1)?) }
+        "MOV" => { ops.expect(2)?; InstructionBuilder::mov(ops.reg(0)?, ops.reg(1)?) }
+        "NEG" => { ops.expect(2)?; InstructionBuilder::neg(ops.reg(0)?, ops.reg(1)?) }
+        "ABS" => { ops.expect(2)?; InstructionBuilder::abs(ops.reg(0)?, ops.reg(1)?) }
+        "AND" => { ops.expect(3)?; InstructionBuilder::and(ops.reg(0)?, ops.reg(1)?, ops.reg(2)?) }
+        "OR" => { ops.expect(3)?; InstructionBuilder::or(ops.reg(0)?, ops.reg(1)?, ops.reg(2)?) }
+        "XOR" => { ops.expect(3)?; InstructionBuilder::xor(ops.reg(0)?, ops.reg(1)?, ops.reg(2)?) }
+        "NOT" => { ops.expect(2)?; InstructionBuilder::not(ops.reg(0)?, ops.reg(1)?) }
+        "SLL" => { ops.expect(3)?; InstructionBuilder::shift_left(ops.reg(0)?, ops.reg(1)?, ops.reg(2)?) }
+        "SRL" => { ops.expect(3)?; InstructionBuilder::shift_right(ops.reg(0)?, ops.reg(1)?, ops.reg(2)?) }
+        "ROL" => { ops.expect(3)?; InstructionBuilder::rotate_left(ops.reg(0)?, ops.reg(1)?, ops.reg(2)?) }
+        "CLZ" => { ops.expect(2)?; InstructionBuilder::count_leading_zeros(ops.reg(0)?, ops.reg(1)?) }
+        "POPCNT" => { ops.expect(2)?; InstructionBuilder::pop_count(ops.reg(0)?, ops.reg(1)?) }
+        "SEB" => { ops.expect(2)?; InstructionBuilder::sign_extend_byte(ops.reg(0)?, ops.reg(1)?) }
+        "SEH" => { ops.expect(2)?; InstructionBuilder::sign_extend_half(ops.reg(0)?, ops.reg(1)?) }
+        "ZEB" => { ops.expect(2)?; InstructionBuilder::zero_extend_byte(ops.reg(0)?, ops.reg(1)?) }
+        "ZEH" => { ops.expect(2)?; InstructionBuilder::zero_extend_half(ops.reg(0)?, ops.reg(1)?) }
+        "ROR" => { ops.expect(3)?; InstructionBuilder::rotate_right(ops.reg(0)?, ops.reg(1)?, ops.reg(2)?) }
+        "CMP" => { ops.expect(2)?; InstructionBuilder::compare(ops.reg(0)?, ops.reg(1)?) }
+        "SLT" => { ops.expect(3)?; InstructionBuilder::set_less_than(ops.reg(0)?, ops.reg(1)?, ops.reg(2)?) }
+        "SLTU" => { ops.expect(3)?; InstructionBuilder::set_less_than_unsigned(ops.reg(0)?, ops.reg(1)?, ops.reg(2)?) }
+        "MIN" => { ops.expect(3)?; InstructionBuilder::min(ops.reg(0)?, ops.reg(1)?, ops.reg(2)?) }
+        "MAX" => { ops.expect(3)?; InstructionBuilder::max(ops.reg(0)?, ops.reg(1)?, ops.reg(2)?) }
+        "MINU" => { ops.expect(3)?; InstructionBuilder::min_unsigned(ops.reg(0)?, ops.reg(1)?, ops.reg(2)?) }
+        "MAXU" => { ops.expect(3)?; InstructionBuilder::max_unsigned(ops.reg(0)?, ops.reg(1)?, ops.reg(2)?) }
+
+        // I-Type
+        "LI" => { ops.expect(2)?; InstructionBuilder::load_immediate(ops.reg(0)?, ops.imm16(1)?) }
+        "ENTER" => { ops.expect(1)?; InstructionBuilder::enter(ops.imm16(0)?) }
+        "ADDI" => { ops.expect(3)?; InstructionBuilder::add_immediate(ops.reg(0)?, ops.reg(1)?, ops.imm16(2)?) }
+        "LOAD" => { ops.expect(3)?; InstructionBuilder::load(ops.reg(0)?, ops.reg(1)?, ops.imm16(2)?) }
+        "STORE" => { ops.expect(3)?; InstructionBuilder::store(ops.reg(0)?, ops.reg(1)?, ops.imm16(2)?) }
+
+        "LB" => { ops.expect(3)?; InstructionBuilder::load_byte(ops.reg(0)?, ops.reg(1)?, ops.imm16(2)?) }
+        "SB" => { ops.expect(3)?; InstructionBuilder::store_byte(ops.reg(0)?, ops.reg(1)?, ops.imm16(2)?) }
+        "LH" => { ops.expect(3)?; InstructionBuilder::load_halfword(ops.reg(0)?, ops.reg(1)?, ops.imm16(2)?) }
+        "SH" => { ops.expect(3)?; InstructionBuilder::store_halfword(ops.reg(0)?, ops.reg(1)?, ops.imm16(2)?) }
+
+        // B-Type
+        "BEQ" => { ops.expect(3)?; InstructionBuilder::branch_equal(ops.reg(0)?, ops.reg(1)?, ops.imm16(2)?) }
+        "BNE" => { ops.expect(3)?; InstructionBuilder::branch_not_equal(ops.reg(0)?, ops.reg(1)?, ops.imm16(2)?) }
+        "BLT" => { ops.expect(3)?; InstructionBuilder::branch_less_than(ops.reg(0)?, ops.reg(1)?, ops.imm16(2)?) }
+        "BGE" => { ops.expect(3)?; InstructionBuilder::branch_greater_equal(ops.reg(0)?, ops.reg(1)?, ops.imm16(2)?) }
+        "BLTU" => { ops.expect(3)?; InstructionBuilder::branch_less_than_unsigned(ops.reg(0)?, ops.reg(1)?, ops.imm16(2)?) }
+        "BGEU" => { ops.expect(3)?; InstructionBuilder::branch_greater_equal_unsigned(ops.reg(0)?, ops.reg(1)?, ops.imm16(2)?) }
+        "BZ" => { ops.expect(2)?; InstructionBuilder::branch_zero(ops.reg(0)?, ops.imm16(1)?) }
+        "BNZ" => { ops.expect(2)?; InstructionBuilder::branch_not_zero(ops.reg(0)?, ops.imm16(1)?) }
+        "BZF" => { ops.expect(1)?; InstructionBuilder::branch_flags_zero(ops.imm16(0)?) }
+        "BNZF" => { ops.expect(1)?; InstructionBuilder::branch_flags_not_zero(ops.imm16(0)?) }
+        "BLTF" => { ops.expect(1)?; InstructionBuilder::branch_flags_less_than(ops.imm16(0)?) }
+        "BGEF" => { ops.expect(1)?; InstructionBuilder::branch_flags_greater_equal(ops.imm16(0)?) }
+
+        // J-Type
+        "JMP" => { ops.expect(1)?; InstructionBuilder::jump(ops.addr(0)?) }
+        "CALL" => { ops.expect(1)?; InstructionBuilder::call(ops.addr(0)?) }
+        "RET" => { ops.expect(0)?; InstructionBuilder::ret() }
+
+        // M-Type
+        "ALLOC" => { ops.expect(2)?; InstructionBuilder::allocate(ops.reg(0)?, ops.reg(1)?) }
+        "FREE" => { ops.expect(1)?; InstructionBuilder::free(ops.reg(0)?) }
+        "ALOAD" => { ops.expect(3)?; InstructionBuilder::array_load(ops.reg(0)?, ops.reg(1)?, ops.reg(2)?) }
+        "ASTORE" => { ops.expect(3)?; InstructionBuilder::array_store(ops.reg(0)?, ops.reg(1)?, ops.reg(2)?) }
+        "PUSH" => { ops.expect(1)?; InstructionBuilder::push(ops.reg(0)?) }
+        "POP" => { ops.expect(1)?; InstructionBuilder::pop(ops.reg(0)?) }
+
+        "MEMCPY" => { ops.expect(3)?; InstructionBuilder::mem_copy(ops.reg(0)?, ops.reg(1)?, ops.reg(2)?) }
+        "MEMSET" => { ops.expect(3)?; InstructionBuilder::mem_set(ops.reg(0)?, ops.reg(1)?, ops.reg(2)?) }
+        "CAS" => { ops.expect(3)?; InstructionBuilder::compare_and_swap(ops.reg(0)?, ops.reg(1)?, ops.reg(2)?) }
+        "JR" => { ops.expect(1)?; InstructionBuilder::jump_register(ops.reg(0)?) }
+        "CALLR" => { ops.expect(1)?; InstructionBuilder::call_register(ops.reg(0)?) }
+
+        // S-Type
+        "PRINT" => { ops.expect(1)?; InstructionBuilder::print(ops.reg(0)?) }
+        "PRINTX" => { ops.expect(1)?; InstructionBuilder::print_hex(ops.reg(0)?) }
+        "PUTC" => { ops.expect(1)?; InstructionBuilder::put_char(ops.reg(0)?) }
+        "READ" => { ops.expect(1)?; InstructionBuilder::read(ops.reg(0)?) }
+        "SYSCALL" => {
+            if operands.len() > 2 {
+                return Err(AssembleError::WrongOperandCount { line, expected: 2, found: operands.len() });
+            }
+            let rd = if operands.is_empty() { None } else { Some(ops.reg(0)?) };
+            let rs = if operands.len() == 2 { Some(ops.reg(1)?) } else { None };
+            InstructionBuilder::syscall(rd, rs)
+        }
+
+        // N-Type
+        "NOP" => { ops.expect(0)?; InstructionBuilder::nop() }
+        "HALT" => { ops.expect(0)?; InstructionBuilder::halt() }
+        "LEAVE" => { ops.expect(0)?; InstructionBuilder::leave() }
+
+        _ => {
+            return Err(AssembleError::UnknownMnemonic {
+                line,
+                mnemonic: mnemonic.to_string(),
+            })
+        }
+    };
+
+    Ok(instr)
+}
+
+/// Operand list of a single source line, with typed accessors that report errors
+struct Operands<'a> {
+    line: usize,
+    operands: &'a [&'a str],
+}
+
+impl Operands<'_> {
+    fn expect(&self, expected: usize) -> Result<(), AssembleError> {
+        if self.operands.len() != expected {
+            return Err(AssembleError::WrongOperandCount {
+                line: self.line,
+                expected,
+                found: self.operands.len(),
+            });
+        }
+        Ok(())
+    }
+
+    fn reg(&self, index: usize) -> Result<Register, AssembleError> {
+        self.prefixed_reg(index, 'R')
+    }
+
+    /// Parse a float register such as `F3`
+    fn freg(&self, index: usize) -> Result<Register, AssembleError> {
+        self.prefixed_reg(index, 'F')
+    }
+
+    fn prefixed_reg(&self, index: usize, prefix: char) -> Result<Register, AssembleError> {
+        let operand = self.operands[index];
+        let bad_register = || AssembleError::BadRegister {
+            line: self.line,
+            operand: operand.to_string(),
+        };
+
+        let number = operand
+            .strip_prefix(prefix)
+            .or_else(|| operand.strip_prefix(prefix.to_ascii_lowercase()))
+            .ok_or_else(bad_register)?;
+        let value: u8 = number.parse().map_err(|_| bad_register())?;
+        Register::new(value).map_err(|_| bad_register())
+    }
+
+    /// Parse a 16-bit immediate, accepting both signed and unsigned spellings
+    fn imm16(&self, index: usize) -> Result<u16, AssembleError> {
+        let value = self.number(index)?;
+        if !(i16::MIN as i64..=u16::MAX as i64).contains(&value) {
+            return Err(AssembleError::ImmediateOutOfRange { line: self.line, value });
+        }
+        Ok(value as u16)
+    }
+
+    /// Parse a 24-bit jump address
+    fn addr(&self, index: usize) -> Result<u32, AssembleError> {
+        let value = self.number(index)?;
+        if !(0..=0xFFFFFF).contains(&value) {
+            return Err(AssembleError::ImmediateOutOfRange { line: self.line, value });
+        }
+        Ok(value as u32)
+    }
+
+    fn number(&self, index: usize) -> Result<i64, AssembleError> {
+        let operand = self.operands[index];
+        let bad_immediate = || AssembleError::BadImmediate {
+            line: self.line,
+            operand: operand.to_string(),
+        };
+
+        let (negative, digits) = match operand.as_bytes().first() {
+            Some(b'-') => (true, &operand[1..]),
+            Some(b'+') => (false, &operand[1..]),
+            _ => (false, operand),
+        };
+
+        let magnitude = match digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+            Some(hex) => i64::from_str_radix(hex, 16),
+            None => digits.parse::<i64>(),
+        }
+        .map_err(|_| bad_immediate())?;
+
+        Ok(if negative { -magnitude } else { magnitude })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler::encode::encode;
+    use crate::compiler::disassemble::disassemble_word;
+    use crate::compiler::instruction_builder::registers::*;
+
+    #[test]
+    fn test_assemble_arithmetic_demo() {
+        let src = "
+            ; Arithmetic demo
+            LI R1, 10       ; R1 = 10
+            LI R2, 5        ; R2 = 5
+            ADD R3, R1, R2  ; R3 = R1 + R2 = 15
+            LI R4, 3
+            MUL R5, R3, R4
+            LI R6, 2
+            SUB R0, R5, R6
+            PRINT R0
+            HALT
+        ";
+
+        let expected = vec![
+            encode(InstructionBuilder::load_immediate(r1(), 10)),
+            encode(InstructionBuilder::load_immediate(r2(), 5)),
+            encode(InstructionBuilder::add(r3(), r1(), r2())),
+            encode(InstructionBuilder::load_immediate(r4(), 3)),
+            encode(InstructionBuilder::mul(r5(), r3(), r4())),
+            encode(InstructionBuilder::load_immediate(r6(), 2)),
+            encode(InstructionBuilder::sub(r0(), r5(), r6())),
+            encode(InstructionBuilder::print(r0())),
+            encode(InstructionBuilder::halt()),
+        ];
+
+        assert_eq!(assemble(src).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_assemble_operand_forms() {
+        let program = assemble("BEQ R1, R2, +8\nBNZ r3, -12\nJMP 0x14\nLI R1, 0xFF\nret").unwrap();
+
+        assert_eq!(program[0], encode(InstructionBuilder::branch_equal(r1(), r2(), 8)));
+        assert_eq!(program[1], encode(InstructionBuilder::branch_not_zero(r3(), -12i16 as u16)));
+        assert_eq!(program[2], encode(InstructionBuilder::jump(0x14)));
+        assert_eq!(program[3], encode(InstructionBuilder::load_immediate(r1(), 0xFF)));
+        assert_eq!(program[4], encode(InstructionBuilder::ret()));
+    }
+
+    #[test]
+    fn test_assemble_disassembly_round_trip() {
+        let src = "ADD R3, R1, R2\nLI R1, 42\nBZ R1, +8\nALLOC R2, R1\nCALL 0x123456\nPOP R8\nFADD F3, F1, F2\nFTOI R4, F3";
+        let program = assemble(src).unwrap();
+
+        for (line, &word) in src.lines().zip(&program) {
+            assert_eq!(disassemble_word(word).unwrap(), line);
+        }
+    }
+
+    #[test]
+    fn test_assemble_errors() {
+        let err = assemble("NOP\nFROB R1").unwrap_err();
+        assert_eq!(err, AssembleError::UnknownMnemonic { line: 2, mnemonic: "FROB".to_string() });
+
+        let err = assemble("LI R32, 1").unwrap_err();
+        assert_eq!(err, AssembleError::BadRegister { line: 1, operand: "R32".to_string() });
+
+        let err = assemble("\n\nLI R1, 70000").unwrap_err();
+        assert_eq!(err, AssembleError::ImmediateOutOfRange { line: 3, value: 70000 });
+        assert_eq!(err.line(), 3);
+
+        // Offsets next to a second register only have 14 bits
+        let err = assemble("LOAD R1, R29, -4\nADDI R1, R2, 9000").unwrap_err();
+        assert_eq!(err, AssembleError::ImmediateOutOfRange { line: 2, value: 9000 });
+
+        let err = assemble("FADD F1, R2, F3").unwrap_err();
+        assert_eq!(err, AssembleError::BadRegister { line: 1, operand: "R2".to_string() });
+
+        let err = assemble("ADD R1, R2").unwrap_err();
+        assert_eq!(err, AssembleError::WrongOperandCount { line: 1, expected: 3, found: 2 });
+
+        assert!(matches!(assemble("LI R1, ten"), Err(AssembleError::BadImmediate { line: 1, .. })));
+    }
+}
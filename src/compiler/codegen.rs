@@ -0,0 +1,643 @@
+use crate::compiler::{
+    instruction_builder::InstructionBuilder,
+    instruction_type::InstructionType,
+    regalloc::RegAllocator,
+    register::Register,
+};
+use crate::lexer::Token;
+use crate::parser::{Expr, Literal, Stmt};
+use std::collections::HashMap;
+
+/// First register handed out by the allocator; a lone expression leaves its value here
+pub const RESULT_REGISTER: u8 = 1;
+
+/// Last general-purpose register available to the allocator (R28 is scratch, R29-R31 are sp/fp/ra)
+pub const LAST_ALLOCATABLE_REGISTER: u8 = 27;
+
+/// Register kept out of the allocator for values that only live across a few instructions
+pub const SCRATCH_REGISTER: u8 = 28;
+
+/// Errors produced while generating code from the AST
+#[derive(Debug, Clone, PartialEq)]
+pub enum CodegenError {
+    UnsupportedExpression(String),
+    UnsupportedStatement(String),
+    UnsupportedOperator(Token),
+    UndefinedVariable(String),
+    InvalidAssignmentTarget,
+    BranchOutOfRange(i64),
+    IntegerOutOfRange(i64),
+    OutOfRegisters,
+}
+
+impl std::fmt::Display for CodegenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            CodegenError::UnsupportedExpression(what) => {
+                write!(f, "cannot compile {} expressions yet", what)
+            }
+            CodegenError::UnsupportedStatement(what) => {
+                write!(f, "cannot compile {} statements yet", what)
+            }
+            CodegenError::UnsupportedOperator(op) => write!(f, "unsupported operator {}", op),
+            CodegenError::UndefinedVariable(name) => write!(f, "undefined variable '{}'", name),
+            CodegenError::InvalidAssignmentTarget => write!(f, "invalid assignment target"),
+            CodegenError::BranchOutOfRange(offset) => {
+                write!(f, "branch offset {} does not fit in 16 bits", offset)
+            }
+            CodegenError::IntegerOutOfRange(value) => {
+                write!(f, "integer {} does not fit in a register", value)
+            }
+            CodegenError::OutOfRegisters => write!(f, "ran out of registers"),
+        }
+    }
+}
+
+impl std::error::Error for CodegenError {}
+
+pub type CodegenResult<T> = Result<T, CodegenError>;
+
+/// Compile a single expression; its value ends up in `RESULT_REGISTER`
+pub fn compile_expr(expr: &Expr) -> CodegenResult<Vec<InstructionType>> {
+    let mut codegen = Codegen::new();
+    codegen.compile_expr(expr)?;
+    Ok(codegen.finish())
+}
+
+/// Compile a sequence of statements
+pub fn compile_program(statements: &[Stmt]) -> CodegenResult<Vec<InstructionType>> {
+    let mut codegen = Codegen::new();
+    for stmt in statements {
+        codegen.compile_stmt(stmt)?;
+    }
+    Ok(codegen.finish())
+}
+
+/// Register-machine code generator
+///
+/// Temporaries come from a `RegAllocator` over R1-R27, which spills to the
+/// stack once every register is live. Variables are pinned to a register of
+/// their own on first assignment. R28 is never allocated, so an instruction
+/// always has a second operand register even when the first one was spilled.
+/// Jumps use absolute addresses, so the output expects to be loaded at address 0.
+#[derive(Debug)]
+pub struct Codegen {
+    code: Vec<InstructionType>,
+    registers: RegAllocator,
+    variables: HashMap<String, Register>,
+}
+
+impl Codegen {
+    pub fn new() -> Self {
+        Self {
+            code: Vec::new(),
+            registers: RegAllocator::new(RESULT_REGISTER, LAST_ALLOCATABLE_REGISTER),
+            variables: HashMap::new(),
+        }
+    }
+
+    /// Allocator state, including spill statistics
+    pub fn registers(&self) -> &RegAllocator {
+        &self.registers
+    }
+
+    /// Register holding the variable `name`, if it has been assigned
+    pub fn variable(&self, name: &str) -> Option<Register> {
+        self.variables.get(name).copied()
+    }
+
+    /// Instructions emitted so far
+    pub fn code(&self) -> &[InstructionType] {
+        &self.code
+    }
+
+    /// Consume the generator and return the emitted instructions
+    pub fn finish(self) -> Vec<InstructionType> {
+        self.code
+    }
+
+    /// Compile `expr` and return the register holding its value
+    ///
+    /// The caller owns the returned register and must hand it back with
+    /// `free_register` once the value is no longer needed.
+    pub fn compile_expr(&mut self, expr: &Expr) -> CodegenResult<Register> {
+        match expr {
+            Expr::Literal(literal) => self.compile_literal(literal),
+            Expr::Grouping(inner) => self.compile_expr(inner),
+            Expr::Unary { op, operand } => {
+                let reg = self.compile_expr(operand)?;
+                match op {
+                    Token::Plus => {}
+                    Token::Minus => self.emit(InstructionBuilder::neg(reg, reg)),
+                    Token::BitwiseNot => self.emit(InstructionBuilder::not(reg, reg)),
+                    other => return Err(CodegenError::UnsupportedOperator(other.clone())),
+                }
+                Ok(reg)
+            }
+            Expr::Binary { op, left, right } => {
+                if let Some(compound) = assignment_operator(op) {
+                    return self.compile_assignment(compound, left, right);
+                }
+
+                let lhs = self.compile_expr(left)?;
+                let rhs = self.compile_expr(right)?;
+                if rhs == lhs {
+                    // Allocating `rhs` spilled `lhs` into the same register; move the
+                    // right operand aside so freeing it can pop the left one back
+                    let scratch = scratch_register();
+                    self.emit(InstructionBuilder::mov(scratch, rhs));
+                    self.free_register(rhs);
+                    self.emit_binary(op, lhs, scratch)?;
+                } else {
+                    self.emit_binary(op, lhs, rhs)?;
+                    self.free_register(rhs);
+                }
+                Ok(lhs)
+            }
+            Expr::Identifier(name) => {
+                let var = self
+                    .variable(name)
+                    .ok_or_else(|| CodegenError::UndefinedVariable(name.clone()))?;
+                let reg = self.alloc_register()?;
+                self.emit(InstructionBuilder::mov(reg, var));
+                Ok(reg)
+            }
+            Expr::Call { .. } => Err(CodegenError::UnsupportedExpression("call".to_string())),
+            Expr::Match { .. } => Err(CodegenError::UnsupportedExpression("match".to_string())),
+            Expr::Array(_) => Err(CodegenError::UnsupportedExpression("array".to_string())),
+            Expr::Map(_) => Err(CodegenError::UnsupportedExpression("map".to_string())),
+            Expr::Block(_) => Err(CodegenError::UnsupportedExpression("block".to_string())),
+        }
+    }
+
+    /// Compile a statement, discarding the value of expression statements
+    pub fn compile_stmt(&mut self, stmt: &Stmt) -> CodegenResult<()> {
+        match stmt {
+            Stmt::Expr(expr) => {
+                let reg = self.compile_expr(expr)?;
+                self.free_register(reg);
+                Ok(())
+            }
+            Stmt::If {
+                condition,
+                then_branch,
+                elif_branches,
+                else_branch,
+            } => {
+                let branches: Vec<(&Expr, &Vec<Stmt>)> = std::iter::once((condition, then_branch))
+                    .chain(elif_branches.iter().map(|(cond, body)| (cond, body)))
+                    .collect();
+                let mut exit_jumps = Vec::new();
+
+                for (index, (cond, body)) in branches.iter().enumerate() {
+                    // BZ skips the body, landing on the next condition or the else block
+                    let reg = self.compile_expr(cond)?;
+                    let skip = self.emit_placeholder();
+                    self.free_register(reg);
+                    self.compile_block(body)?;
+
+                    // The last body only needs to jump if there is an else block to skip
+                    if index + 1 < branches.len() || else_branch.is_some() {
+                        exit_jumps.push(self.emit_placeholder());
+                    }
+                    let next = self.code.len();
+                    self.patch_branch(skip, InstructionBuilder::branch_zero(reg, 0), next)?;
+                }
+
+                if let Some(body) = else_branch {
+                    self.compile_block(body)?;
+                }
+
+                let end = self.code.len();
+                for jump in exit_jumps {
+                    self.code[jump] = InstructionBuilder::jump(address_of(end));
+                }
+                Ok(())
+            }
+            Stmt::While { condition, body } => {
+                let start = self.code.len();
+                let reg = self.compile_expr(condition)?;
+                let exit = self.emit_placeholder();
+                self.free_register(reg);
+                self.compile_block(body)?;
+                self.emit(InstructionBuilder::jump(address_of(start)));
+
+                let end = self.code.len();
+                self.patch_branch(exit, InstructionBuilder::branch_zero(reg, 0), end)
+            }
+            Stmt::Return(_) => Err(CodegenError::UnsupportedStatement("return".to_string())),
+            Stmt::Func(_) => Err(CodegenError::UnsupportedStatement("func".to_string())),
+        }
+    }
+
+    /// Take the lowest free register, spilling the oldest temporary if there is none
+    pub fn alloc_register(&mut self) -> CodegenResult<Register> {
+        self.registers.alloc(&mut self.code)
+    }
+
+    /// Return a register to the free stack
+    ///
+    /// Temporaries must be freed in reverse allocation order so spilled
+    /// values come back off the stack in the right place.
+    pub fn free_register(&mut self, reg: Register) {
+        self.registers.free(reg, &mut self.code);
+    }
+
+    fn compile_literal(&mut self, literal: &Literal) -> CodegenResult<Register> {
+        let value = match literal {
+            Literal::Integer(value) => *value,
+            Literal::Bool(value) => *value as i64,
+            Literal::Character(c) => *c as i64,
+            Literal::Float(_) => return Err(CodegenError::UnsupportedExpression("float".to_string())),
+            Literal::String(_) => return Err(CodegenError::UnsupportedExpression("string".to_string())),
+            Literal::Nil => return Err(CodegenError::UnsupportedExpression("nil".to_string())),
+        };
+        let value = i32::try_from(value).map_err(|_| CodegenError::IntegerOutOfRange(value))?;
+
+        let reg = self.alloc_register()?;
+        self.load_constant(reg, value)?;
+        Ok(reg)
+    }
+
+    /// Load a 32-bit constant; values outside the 16-bit LI range are
+    /// built from a high half shifted into place plus the sign-extended low half
+    fn load_constant(&mut self, reg: Register, value: i32) -> CodegenResult<()> {
+        if let Ok(small) = i16::try_from(value) {
+            self.emit(InstructionBuilder::load_immediate(reg, small as u16));
+            return Ok(());
+        }
+
+        // Allocating a temporary here could spill `reg` itself, so use the scratch register
+        let low = value as i16;
+        let high = value.wrapping_sub(low as i32) >> 16;
+        let tmp = scratch_register();
+        self.emit(InstructionBuilder::load_immediate(reg, high as u16));
+        self.emit(InstructionBuilder::load_immediate(tmp, 16));
+        self.emit(InstructionBuilder::shift_left(reg, reg, tmp));
+        self.emit(InstructionBuilder::load_immediate(tmp, low as u16));
+        self.emit(InstructionBuilder::add(reg, reg, tmp));
+        Ok(())
+    }
+
+    fn compile_block(&mut self, body: &[Stmt]) -> CodegenResult<()> {
+        for stmt in body {
+            self.compile_stmt(stmt)?;
+        }
+        Ok(())
+    }
+
+    /// `var = value` or a compound form such as `var += value`
+    fn compile_assignment(
+        &mut self,
+        op: Option<Token>,
+        target: &Expr,
+        value: &Expr,
+    ) -> CodegenResult<Register> {
+        let Expr::Identifier(name) = target else {
+            return Err(CodegenError::InvalidAssignmentTarget);
+        };
+
+        // A new variable claims its register before any temporaries are live,
+        // keeping temporaries strictly last-in first-out
+        let existing = self.variable(name);
+        let new_var = match (&op, existing) {
+            (None, None) => Some(self.registers.alloc_pinned()?),
+            _ => None,
+        };
+
+        let reg = self.compile_expr(value)?;
+        let var = match (op, existing) {
+            (None, Some(var)) => var,
+            (None, None) => {
+                let var = new_var.unwrap();
+                self.variables.insert(name.clone(), var);
+                var
+            }
+            (Some(op), Some(var)) => {
+                // reg = var <op> reg, keeping the operand order intact; the left
+                // operand goes in the scratch register so `reg` cannot be spilled
+                let lhs = scratch_register();
+                self.emit(InstructionBuilder::mov(lhs, var));
+                self.emit_binary(&op, lhs, reg)?;
+                self.emit(InstructionBuilder::mov(reg, lhs));
+                var
+            }
+            (Some(_), None) => return Err(CodegenError::UndefinedVariable(name.clone())),
+        };
+
+        self.emit(InstructionBuilder::mov(var, reg));
+        Ok(reg)
+    }
+
+    /// Emit `lhs = lhs <op> rhs`; `rhs` may be clobbered
+    fn emit_binary(&mut self, op: &Token, lhs: Register, rhs: Register) -> CodegenResult<()> {
+        let zero = Register::new(0).unwrap();
+        match op {
+            Token::Plus => self.emit(InstructionBuilder::add(lhs, lhs, rhs)),
+            Token::Minus => self.emit(InstructionBuilder::sub(lhs, lhs, rhs)),
+            Token::Multiply => self.emit(InstructionBuilder::mul(lhs, lhs, rhs)),
+            Token::Divide => self.emit(InstructionBuilder::div(lhs, lhs, rhs)),
+            Token::BitwiseAnd => self.emit(InstructionBuilder::and(lhs, lhs, rhs)),
+            Token::BitwiseOr => self.emit(InstructionBuilder::or(lhs, lhs, rhs)),
+            Token::BitwiseXor => self.emit(InstructionBuilder::xor(lhs, lhs, rhs)),
+            Token::LeftShift => self.emit(InstructionBuilder::shift_left(lhs, lhs, rhs)),
+            Token::RightShift => self.emit(InstructionBuilder::shift_right(lhs, lhs, rhs)),
+            Token::Less => self.emit(InstructionBuilder::set_less_than(lhs, lhs, rhs)),
+            Token::Greater => self.emit(InstructionBuilder::set_less_than(lhs, rhs, lhs)),
+            Token::LessEqual => {
+                self.emit(InstructionBuilder::set_less_than(lhs, rhs, lhs));
+                self.emit_flip(lhs, rhs);
+            }
+            Token::GreaterEqual => {
+                self.emit(InstructionBuilder::set_less_than(lhs, lhs, rhs));
+                self.emit_flip(lhs, rhs);
+            }
+            Token::NotEqual | Token::Equal => {
+                // (lhs - rhs) != 0, computed as 0 <u (lhs - rhs)
+                self.emit(InstructionBuilder::sub(lhs, lhs, rhs));
+                self.emit(InstructionBuilder::set_less_than_unsigned(lhs, zero, lhs));
+                if *op == Token::Equal {
+                    self.emit_flip(lhs, rhs);
+                }
+            }
+            other => return Err(CodegenError::UnsupportedOperator(other.clone())),
+        }
+        Ok(())
+    }
+
+    /// Turn a 0/1 boolean in `reg` into its negation, using `scratch` for the constant
+    fn emit_flip(&mut self, reg: Register, scratch: Register) {
+        self.emit(InstructionBuilder::load_immediate(scratch, 1));
+        self.emit(InstructionBuilder::xor(reg, reg, scratch));
+    }
+
+    /// Reserve a slot for a branch or jump whose target is not known yet
+    fn emit_placeholder(&mut self) -> usize {
+        self.emit(InstructionBuilder::nop());
+        self.code.len() - 1
+    }
+
+    /// Fill in the branch at index `at` so that it lands on index `target`
+    fn patch_branch(&mut self, at: usize, branch: InstructionType, target: usize) -> CodegenResult<()> {
+        if let InstructionType::BType { opcode, rs, rt, .. } = branch {
+            self.code[at] = InstructionType::BType {
+                opcode,
+                rs,
+                rt,
+                offset: branch_offset(at, target)?,
+            };
+        }
+        Ok(())
+    }
+
+    fn emit(&mut self, instr: InstructionType) {
+        self.code.push(instr);
+    }
+}
+
+/// Map `=` to `None` and compound assignments to their arithmetic operator
+fn assignment_operator(op: &Token) -> Option<Option<Token>> {
+    match op {
+        Token::Assign => Some(None),
+        Token::PlusAssign => Some(Some(Token::Plus)),
+        Token::MinusAssign => Some(Some(Token::Minus)),
+        Token::MultiplyAssign => Some(Some(Token::Multiply)),
+        Token::DivideAssign => Some(Some(Token::Divide)),
+        _ => None,
+    }
+}
+
+/// Byte offset of a branch at index `from` to index `to`, relative to the
+/// instruction after the branch (see `execute_btype`)
+fn branch_offset(from: usize, to: usize) -> CodegenResult<u16> {
+    let offset = (to as i64 - (from as i64 + 1)) * 4;
+    i16::try_from(offset)
+        .map(|offset| offset as u16)
+        .map_err(|_| CodegenError::BranchOutOfRange(offset))
+}
+
+/// Byte address of the instruction at `index`
+fn address_of(index: usize) -> u32 {
+    (index * 4) as u32
+}
+
+fn scratch_register() -> Register {
+    Register::new(SCRATCH_REGISTER).unwrap()
+}
+
+impl Default for Codegen {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler::encode::encode;
+    use crate::compiler::opcode::{ITypeOp, RTypeOp};
+    use crate::parser::Parser;
+    use crate::vm::VM;
+
+    fn compile(source: &str) -> Vec<InstructionType> {
+        let expr = Parser::new(source).parse_expression().unwrap();
+        compile_expr(&expr).unwrap()
+    }
+
+    fn eval(source: &str) -> i32 {
+        let mut program: Vec<u32> = compile(source).into_iter().map(encode).collect();
+        program.push(encode(InstructionBuilder::halt()));
+
+        let mut vm = VM::new_default();
+        vm.load_program(&program).unwrap();
+        vm.run().unwrap();
+        vm.get_registers().read(RESULT_REGISTER).unwrap()
+    }
+
+    /// Compile and run a program, returning the final value of `var`
+    fn run_program(source: &str, var: &str) -> i32 {
+        let (statements, errors) = Parser::new(source).parse_program();
+        assert!(errors.is_empty(), "{:?}", errors);
+
+        let mut codegen = Codegen::new();
+        for stmt in &statements {
+            codegen.compile_stmt(stmt).unwrap();
+        }
+        let reg = codegen.variable(var).unwrap();
+        let mut program: Vec<u32> = codegen.finish().into_iter().map(encode).collect();
+        program.push(encode(InstructionBuilder::halt()));
+
+        let mut vm = VM::new_default();
+        vm.load_program(&program).unwrap();
+        vm.run().unwrap();
+        vm.get_registers().read(reg.get_value()).unwrap()
+    }
+
+    fn opcodes(code: &[InstructionType]) -> Vec<String> {
+        code.iter()
+            .map(|instr| match instr {
+                InstructionType::RType { opcode, .. } => format!("{:?}", opcode),
+                InstructionType::IType { opcode, .. } => format!("{:?}", opcode),
+                other => format!("{:?}", other),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_precedence_order() {
+        let code = compile("1 + 2 * 3");
+        assert_eq!(opcodes(&code), vec!["LI", "LI", "LI", "MUL", "ADD"]);
+        assert_eq!(
+            code[4],
+            InstructionBuilder::add(
+                Register::new(1).unwrap(),
+                Register::new(1).unwrap(),
+                Register::new(2).unwrap()
+            )
+        );
+        assert!(matches!(code[0], InstructionType::IType { opcode: ITypeOp::LI, .. }));
+        assert!(matches!(code[3], InstructionType::RType { opcode: RTypeOp::MUL, .. }));
+    }
+
+    #[test]
+    fn test_compiled_expression_runs_on_vm() {
+        assert_eq!(eval("1 + 2 * 3"), 7);
+        assert_eq!(eval("(1 + 2) * 3"), 9);
+        assert_eq!(eval("20 / 4 - 7"), -2);
+        assert_eq!(eval("-(3 - 10) * 2"), 14);
+        assert_eq!(eval("6 & 3 | 8"), 10);
+        assert_eq!(eval("1 << 4 >> 2"), 4);
+    }
+
+    #[test]
+    fn test_large_constants() {
+        assert_eq!(eval("100000"), 100000);
+        assert_eq!(eval("-100000 + 1"), -99999);
+        assert_eq!(eval("2147483647"), i32::MAX);
+        assert_eq!(eval("40000 + 40000"), 80000);
+    }
+
+    #[test]
+    fn test_if_else_takes_either_branch() {
+        let source = "y = 0\nif x < 10 { y = 1 } else { y = 2 }";
+        assert_eq!(run_program(&format!("x = 3\n{}", source), "y"), 1);
+        assert_eq!(run_program(&format!("x = 12\n{}", source), "y"), 2);
+    }
+
+    #[test]
+    fn test_if_without_else_and_elif_chain() {
+        assert_eq!(run_program("y = 5\nif 1 > 2 { y = 9 }", "y"), 5);
+        assert_eq!(run_program("y = 5\nif 2 >= 2 { y += 4 }", "y"), 9);
+
+        let chain = "if x == 1 { y = 10 } elif x == 2 { y = 20 } elif x != 3 { y = 30 } else { y = 40 }";
+        for (x, expected) in [(1, 10), (2, 20), (3, 40), (7, 30)] {
+            assert_eq!(run_program(&format!("x = {}\ny = 0\n{}", x, chain), "y"), expected);
+        }
+    }
+
+    #[test]
+    fn test_comparisons_produce_booleans() {
+        for (source, expected) in [
+            ("3 < 4", 1), ("4 < 3", 0), ("3 <= 3", 1), ("4 <= 3", 0),
+            ("4 > 3", 1), ("3 >= 4", 0), ("5 == 5", 1), ("5 != 5", 0),
+            ("-1 < 1", 1),
+        ] {
+            assert_eq!(eval(source), expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_branch_offsets_are_relative_to_next_instruction() {
+        let code = compile_program(&Parser::new("if 1 { 2 }").parse_program().0).unwrap();
+        // LI, BZ, LI -- the branch skips exactly one instruction
+        assert_eq!(code.len(), 3);
+        assert!(matches!(code[1], InstructionType::BType { offset: 4, .. }));
+    }
+
+    #[test]
+    fn test_deep_expression_spills() {
+        let terms: Vec<String> = (1..=40).map(|n| n.to_string()).collect();
+        // Right-nested so every term stays live until the innermost addition
+        let source = terms.iter().rev().skip(1).fold(terms[39].clone(), |acc, term| {
+            format!("{} + ({})", term, acc)
+        });
+        let expr = Parser::new(&source).parse_expression().unwrap();
+
+        let mut codegen = Codegen::new();
+        let reg = codegen.compile_expr(&expr).unwrap();
+        assert_eq!(reg.get_value(), RESULT_REGISTER);
+        assert!(codegen.registers().spill_count() > 0);
+        assert_eq!(codegen.registers().spill_depth(), 0);
+
+        assert_eq!(eval(&source), (1..=40).sum::<i32>());
+    }
+
+    #[test]
+    fn test_operands_survive_register_pressure() {
+        // 26 variables pin all but one register, leaving a single temporary,
+        // so the right operand of every operation spills the left one
+        let mut source: String = (1..=22).map(|n| format!("v{} = {}\n", n, n)).collect();
+        source.push_str("r = 0\ns = 0\nt = 0\nbig = 0\n");
+        source.push_str("r = v1 + v2\ns = v7 - v3 * v2\nt = v4 < v5\nbig = 100000\nv6 -= v9\n");
+        let (statements, errors) = Parser::new(&source).parse_program();
+        assert!(errors.is_empty(), "{:?}", errors);
+
+        let mut codegen = Codegen::new();
+        for stmt in &statements {
+            codegen.compile_stmt(stmt).unwrap();
+        }
+        assert_eq!(codegen.registers().free_count(), 1);
+        assert!(codegen.registers().spill_count() > 0);
+        assert_eq!(codegen.registers().spill_depth(), 0);
+
+        let vars: Vec<u8> = ["r", "s", "t", "big", "v6"]
+            .iter()
+            .map(|name| codegen.variable(name).unwrap().get_value())
+            .collect();
+        let mut program: Vec<u32> = codegen.finish().into_iter().map(encode).collect();
+        program.push(encode(InstructionBuilder::halt()));
+
+        let mut vm = VM::new_default();
+        vm.load_program(&program).unwrap();
+        vm.run().unwrap();
+        let values: Vec<i32> = vars.iter().map(|&reg| vm.get_registers().read(reg).unwrap()).collect();
+        assert_eq!(values, vec![3, 1, 1, 100000, -3]);
+    }
+
+    #[test]
+    fn test_while_counts_up() {
+        assert_eq!(run_program("i = 0\nwhile i < 5 { i = i + 1 }", "i"), 5);
+        assert_eq!(run_program("i = 9\nwhile i < 5 { i = i + 1 }", "i"), 9);
+    }
+
+    #[test]
+    fn test_nested_loops() {
+        let source = "total = 0\ni = 0\nwhile i < 4 {\n  j = 0\n  while j < 3 {\n    total += i * j\n    j += 1\n  }\n  i += 1\n}";
+        assert_eq!(run_program(source, "total"), 18);
+    }
+
+    #[test]
+    fn test_loop_body_too_large_for_branch() {
+        let body = "i = i + 1\n".repeat(3000);
+        let source = format!("i = 0\nwhile i < 5 {{\n{}}}", body);
+        let (statements, errors) = Parser::new(&source).parse_program();
+        assert!(errors.is_empty());
+
+        assert!(matches!(
+            compile_program(&statements),
+            Err(CodegenError::BranchOutOfRange(offset)) if offset > i16::MAX as i64
+        ));
+    }
+
+    #[test]
+    fn test_unsupported_inputs() {
+        let expr = Parser::new("[1, 2] + 1").parse_expression().unwrap();
+        assert!(matches!(compile_expr(&expr), Err(CodegenError::UnsupportedExpression(_))));
+
+        let expr = Parser::new("x + 1").parse_expression().unwrap();
+        assert_eq!(compile_expr(&expr), Err(CodegenError::UndefinedVariable("x".to_string())));
+
+        let expr = Parser::new("5000000000").parse_expression().unwrap();
+        assert_eq!(compile_expr(&expr), Err(CodegenError::IntegerOutOfRange(5000000000)));
+    }
+}
@@ -1,28 +1,52 @@
 use crate::compiler::{
+    encode::{short_immediate, STYPE_RD_PRESENT, STYPE_RS_PRESENT},
     instruction_type::InstructionType,
     opcode::{BTypeOp, ITypeOp, JTypeOp, MTypeOp, NTypeOp, RTypeOp, STypeOp},
     register::Register,
 };
 
+/// Decode a raw instruction word into its canonical `InstructionType`
+///
+/// See `InstructionType::canonical` for how unused operand fields are normalised.
 pub fn decode(bits: u32) -> Result<InstructionType, String> {
     let opcode_byte = ((bits >> 24) & 0xFF) as u8;
 
-    match opcode_byte {
+    let instr = match opcode_byte {
         // R-Type instructions
-        0x10..=0x14 | 0x20..=0x23 => decode_rtype(bits, opcode_byte),
+        0x10..=0x2E | 0x90..=0x95 => decode_rtype(bits, opcode_byte),
         // I-Type instructions
-        0x30..=0x31 | 0x40..=0x41 => decode_itype(bits, opcode_byte),
+        0x30..=0x32 | 0x40..=0x45 => decode_itype(bits, opcode_byte),
         // B-Type instructions
-        0x50..=0x55 => decode_btype(bits, opcode_byte),
+        0x50..=0x5B => decode_btype(bits, opcode_byte),
         // J-Type instructions
         0x60..=0x62 => decode_jtype(bits, opcode_byte),
         // M-Type instructions
-        0x70..=0x73 => decode_mtype(bits, opcode_byte),
+        0x70..=0x7A => decode_mtype(bits, opcode_byte),
         // S-Type instructions
-        0x80..=0x82 => decode_stype(bits, opcode_byte),
+        0x80..=0x84 => decode_stype(bits, opcode_byte),
         // N-Type instructions
-        0x00..=0x01 => decode_ntype(bits, opcode_byte),
+        0x00..=0x02 => decode_ntype(bits, opcode_byte),
         _ => Err(format!("Invalid opcode: 0x{:02X}", opcode_byte)),
+    }?;
+
+    Ok(sign_extend_short_immediate(instr).canonical())
+}
+
+/// Sign-extend the low 14 bits of a short immediate, discarding the register bits above them
+fn sign_extend_short_immediate(instr: InstructionType) -> InstructionType {
+    if short_immediate(&instr).is_none() {
+        return instr;
+    }
+    let extend = |value: u16| (((value << 2) as i16) >> 2) as u16;
+
+    match instr {
+        InstructionType::IType { opcode, rd, rs, imm } => {
+            InstructionType::IType { opcode, rd, rs, imm: extend(imm) }
+        }
+        InstructionType::BType { opcode, rs, rt, offset } => {
+            InstructionType::BType { opcode, rs, rt, offset: extend(offset) }
+        }
+        _ => instr,
     }
 }
 
@@ -33,10 +57,38 @@ fn decode_rtype(bits: u32, opcode_byte: u8) -> Result<InstructionType, String> {
         0x12 => RTypeOp::MUL,
         0x13 => RTypeOp::DIV,
         0x14 => RTypeOp::MOV,
+        0x15 => RTypeOp::NEG,
+        0x16 => RTypeOp::ABS,
+        0x17 => RTypeOp::MULH,
+        0x18 => RTypeOp::MULHU,
+        0x19 => RTypeOp::DIVMOD,
+        0x1A => RTypeOp::FADD,
+        0x1B => RTypeOp::FSUB,
+        0x1C => RTypeOp::FMUL,
+        0x1D => RTypeOp::FDIV,
+        0x1E => RTypeOp::ITOF,
+        0x1F => RTypeOp::FTOI,
         0x20 => RTypeOp::AND,
         0x21 => RTypeOp::OR,
         0x22 => RTypeOp::XOR,
         0x23 => RTypeOp::NOT,
+        0x24 => RTypeOp::SLL,
+        0x25 => RTypeOp::SRL,
+        0x26 => RTypeOp::CMP,
+        0x27 => RTypeOp::SLT,
+        0x28 => RTypeOp::SLTU,
+        0x29 => RTypeOp::MIN,
+        0x2A => RTypeOp::MAX,
+        0x2B => RTypeOp::MINU,
+        0x2C => RTypeOp::MAXU,
+        0x2D => RTypeOp::ROL,
+        0x2E => RTypeOp::ROR,
+        0x90 => RTypeOp::CLZ,
+        0x91 => RTypeOp::POPCNT,
+        0x92 => RTypeOp::SEB,
+        0x93 => RTypeOp::SEH,
+        0x94 => RTypeOp::ZEB,
+        0x95 => RTypeOp::ZEH,
         _ => unreachable!(), // Already validated by range
     };
 
@@ -51,8 +103,13 @@ fn decode_itype(bits: u32, opcode_byte: u8) -> Result<InstructionType, String> {
     let opcode = match opcode_byte {
         0x30 => ITypeOp::LI,
         0x31 => ITypeOp::ADDI,
+        0x32 => ITypeOp::ENTER,
         0x40 => ITypeOp::LOAD,
         0x41 => ITypeOp::STORE,
+        0x42 => ITypeOp::LB,
+        0x43 => ITypeOp::SB,
+        0x44 => ITypeOp::LH,
+        0x45 => ITypeOp::SH,
         _ => unreachable!(), // Already validated by range
     };
 
@@ -76,6 +133,12 @@ fn decode_btype(bits: u32, opcode_byte: u8) -> Result<InstructionType, String> {
         0x53 => BTypeOp::BGE,
         0x54 => BTypeOp::BZ,
         0x55 => BTypeOp::BNZ,
+        0x56 => BTypeOp::BZF,
+        0x57 => BTypeOp::BNZF,
+        0x58 => BTypeOp::BLTF,
+        0x59 => BTypeOp::BGEF,
+        0x5A => BTypeOp::BLTU,
+        0x5B => BTypeOp::BGEU,
         _ => unreachable!(), // Already validated by range
     };
 
@@ -99,7 +162,7 @@ fn decode_jtype(bits: u32, opcode_byte: u8) -> Result<InstructionType, String> {
         _ => unreachable!(), // Already validated by range
     };
 
-    let addr = (bits & 0xFFFF) as u16;
+    let addr = bits & 0xFFFFFF;
 
     Ok(InstructionType::JType { opcode, addr })
 }
@@ -110,6 +173,13 @@ fn decode_mtype(bits: u32, opcode_byte: u8) -> Result<InstructionType, String> {
         0x71 => MTypeOp::FREE,
         0x72 => MTypeOp::ALOAD,
         0x73 => MTypeOp::ASTORE,
+        0x74 => MTypeOp::PUSH,
+        0x75 => MTypeOp::POP,
+        0x76 => MTypeOp::MEMCPY,
+        0x77 => MTypeOp::MEMSET,
+        0x78 => MTypeOp::JR,
+        0x79 => MTypeOp::CALLR,
+        0x7A => MTypeOp::CAS,
         _ => unreachable!(), // Already validated by range
     };
 
@@ -125,11 +195,23 @@ fn decode_stype(bits: u32, opcode_byte: u8) -> Result<InstructionType, String> {
         0x80 => STypeOp::PRINT,
         0x81 => STypeOp::READ,
         0x82 => STypeOp::SYSCALL,
+        0x83 => STypeOp::PUTC,
+        0x84 => STypeOp::PRINTX,
         _ => unreachable!(), // Already validated by range
     };
 
-    let rd = Some(Register::new(((bits >> 19) & 0x1F) as u8)?);
-    let rs = Some(Register::new(((bits >> 14) & 0x1F) as u8)?);
+    // Operands are only present when their flag bit is set, so an absent
+    // register is not confused with R0
+    let rd = if bits & STYPE_RD_PRESENT != 0 {
+        Some(Register::new(((bits >> 19) & 0x1F) as u8)?)
+    } else {
+        None
+    };
+    let rs = if bits & STYPE_RS_PRESENT != 0 {
+        Some(Register::new(((bits >> 14) & 0x1F) as u8)?)
+    } else {
+        None
+    };
 
     Ok(InstructionType::SType { opcode, rd, rs })
 }
@@ -138,8 +220,169 @@ fn decode_ntype(_bits: u32, opcode_byte: u8) -> Result<InstructionType, String>
     let opcode = match opcode_byte {
         0x00 => NTypeOp::NOP,
         0x01 => NTypeOp::HALT,
+        0x02 => NTypeOp::LEAVE,
         _ => unreachable!(), // Already validated by range
     };
 
     Ok(InstructionType::NType { opcode })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler::encode::encode;
+    use crate::compiler::instruction_builder::InstructionBuilder;
+
+    /// One instance of every builder helper, with immediates at the edges of their range
+    fn every_builder_output() -> Vec<InstructionType> {
+        use crate::compiler::instruction_builder::registers::*;
+        type B = InstructionBuilder;
+
+        vec![
+            B::add(r1(), r2(), r3()),
+            B::sub(r4(), r5(), r6()),
+            B::mul(r7(), r8(), r9()),
+            B::mul_high(r7(), r8(), r9()),
+            B::mul_high_unsigned(r7(), r8(), r9()),
+            B::div(r10(), r11(), r1()),
+            B::divmod(r10(), r8(), r1()),
+            B::float_add(r1(), r4(), r3()),
+            B::float_sub(r1(), r4(), r3()),
+            B::float_mul(r1(), r4(), r3()),
+            B::float_div(r1(), r4(), r3()),
+            B::int_to_float(r1(), r4()),
+            B::float_to_int(r1(), r4()),
+            B::mov(r1(), r2()),
+            B::neg(r1(), r4()),
+            B::abs(r2(), r8()),
+            B::and(r1(), r2(), r3()),
+            B::or(r1(), r2(), r3()),
+            B::xor(r1(), r2(), r3()),
+            B::not(r5(), r6()),
+            B::shift_left(r1(), r2(), r3()),
+            B::shift_right(r1(), r2(), r3()),
+            B::rotate_left(r1(), r4(), r3()),
+            B::rotate_right(r1(), r4(), r3()),
+            B::count_leading_zeros(r1(), r4()),
+            B::pop_count(r1(), r4()),
+            B::sign_extend_byte(r1(), r4()),
+            B::sign_extend_half(r1(), r4()),
+            B::zero_extend_byte(r1(), r4()),
+            B::zero_extend_half(r1(), r4()),
+            B::compare(r3(), r4()),
+            B::set_less_than(r1(), r2(), r3()),
+            B::set_less_than_unsigned(r1(), r2(), r3()),
+            B::min(r1(), r4(), r3()),
+            B::max(r1(), r4(), r3()),
+            B::min_unsigned(r1(), r4(), r3()),
+            B::max_unsigned(r1(), r4(), r3()),
+            B::load_immediate(r1(), 0xFFFF),
+            B::load_immediate(r31(), 0x8000),
+            B::add_immediate(r1(), r31(), 0x1FFF),
+            B::add_immediate(r1(), r3(), -0x2000i16 as u16),
+            B::enter(0xFFFF),
+            B::load(r1(), sp(), -4i16 as u16),
+            B::store(r1(), fp(), -8i16 as u16),
+            B::load_byte(r1(), r3(), 0x1FFF),
+            B::store_byte(r1(), r31(), -1i16 as u16),
+            B::load_halfword(r1(), r1(), 2),
+            B::store_halfword(r1(), r7(), -2i16 as u16),
+            B::branch_equal(r1(), r2(), -12i16 as u16),
+            B::branch_not_equal(r1(), r3(), 0x1FFC),
+            B::branch_less_than(r1(), r31(), -0x2000i16 as u16),
+            B::branch_greater_equal(r5(), r1(), 0x10),
+            B::branch_less_than_unsigned(r1(), r7(), -4i16 as u16),
+            B::branch_greater_equal_unsigned(r1(), r2(), 0x10),
+            B::branch_zero(r3(), 0xFFFC),
+            B::branch_not_zero(r31(), 0x7FFC),
+            B::branch_flags_zero(0xFFFC),
+            B::branch_flags_not_zero(0x7FFC),
+            B::branch_flags_less_than(8),
+            B::branch_flags_greater_equal(8),
+            B::jump(0x40),
+            B::call(0x80),
+            B::ret(),
+            B::allocate(r1(), r2()),
+            B::free(r1()),
+            B::array_load(r1(), r2(), r3()),
+            B::array_store(r1(), r2(), r3()),
+            B::push(r4()),
+            B::pop(r5()),
+            B::mem_copy(r1(), r2(), r3()),
+            B::mem_set(r1(), r2(), r3()),
+            B::compare_and_swap(r1(), r4(), r3()),
+            B::jump_register(r5()),
+            B::call_register(r6()),
+            B::print(r1()),
+            B::print_hex(r2()),
+            B::read(r2()),
+            B::put_char(r3()),
+            B::syscall(None, Some(r1())),
+            B::syscall(Some(r2()), None),
+            B::syscall(Some(r0()), Some(r0())),
+            B::syscall(None, None),
+            B::nop(),
+            B::halt(),
+            B::leave(),
+        ]
+    }
+
+    #[test]
+    fn test_round_trip_every_builder_output() {
+        for instr in every_builder_output() {
+            assert_eq!(decode(encode(instr)).unwrap(), instr, "bits 0x{:08X}", encode(instr));
+        }
+    }
+
+    #[test]
+    fn test_short_immediates_are_range_checked() {
+        use crate::compiler::encode::try_encode;
+        use crate::compiler::instruction_builder::registers::*;
+        type B = InstructionBuilder;
+
+        // Both edges of the 14-bit range survive next to every register
+        for imm in [-0x2000i16, -1, 0, 0x1FFF] {
+            for reg in 0..32 {
+                let rs = Register::new(reg).unwrap();
+                let addi = B::add_immediate(r1(), rs, imm as u16);
+                assert_eq!(decode(encode(addi)).unwrap(), addi);
+                let blt = B::branch_less_than(r1(), rs, imm as u16);
+                assert_eq!(decode(encode(blt)).unwrap(), blt);
+            }
+        }
+
+        // Anything wider is rejected rather than spilling into the register field
+        assert!(try_encode(B::add_immediate(r1(), r2(), 0x2000)).is_err());
+        assert!(try_encode(B::load(r1(), sp(), -0x2001i16 as u16)).is_err());
+        assert!(try_encode(B::branch_equal(r1(), r2(), 0x4000)).is_err());
+        assert!(std::panic::catch_unwind(|| encode(B::store(r1(), r2(), 0x8000))).is_err());
+
+        // Forms without a second register keep all 16 bits
+        assert!(try_encode(B::load_immediate(r1(), 0x8000)).is_ok());
+        assert!(try_encode(B::branch_zero(r1(), 0x4000)).is_ok());
+    }
+
+    #[test]
+    fn test_stype_preserves_absent_operands() {
+        let print = decode(encode(InstructionBuilder::print(Register::new(0).unwrap()))).unwrap();
+        assert_eq!(
+            print,
+            InstructionType::SType {
+                opcode: STypeOp::PRINT,
+                rd: None,
+                rs: Some(Register::new(0).unwrap())
+            }
+        );
+    }
+
+    #[test]
+    fn test_jump_address_round_trip() {
+        for instr in [InstructionBuilder::jump(0x123456), InstructionBuilder::call(0xFFFFFF)] {
+            assert_eq!(decode(encode(instr)).unwrap(), instr);
+        }
+
+        // Addresses wider than 24 bits are truncated by the encoding
+        let bits = encode(InstructionBuilder::jump(0x1123456));
+        assert_eq!(bits, 0x60123456);
+    }
+}
@@ -0,0 +1,199 @@
+use crate::compiler::{
+    decode::decode,
+    instruction_type::InstructionType,
+    opcode::{BTypeOp, ITypeOp, JTypeOp, MTypeOp, RTypeOp, STypeOp},
+    register::Register,
+};
+
+/// Format a decoded instruction as MIPS-like assembly text, e.g. `ADD R3, R1, R2`
+pub fn disassemble(instr: &InstructionType) -> String {
+    match *instr {
+        InstructionType::RType { opcode, rd, rs, rt } => match opcode {
+            RTypeOp::MOV
+            | RTypeOp::NOT
+            | RTypeOp::NEG
+            | RTypeOp::ABS
+            | RTypeOp::CLZ
+            | RTypeOp::POPCNT
+            | RTypeOp::SEB
+            | RTypeOp::SEH
+            | RTypeOp::ZEB
+            | RTypeOp::ZEH => {
+                format!("{} {}, {}", opcode, reg(rd), reg(rs))
+            }
+            RTypeOp::CMP => format!("{} {}, {}", opcode, reg(rs), reg(rt)),
+            RTypeOp::FADD | RTypeOp::FSUB | RTypeOp::FMUL | RTypeOp::FDIV => {
+                format!("{} {}, {}, {}", opcode, freg(rd), freg(rs), freg(rt))
+            }
+            RTypeOp::ITOF => format!("{} {}, {}", opcode, freg(rd), reg(rs)),
+            RTypeOp::FTOI => format!("{} {}, {}", opcode, reg(rd), freg(rs)),
+            _ => format!("{} {}, {}, {}", opcode, reg(rd), reg(rs), reg(rt)),
+        },
+        InstructionType::IType { opcode, rd, rs, imm } => match opcode {
+            ITypeOp::LI => format!("{} {}, {}", opcode, reg(rd), imm as i16),
+            ITypeOp::ENTER => format!("{} {}", opcode, imm),
+            _ => format!("{} {}, {}, {}", opcode, reg(rd), reg(rs), imm as i16),
+        },
+        InstructionType::BType { opcode, rs, rt, offset } => match opcode {
+            BTypeOp::BZ | BTypeOp::BNZ => {
+                format!("{} {}, {:+}", opcode, reg(rs), offset as i16)
+            }
+            BTypeOp::BZF | BTypeOp::BNZF | BTypeOp::BLTF | BTypeOp::BGEF => {
+                format!("{} {:+}", opcode, offset as i16)
+            }
+            _ => format!("{} {}, {}, {:+}", opcode, reg(rs), reg(rt), offset as i16),
+        },
+        InstructionType::JType { opcode, addr } => match opcode {
+            JTypeOp::RET => format!("{}", opcode),
+            _ => format!("{} 0x{:X}", opcode, addr),
+        },
+        InstructionType::MType { opcode, rd, rs, rt } => match opcode {
+            MTypeOp::ALLOC => format!("{} {}, {}", opcode, reg(rd), reg(rs)),
+            MTypeOp::FREE | MTypeOp::PUSH | MTypeOp::JR | MTypeOp::CALLR => format!("{} {}", opcode, reg(rs)),
+            MTypeOp::POP => format!("{} {}", opcode, reg(rd)),
+            _ => format!("{} {}, {}, {}", opcode, reg(rd), reg(rs), reg(rt)),
+        },
+        InstructionType::SType { opcode, rd, rs } => {
+            let operands: Vec<String> = match opcode {
+                STypeOp::PRINT | STypeOp::PRINTX | STypeOp::PUTC => rs.into_iter().map(reg).collect(),
+                STypeOp::READ => rd.into_iter().map(reg).collect(),
+                STypeOp::SYSCALL => rd.into_iter().chain(rs).map(reg).collect(),
+            };
+
+            if operands.is_empty() {
+                format!("{}", opcode)
+            } else {
+                format!("{} {}", opcode, operands.join(", "))
+            }
+        }
+        InstructionType::NType { opcode } => format!("{}", opcode),
+    }
+}
+
+/// Decode a raw instruction word and format it as assembly text
+pub fn disassemble_word(bits: u32) -> Result<String, String> {
+    decode(bits).map(|instr| disassemble(&instr))
+}
+
+/// Produce a listing of a whole program, one `address: word  mnemonic` line per word
+///
+/// Words that fail to decode are rendered as `.word 0x........` so the listing
+/// always covers the full program.
+pub fn disassemble_program(bytecode: &[u32]) -> String {
+    let mut output = String::new();
+
+    for (i, &word) in bytecode.iter().enumerate() {
+        let text = disassemble_word(word).unwrap_or_else(|_| format!(".word 0x{:08X}", word));
+        output.push_str(&format!("0x{:04X}: 0x{:08X}  {}\n", i * 4, word, text));
+    }
+
+    output
+}
+
+fn reg(register: Register) -> String {
+    format!("R{}", register.get_value())
+}
+
+fn freg(register: Register) -> String {
+    format!("F{}", register.get_value())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler::encode::encode;
+    use crate::compiler::instruction_builder::{InstructionBuilder, registers::*};
+
+    #[test]
+    fn test_rtype_disassembly() {
+        assert_eq!(disassemble(&InstructionBuilder::add(r3(), r1(), r2())), "ADD R3, R1, R2");
+        assert_eq!(disassemble(&InstructionBuilder::mov(r4(), r5())), "MOV R4, R5");
+        assert_eq!(disassemble(&InstructionBuilder::neg(r4(), r5())), "NEG R4, R5");
+        assert_eq!(disassemble(&InstructionBuilder::float_add(r3(), r1(), r2())), "FADD F3, F1, F2");
+        assert_eq!(disassemble(&InstructionBuilder::int_to_float(r1(), r2())), "ITOF F1, R2");
+        assert_eq!(disassemble(&InstructionBuilder::float_to_int(r1(), r2())), "FTOI R1, F2");
+        assert_eq!(disassemble(&InstructionBuilder::compare(r1(), r2())), "CMP R1, R2");
+    }
+
+    #[test]
+    fn test_itype_disassembly() {
+        assert_eq!(disassemble(&InstructionBuilder::load_immediate(r1(), 42)), "LI R1, 42");
+        assert_eq!(
+            disassemble(&InstructionBuilder::add_immediate(r1(), r2(), -3i16 as u16)),
+            "ADDI R1, R2, -3"
+        );
+        assert_eq!(disassemble(&InstructionBuilder::load(r5(), r2(), 4)), "LOAD R5, R2, 4");
+    }
+
+    #[test]
+    fn test_btype_disassembly() {
+        assert_eq!(
+            disassemble(&InstructionBuilder::branch_equal(r1(), r2(), 8)),
+            "BEQ R1, R2, +8"
+        );
+        assert_eq!(
+            disassemble(&InstructionBuilder::branch_not_zero(r3(), -12i16 as u16)),
+            "BNZ R3, -12"
+        );
+        assert_eq!(disassemble(&InstructionBuilder::branch_flags_less_than(4)), "BLTF +4");
+    }
+
+    #[test]
+    fn test_jtype_disassembly() {
+        assert_eq!(disassemble(&InstructionBuilder::jump(0x14)), "JMP 0x14");
+        assert_eq!(disassemble(&InstructionBuilder::call(0x123456)), "CALL 0x123456");
+        assert_eq!(disassemble(&InstructionBuilder::ret()), "RET");
+    }
+
+    #[test]
+    fn test_mtype_disassembly() {
+        assert_eq!(disassemble(&InstructionBuilder::allocate(r2(), r1())), "ALLOC R2, R1");
+        assert_eq!(disassemble(&InstructionBuilder::free(r2())), "FREE R2");
+        assert_eq!(
+            disassemble(&InstructionBuilder::array_load(r1(), r2(), r3())),
+            "ALOAD R1, R2, R3"
+        );
+        assert_eq!(disassemble(&InstructionBuilder::push(r7())), "PUSH R7");
+        assert_eq!(disassemble(&InstructionBuilder::pop(r8())), "POP R8");
+    }
+
+    #[test]
+    fn test_stype_and_ntype_disassembly() {
+        assert_eq!(disassemble(&InstructionBuilder::print(r1())), "PRINT R1");
+        assert_eq!(disassemble(&InstructionBuilder::print_hex(r2())), "PRINTX R2");
+        assert_eq!(disassemble(&InstructionBuilder::jump_register(r7())), "JR R7");
+        assert_eq!(disassemble(&InstructionBuilder::read(r2())), "READ R2");
+        assert_eq!(disassemble(&InstructionBuilder::nop()), "NOP");
+        assert_eq!(disassemble(&InstructionBuilder::halt()), "HALT");
+        assert_eq!(disassemble(&InstructionBuilder::enter(16)), "ENTER 16");
+        assert_eq!(disassemble(&InstructionBuilder::leave()), "LEAVE");
+    }
+
+    #[test]
+    fn test_disassemble_word() {
+        let bits = encode(InstructionBuilder::add(r3(), r1(), r2()));
+        assert_eq!(disassemble_word(bits).unwrap(), "ADD R3, R1, R2");
+        assert!(disassemble_word(0xFF000000).is_err());
+    }
+
+    #[test]
+    fn test_disassemble_program() {
+        let program = vec![
+            encode(InstructionBuilder::load_immediate(r1(), 66)),
+            0xFF000000,
+            encode(InstructionBuilder::halt()),
+        ];
+
+        let listing = disassemble_program(&program);
+        let lines: Vec<&str> = listing.lines().collect();
+        assert_eq!(lines.len(), 3);
+
+        for (i, line) in lines.iter().enumerate() {
+            assert!(line.starts_with(&format!("0x{:04X}: ", i * 4)));
+        }
+
+        assert!(lines[0].ends_with("  LI R1, 66"));
+        assert_eq!(lines[1], "0x0004: 0xFF000000  .word 0xFF000000");
+        assert!(lines[2].ends_with("  HALT"));
+    }
+}
@@ -1,7 +1,56 @@
-use crate::compiler::instruction_type::InstructionType;
+use crate::compiler::{
+    instruction_type::InstructionType,
+    opcode::{BTypeOp, ITypeOp},
+};
+use std::ops::RangeInclusive;
 
-pub fn encode(instruction: InstructionType) -> u32 {
-    match instruction {
+/// S-Type flag marking that the `rd` field (bits 19-23) holds a register
+pub const STYPE_RD_PRESENT: u32 = 1 << 13;
+
+/// S-Type flag marking that the `rs` field (bits 14-18) holds a register
+pub const STYPE_RS_PRESENT: u32 = 1 << 12;
+
+/// Values a short immediate can hold
+///
+/// I- and B-Type instructions that read their second register (bits 14-18) only
+/// have bits 0-13 left for the immediate or offset, which is sign-extended on decode.
+pub const SHORT_IMMEDIATE_RANGE: RangeInclusive<i16> = -0x2000..=0x1FFF;
+
+/// The immediate or offset of `instruction` if it is stored in the short 14-bit form
+///
+/// `LI`, `ENTER` and the single-register and flag branches keep the full 16 bits.
+pub fn short_immediate(instruction: &InstructionType) -> Option<i16> {
+    match *instruction {
+        InstructionType::IType { opcode: ITypeOp::LI | ITypeOp::ENTER, .. } => None,
+        InstructionType::IType { imm, .. } => Some(imm as i16),
+        InstructionType::BType {
+            opcode: BTypeOp::BEQ | BTypeOp::BNE | BTypeOp::BLT | BTypeOp::BGE | BTypeOp::BLTU | BTypeOp::BGEU,
+            offset,
+            ..
+        } => Some(offset as i16),
+        _ => None,
+    }
+}
+
+/// Encode an instruction, rejecting operands that don't fit the instruction word
+///
+/// The only operands that can fail are short immediates outside `SHORT_IMMEDIATE_RANGE`.
+pub fn try_encode(instruction: InstructionType) -> Result<u32, String> {
+    let instruction = instruction.canonical();
+    if let Some(imm) = short_immediate(&instruction)
+        && !SHORT_IMMEDIATE_RANGE.contains(&imm)
+    {
+        return Err(format!(
+            "{} immediate {} does not fit in 14 signed bits",
+            instruction.mnemonic(),
+            imm
+        ));
+    }
+
+    let short = short_immediate(&instruction).is_some();
+    let immediate = |value: u16| if short { value as u32 & 0x3FFF } else { value as u32 };
+
+    Ok(match instruction {
         InstructionType::RType { opcode, rd, rs, rt } => {
             ((opcode as u8 as u32) << 24)
                 | ((rd.get_value() as u32) << 19)
@@ -17,7 +66,7 @@ pub fn encode(instruction: InstructionType) -> u32 {
             ((opcode as u8 as u32) << 24)
                 | ((rd.get_value() as u32) << 19)
                 | ((rs.get_value() as u32) << 14)
-                | (imm as u32)
+                | immediate(imm)
         }
         InstructionType::BType {
             opcode,
@@ -28,9 +77,9 @@ pub fn encode(instruction: InstructionType) -> u32 {
             ((opcode as u8 as u32) << 24)
                 | ((rs.get_value() as u32) << 19)
                 | ((rt.get_value() as u32) << 14)
-                | (offset as u32)
+                | immediate(offset)
         }
-        InstructionType::JType { opcode, addr } => ((opcode as u8 as u32) << 24) | (addr as u32),
+        InstructionType::JType { opcode, addr } => ((opcode as u8 as u32) << 24) | (addr & 0xFFFFFF),
         InstructionType::MType { opcode, rd, rs, rt } => {
             ((opcode as u8 as u32) << 24)
                 | ((rd.get_value() as u32) << 19)
@@ -41,7 +90,20 @@ pub fn encode(instruction: InstructionType) -> u32 {
             ((opcode as u8 as u32) << 24)
                 | (rd.map_or(0, |r| r.get_value() as u32) << 19)
                 | (rs.map_or(0, |r| r.get_value() as u32) << 14)
+                | rd.map_or(0, |_| STYPE_RD_PRESENT)
+                | rs.map_or(0, |_| STYPE_RS_PRESENT)
         }
         InstructionType::NType { opcode } => (opcode as u8 as u32) << 24,
-    }
+    })
+}
+
+/// Encode an instruction
+///
+/// Unused operand fields are encoded as `canonical` leaves them.
+///
+/// # Panics
+///
+/// If the instruction can't be encoded; see `try_encode`.
+pub fn encode(instruction: InstructionType) -> u32 {
+    try_encode(instruction).unwrap_or_else(|err| panic!("{}", err))
 }
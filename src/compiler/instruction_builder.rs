@@ -34,6 +34,22 @@ impl InstructionBuilder {
         }
     }
     
+    /// Create a MULH instruction: rd = high 32 bits of rs * rt (signed)
+    pub fn mul_high(rd: Register, rs: Register, rt: Register) -> InstructionType {
+        InstructionType::RType {
+            opcode: RTypeOp::MULH,
+            rd, rs, rt
+        }
+    }
+    
+    /// Create a MULHU instruction: rd = high 32 bits of rs * rt (unsigned)
+    pub fn mul_high_unsigned(rd: Register, rs: Register, rt: Register) -> InstructionType {
+        InstructionType::RType {
+            opcode: RTypeOp::MULHU,
+            rd, rs, rt
+        }
+    }
+    
     /// Create a DIV instruction: rd = rs / rt
     pub fn div(rd: Register, rs: Register, rt: Register) -> InstructionType {
         InstructionType::RType {
@@ -42,6 +58,66 @@ impl InstructionBuilder {
         }
     }
     
+    /// Create a DIVMOD instruction: rd = rs / rt, rd+1 = rs % rt
+    ///
+    /// The remainder lands in the register numbered one above `rd`, so `rd` must not be R31.
+    pub fn divmod(rd: Register, rs: Register, rt: Register) -> InstructionType {
+        InstructionType::RType {
+            opcode: RTypeOp::DIVMOD,
+            rd, rs, rt
+        }
+    }
+    
+    /// Create an FADD instruction: fd = fs + ft (float registers)
+    pub fn float_add(fd: Register, fs: Register, ft: Register) -> InstructionType {
+        InstructionType::RType {
+            opcode: RTypeOp::FADD,
+            rd: fd, rs: fs, rt: ft
+        }
+    }
+    
+    /// Create an FSUB instruction: fd = fs - ft (float registers)
+    pub fn float_sub(fd: Register, fs: Register, ft: Register) -> InstructionType {
+        InstructionType::RType {
+            opcode: RTypeOp::FSUB,
+            rd: fd, rs: fs, rt: ft
+        }
+    }
+    
+    /// Create an FMUL instruction: fd = fs * ft (float registers)
+    pub fn float_mul(fd: Register, fs: Register, ft: Register) -> InstructionType {
+        InstructionType::RType {
+            opcode: RTypeOp::FMUL,
+            rd: fd, rs: fs, rt: ft
+        }
+    }
+    
+    /// Create an FDIV instruction: fd = fs / ft (float registers)
+    pub fn float_div(fd: Register, fs: Register, ft: Register) -> InstructionType {
+        InstructionType::RType {
+            opcode: RTypeOp::FDIV,
+            rd: fd, rs: fs, rt: ft
+        }
+    }
+    
+    /// Create an ITOF instruction: fd = rs as f32 (float destination, integer source)
+    pub fn int_to_float(fd: Register, rs: Register) -> InstructionType {
+        let zero_reg = Register::new(0).unwrap(); // Use register 0 as dummy
+        InstructionType::RType {
+            opcode: RTypeOp::ITOF,
+            rd: fd, rs, rt: zero_reg
+        }
+    }
+    
+    /// Create an FTOI instruction: rd = fs as i32 (integer destination, float source)
+    pub fn float_to_int(rd: Register, fs: Register) -> InstructionType {
+        let zero_reg = Register::new(0).unwrap(); // Use register 0 as dummy
+        InstructionType::RType {
+            opcode: RTypeOp::FTOI,
+            rd, rs: fs, rt: zero_reg
+        }
+    }
+    
     /// Create a MOV instruction: rd = rs (rt is ignored)
     pub fn mov(rd: Register, rs: Register) -> InstructionType {
         let zero_reg = Register::new(0).unwrap(); // Use register 0 as dummy
@@ -51,6 +127,24 @@ impl InstructionBuilder {
         }
     }
     
+    /// Create a NEG instruction: rd = -rs (rt is ignored)
+    pub fn neg(rd: Register, rs: Register) -> InstructionType {
+        let zero_reg = Register::new(0).unwrap(); // Use register 0 as dummy
+        InstructionType::RType {
+            opcode: RTypeOp::NEG,
+            rd, rs, rt: zero_reg
+        }
+    }
+    
+    /// Create an ABS instruction: rd = |rs| (rt is ignored)
+    pub fn abs(rd: Register, rs: Register) -> InstructionType {
+        let zero_reg = Register::new(0).unwrap(); // Use register 0 as dummy
+        InstructionType::RType {
+            opcode: RTypeOp::ABS,
+            rd, rs, rt: zero_reg
+        }
+    }
+    
     /// Create an AND instruction: rd = rs & rt
     pub fn and(rd: Register, rs: Register, rt: Register) -> InstructionType {
         InstructionType::RType {
@@ -84,6 +178,149 @@ impl InstructionBuilder {
         }
     }
     
+    /// Create a Shift Left Logical instruction: rd = rs << rt
+    pub fn shift_left(rd: Register, rs: Register, rt: Register) -> InstructionType {
+        InstructionType::RType {
+            opcode: RTypeOp::SLL,
+            rd, rs, rt
+        }
+    }
+    
+    /// Create a Shift Right Logical instruction: rd = rs >> rt (zero-filling)
+    pub fn shift_right(rd: Register, rs: Register, rt: Register) -> InstructionType {
+        InstructionType::RType {
+            opcode: RTypeOp::SRL,
+            rd, rs, rt
+        }
+    }
+    
+    /// Create a Compare instruction: flags = compare(rs - rt)
+    pub fn compare(rs: Register, rt: Register) -> InstructionType {
+        let zero_reg = Register::new(0).unwrap(); // Use register 0 as dummy
+        InstructionType::RType {
+            opcode: RTypeOp::CMP,
+            rd: zero_reg, rs, rt
+        }
+    }
+    
+    /// Create a Rotate Left instruction: rd = rs rotated left by rt
+    pub fn rotate_left(rd: Register, rs: Register, rt: Register) -> InstructionType {
+        InstructionType::RType {
+            opcode: RTypeOp::ROL,
+            rd, rs, rt
+        }
+    }
+    
+    /// Create a Rotate Right instruction: rd = rs rotated right by rt
+    pub fn rotate_right(rd: Register, rs: Register, rt: Register) -> InstructionType {
+        InstructionType::RType {
+            opcode: RTypeOp::ROR,
+            rd, rs, rt
+        }
+    }
+    
+    /// Create a Count Leading Zeros instruction: rd = leading zero bits of rs (rt is ignored)
+    pub fn count_leading_zeros(rd: Register, rs: Register) -> InstructionType {
+        let zero_reg = Register::new(0).unwrap(); // Use register 0 as dummy
+        InstructionType::RType {
+            opcode: RTypeOp::CLZ,
+            rd, rs, rt: zero_reg
+        }
+    }
+    
+    /// Create a Population Count instruction: rd = number of set bits in rs (rt is ignored)
+    pub fn pop_count(rd: Register, rs: Register) -> InstructionType {
+        let zero_reg = Register::new(0).unwrap(); // Use register 0 as dummy
+        InstructionType::RType {
+            opcode: RTypeOp::POPCNT,
+            rd, rs, rt: zero_reg
+        }
+    }
+    
+    /// Create a Sign-Extend Byte instruction: rd = sign_extend(low byte of rs) (rt is ignored)
+    pub fn sign_extend_byte(rd: Register, rs: Register) -> InstructionType {
+        let zero_reg = Register::new(0).unwrap(); // Use register 0 as dummy
+        InstructionType::RType {
+            opcode: RTypeOp::SEB,
+            rd, rs, rt: zero_reg
+        }
+    }
+    
+    /// Create a Sign-Extend Halfword instruction: rd = sign_extend(low halfword of rs) (rt is ignored)
+    pub fn sign_extend_half(rd: Register, rs: Register) -> InstructionType {
+        let zero_reg = Register::new(0).unwrap(); // Use register 0 as dummy
+        InstructionType::RType {
+            opcode: RTypeOp::SEH,
+            rd, rs, rt: zero_reg
+        }
+    }
+    
+    /// Create a Zero-Extend Byte instruction: rd = zero_extend(low byte of rs) (rt is ignored)
+    pub fn zero_extend_byte(rd: Register, rs: Register) -> InstructionType {
+        let zero_reg = Register::new(0).unwrap(); // Use register 0 as dummy
+        InstructionType::RType {
+            opcode: RTypeOp::ZEB,
+            rd, rs, rt: zero_reg
+        }
+    }
+    
+    /// Create a Zero-Extend Halfword instruction: rd = zero_extend(low halfword of rs) (rt is ignored)
+    pub fn zero_extend_half(rd: Register, rs: Register) -> InstructionType {
+        let zero_reg = Register::new(0).unwrap(); // Use register 0 as dummy
+        InstructionType::RType {
+            opcode: RTypeOp::ZEH,
+            rd, rs, rt: zero_reg
+        }
+    }
+    
+    /// Create a Set on Less Than instruction: rd = (rs < rt) ? 1 : 0 (signed)
+    pub fn set_less_than(rd: Register, rs: Register, rt: Register) -> InstructionType {
+        InstructionType::RType {
+            opcode: RTypeOp::SLT,
+            rd, rs, rt
+        }
+    }
+    
+    /// Create a Set on Less Than Unsigned instruction: rd = (rs < rt) ? 1 : 0 (unsigned)
+    pub fn set_less_than_unsigned(rd: Register, rs: Register, rt: Register) -> InstructionType {
+        InstructionType::RType {
+            opcode: RTypeOp::SLTU,
+            rd, rs, rt
+        }
+    }
+    
+    /// Create a MIN instruction: rd = min(rs, rt) (signed)
+    pub fn min(rd: Register, rs: Register, rt: Register) -> InstructionType {
+        InstructionType::RType {
+            opcode: RTypeOp::MIN,
+            rd, rs, rt
+        }
+    }
+    
+    /// Create a MAX instruction: rd = max(rs, rt) (signed)
+    pub fn max(rd: Register, rs: Register, rt: Register) -> InstructionType {
+        InstructionType::RType {
+            opcode: RTypeOp::MAX,
+            rd, rs, rt
+        }
+    }
+    
+    /// Create an Unsigned MIN instruction: rd = min(rs, rt) (unsigned)
+    pub fn min_unsigned(rd: Register, rs: Register, rt: Register) -> InstructionType {
+        InstructionType::RType {
+            opcode: RTypeOp::MINU,
+            rd, rs, rt
+        }
+    }
+    
+    /// Create an Unsigned MAX instruction: rd = max(rs, rt) (unsigned)
+    pub fn max_unsigned(rd: Register, rs: Register, rt: Register) -> InstructionType {
+        InstructionType::RType {
+            opcode: RTypeOp::MAXU,
+            rd, rs, rt
+        }
+    }
+    
     // ===== I-Type Instructions (Immediate operations) =====
     
     /// Create a Load Immediate instruction: rd = imm
@@ -119,6 +356,47 @@ impl InstructionBuilder {
         }
     }
     
+    /// Create a Load Byte instruction: rd = sign_extend(byte memory[rs + offset])
+    pub fn load_byte(rd: Register, rs: Register, offset: u16) -> InstructionType {
+        InstructionType::IType {
+            opcode: ITypeOp::LB,
+            rd, rs, imm: offset
+        }
+    }
+    
+    /// Create a Store Byte instruction: byte memory[rs + offset] = rd
+    pub fn store_byte(rd: Register, rs: Register, offset: u16) -> InstructionType {
+        InstructionType::IType {
+            opcode: ITypeOp::SB,
+            rd, rs, imm: offset
+        }
+    }
+    
+    /// Create a Load Halfword instruction: rd = sign_extend(halfword memory[rs + offset])
+    pub fn load_halfword(rd: Register, rs: Register, offset: u16) -> InstructionType {
+        InstructionType::IType {
+            opcode: ITypeOp::LH,
+            rd, rs, imm: offset
+        }
+    }
+    
+    /// Create a Store Halfword instruction: halfword memory[rs + offset] = rd
+    pub fn store_halfword(rd: Register, rs: Register, offset: u16) -> InstructionType {
+        InstructionType::IType {
+            opcode: ITypeOp::SH,
+            rd, rs, imm: offset
+        }
+    }
+    
+    /// Create an Enter instruction: push fp, fp = sp, then reserve `frame_size` bytes of locals
+    pub fn enter(frame_size: u16) -> InstructionType {
+        let zero_reg = Register::new(0).unwrap(); // Use register 0 as dummy
+        InstructionType::IType {
+            opcode: ITypeOp::ENTER,
+            rd: zero_reg, rs: zero_reg, imm: frame_size
+        }
+    }
+    
     // ===== B-Type Instructions (Branch operations) =====
     
     /// Create a Branch if Equal instruction: if (rs == rt) jump to offset
@@ -153,6 +431,22 @@ impl InstructionBuilder {
         }
     }
     
+    /// Create a Branch if Less Than Unsigned instruction: if (rs < rt) jump to offset
+    pub fn branch_less_than_unsigned(rs: Register, rt: Register, offset: u16) -> InstructionType {
+        InstructionType::BType {
+            opcode: BTypeOp::BLTU,
+            rs, rt, offset
+        }
+    }
+    
+    /// Create a Branch if Greater or Equal Unsigned instruction: if (rs >= rt) jump to offset
+    pub fn branch_greater_equal_unsigned(rs: Register, rt: Register, offset: u16) -> InstructionType {
+        InstructionType::BType {
+            opcode: BTypeOp::BGEU,
+            rs, rt, offset
+        }
+    }
+    
     /// Create a Branch if Zero instruction: if (rs == 0) jump to offset
     pub fn branch_zero(rs: Register, offset: u16) -> InstructionType {
         let zero_reg = Register::new(0).unwrap(); // Use register 0 as dummy
@@ -171,18 +465,54 @@ impl InstructionBuilder {
         }
     }
     
+    /// Create a Branch if Zero flag instruction: if (zero) jump to offset
+    pub fn branch_flags_zero(offset: u16) -> InstructionType {
+        let zero_reg = Register::new(0).unwrap(); // Use register 0 as dummy
+        InstructionType::BType {
+            opcode: BTypeOp::BZF,
+            rs: zero_reg, rt: zero_reg, offset
+        }
+    }
+    
+    /// Create a Branch if Not Zero flag instruction: if (!zero) jump to offset
+    pub fn branch_flags_not_zero(offset: u16) -> InstructionType {
+        let zero_reg = Register::new(0).unwrap(); // Use register 0 as dummy
+        InstructionType::BType {
+            opcode: BTypeOp::BNZF,
+            rs: zero_reg, rt: zero_reg, offset
+        }
+    }
+    
+    /// Create a Branch if Less Than flag instruction: if (negative != overflow) jump to offset
+    pub fn branch_flags_less_than(offset: u16) -> InstructionType {
+        let zero_reg = Register::new(0).unwrap(); // Use register 0 as dummy
+        InstructionType::BType {
+            opcode: BTypeOp::BLTF,
+            rs: zero_reg, rt: zero_reg, offset
+        }
+    }
+    
+    /// Create a Branch if Greater or Equal flag instruction: if (negative == overflow) jump to offset
+    pub fn branch_flags_greater_equal(offset: u16) -> InstructionType {
+        let zero_reg = Register::new(0).unwrap(); // Use register 0 as dummy
+        InstructionType::BType {
+            opcode: BTypeOp::BGEF,
+            rs: zero_reg, rt: zero_reg, offset
+        }
+    }
+    
     // ===== J-Type Instructions (Jump operations) =====
     
-    /// Create a Jump instruction: jump to addr
-    pub fn jump(addr: u16) -> InstructionType {
+    /// Create a Jump instruction: jump to addr (24-bit)
+    pub fn jump(addr: u32) -> InstructionType {
         InstructionType::JType {
             opcode: JTypeOp::JMP,
             addr
         }
     }
     
-    /// Create a Call instruction: call function at addr
-    pub fn call(addr: u16) -> InstructionType {
+    /// Create a Call instruction: call function at addr (24-bit)
+    pub fn call(addr: u32) -> InstructionType {
         InstructionType::JType {
             opcode: JTypeOp::CALL,
             addr
@@ -233,6 +563,66 @@ impl InstructionBuilder {
         }
     }
     
+    /// Create a Push instruction: push(rs)
+    pub fn push(rs: Register) -> InstructionType {
+        let zero_reg = Register::new(0).unwrap(); // Use register 0 as dummy
+        InstructionType::MType {
+            opcode: MTypeOp::PUSH,
+            rd: zero_reg, rs, rt: zero_reg
+        }
+    }
+    
+    /// Create a Pop instruction: rd = pop()
+    pub fn pop(rd: Register) -> InstructionType {
+        let zero_reg = Register::new(0).unwrap(); // Use register 0 as dummy
+        InstructionType::MType {
+            opcode: MTypeOp::POP,
+            rd, rs: zero_reg, rt: zero_reg
+        }
+    }
+    
+    /// Create a Memory Copy instruction: copy rt bytes from address rs to address rd
+    pub fn mem_copy(rd: Register, rs: Register, rt: Register) -> InstructionType {
+        InstructionType::MType {
+            opcode: MTypeOp::MEMCPY,
+            rd, rs, rt
+        }
+    }
+    
+    /// Create a Memory Set instruction: fill rt bytes at address rd with the low byte of rs
+    pub fn mem_set(rd: Register, rs: Register, rt: Register) -> InstructionType {
+        InstructionType::MType {
+            opcode: MTypeOp::MEMSET,
+            rd, rs, rt
+        }
+    }
+    
+    /// Create a Compare-And-Swap instruction: if memory[rs] == rt { memory[rs] = rd }, rd = old memory[rs]
+    pub fn compare_and_swap(rd: Register, rs: Register, rt: Register) -> InstructionType {
+        InstructionType::MType {
+            opcode: MTypeOp::CAS,
+            rd, rs, rt
+        }
+    }
+    
+    /// Create a Jump Register instruction: jump to the address held in rs
+    pub fn jump_register(rs: Register) -> InstructionType {
+        let zero_reg = Register::new(0).unwrap(); // Use register 0 as dummy
+        InstructionType::MType {
+            opcode: MTypeOp::JR,
+            rd: zero_reg, rs, rt: zero_reg
+        }
+    }
+    
+    /// Create a Call Register instruction: call the function at the address held in rs
+    pub fn call_register(rs: Register) -> InstructionType {
+        let zero_reg = Register::new(0).unwrap(); // Use register 0 as dummy
+        InstructionType::MType {
+            opcode: MTypeOp::CALLR,
+            rd: zero_reg, rs, rt: zero_reg
+        }
+    }
+    
     // ===== S-Type Instructions (System/IO operations) =====
     
     /// Create a Print instruction: print(rs)
@@ -244,6 +634,15 @@ impl InstructionBuilder {
         }
     }
     
+    /// Create a Print Hex instruction: print(rs) formatted as 0x%08X
+    pub fn print_hex(rs: Register) -> InstructionType {
+        InstructionType::SType {
+            opcode: STypeOp::PRINTX,
+            rd: None,
+            rs: Some(rs)
+        }
+    }
+    
     /// Create a Read instruction: rd = read()
     pub fn read(rd: Register) -> InstructionType {
         InstructionType::SType {
@@ -253,6 +652,15 @@ impl InstructionBuilder {
         }
     }
     
+    /// Create a Put Char instruction: write the low byte of rs as a character
+    pub fn put_char(rs: Register) -> InstructionType {
+        InstructionType::SType {
+            opcode: STypeOp::PUTC,
+            rd: None,
+            rs: Some(rs)
+        }
+    }
+    
     /// Create a System Call instruction
     pub fn syscall(rd: Option<Register>, rs: Option<Register>) -> InstructionType {
         InstructionType::SType {
@@ -276,6 +684,13 @@ impl InstructionBuilder {
             opcode: NTypeOp::HALT
         }
     }
+    
+    /// Create a Leave instruction: sp = fp, fp = pop(), undoing `enter`
+    pub fn leave() -> InstructionType {
+        InstructionType::NType {
+            opcode: NTypeOp::LEAVE
+        }
+    }
 }
 
 // ===== Convenience functions for common register creation =====
@@ -344,6 +759,106 @@ pub mod registers {
         Register::new(11).unwrap()
     }
     
+    /// Create register 12
+    pub fn r12() -> Register {
+        Register::new(12).unwrap()
+    }
+    
+    /// Create register 13
+    pub fn r13() -> Register {
+        Register::new(13).unwrap()
+    }
+    
+    /// Create register 14
+    pub fn r14() -> Register {
+        Register::new(14).unwrap()
+    }
+    
+    /// Create register 15
+    pub fn r15() -> Register {
+        Register::new(15).unwrap()
+    }
+    
+    /// Create register 16
+    pub fn r16() -> Register {
+        Register::new(16).unwrap()
+    }
+    
+    /// Create register 17
+    pub fn r17() -> Register {
+        Register::new(17).unwrap()
+    }
+    
+    /// Create register 18
+    pub fn r18() -> Register {
+        Register::new(18).unwrap()
+    }
+    
+    /// Create register 19
+    pub fn r19() -> Register {
+        Register::new(19).unwrap()
+    }
+    
+    /// Create register 20
+    pub fn r20() -> Register {
+        Register::new(20).unwrap()
+    }
+    
+    /// Create register 21
+    pub fn r21() -> Register {
+        Register::new(21).unwrap()
+    }
+    
+    /// Create register 22
+    pub fn r22() -> Register {
+        Register::new(22).unwrap()
+    }
+    
+    /// Create register 23
+    pub fn r23() -> Register {
+        Register::new(23).unwrap()
+    }
+    
+    /// Create register 24
+    pub fn r24() -> Register {
+        Register::new(24).unwrap()
+    }
+    
+    /// Create register 25
+    pub fn r25() -> Register {
+        Register::new(25).unwrap()
+    }
+    
+    /// Create register 26
+    pub fn r26() -> Register {
+        Register::new(26).unwrap()
+    }
+    
+    /// Create register 27
+    pub fn r27() -> Register {
+        Register::new(27).unwrap()
+    }
+    
+    /// Create register 28
+    pub fn r28() -> Register {
+        Register::new(28).unwrap()
+    }
+    
+    /// Create register 29
+    pub fn r29() -> Register {
+        Register::new(29).unwrap()
+    }
+    
+    /// Create register 30
+    pub fn r30() -> Register {
+        Register::new(30).unwrap()
+    }
+    
+    /// Create register 31
+    pub fn r31() -> Register {
+        Register::new(31).unwrap()
+    }
+    
     /// Create register by number (0-31)
     pub fn reg(n: u8) -> Result<Register, String> {
         Register::new(n)
@@ -438,4 +953,13 @@ mod tests {
         assert_eq!(fp().get_value(), 30);
         assert_eq!(ra().get_value(), 31);
     }
+    
+    #[test]
+    fn test_all_numbered_registers() {
+        assert_eq!(r12().get_value(), 12);
+        assert_eq!(r28().get_value(), 28);
+        assert_eq!(r29(), sp());
+        assert_eq!(r30(), fp());
+        assert_eq!(r31(), ra());
+    }
 }
\ No newline at end of file
@@ -26,7 +26,7 @@ pub enum InstructionType {
     },
     JType {
         opcode: JTypeOp,
-        addr: u16,
+        addr: u32, // Only the low 24 bits are encoded
     },
     MType {
         opcode: MTypeOp,
@@ -43,3 +43,134 @@ pub enum InstructionType {
         opcode: NTypeOp,
     },
 }
+
+impl InstructionType {
+    /// Assembly mnemonic of this instruction's opcode
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            InstructionType::RType { opcode, .. } => opcode.name(),
+            InstructionType::IType { opcode, .. } => opcode.name(),
+            InstructionType::BType { opcode, .. } => opcode.name(),
+            InstructionType::JType { opcode, .. } => opcode.name(),
+            InstructionType::MType { opcode, .. } => opcode.name(),
+            InstructionType::SType { opcode, .. } => opcode.name(),
+            InstructionType::NType { opcode } => opcode.name(),
+        }
+    }
+
+    /// Return the canonical form of this instruction
+    ///
+    /// Operand fields an opcode does not read are normalised to R0 (or `None`
+    /// for S-Type operands), matching the placeholders `InstructionBuilder`
+    /// fills in. `decode` always returns canonical instructions, so stray bits
+    /// in unused fields never make two equivalent instructions compare unequal.
+    /// For example `MOV` only reads `rd` and `rs`, `BZ` only `rs`, and `LI` only `rd`.
+    pub fn canonical(self) -> Self {
+        let zero = Register::new(0).unwrap();
+
+        match self {
+            InstructionType::RType { opcode, rd, rs, rt } => match opcode {
+                RTypeOp::MOV
+                | RTypeOp::NOT
+                | RTypeOp::NEG
+                | RTypeOp::ABS
+                | RTypeOp::ITOF
+                | RTypeOp::FTOI
+                | RTypeOp::CLZ
+                | RTypeOp::POPCNT
+                | RTypeOp::SEB
+                | RTypeOp::SEH
+                | RTypeOp::ZEB
+                | RTypeOp::ZEH => InstructionType::RType { opcode, rd, rs, rt: zero },
+                RTypeOp::CMP => InstructionType::RType { opcode, rd: zero, rs, rt },
+                _ => self,
+            },
+            InstructionType::IType { opcode, rd, imm, .. } if opcode == ITypeOp::LI => {
+                InstructionType::IType { opcode, rd, rs: zero, imm }
+            }
+            InstructionType::IType { opcode, imm, .. } if opcode == ITypeOp::ENTER => {
+                InstructionType::IType { opcode, rd: zero, rs: zero, imm }
+            }
+            InstructionType::BType { opcode, rs, offset, .. } => match opcode {
+                BTypeOp::BZ | BTypeOp::BNZ => InstructionType::BType { opcode, rs, rt: zero, offset },
+                BTypeOp::BZF | BTypeOp::BNZF | BTypeOp::BLTF | BTypeOp::BGEF => {
+                    InstructionType::BType { opcode, rs: zero, rt: zero, offset }
+                }
+                _ => self,
+            },
+            InstructionType::JType { opcode, addr } => match opcode {
+                JTypeOp::RET => InstructionType::JType { opcode, addr: 0 },
+                _ => InstructionType::JType { opcode, addr: addr & 0xFFFFFF },
+            },
+            InstructionType::MType { opcode, rd, rs, .. } => match opcode {
+                MTypeOp::ALLOC => InstructionType::MType { opcode, rd, rs, rt: zero },
+                MTypeOp::FREE | MTypeOp::PUSH | MTypeOp::JR | MTypeOp::CALLR => InstructionType::MType { opcode, rd: zero, rs, rt: zero },
+                MTypeOp::POP => InstructionType::MType { opcode, rd, rs: zero, rt: zero },
+                _ => self,
+            },
+            InstructionType::SType { opcode, rd, rs } => match opcode {
+                STypeOp::PRINT | STypeOp::PRINTX | STypeOp::PUTC => InstructionType::SType { opcode, rd: None, rs },
+                STypeOp::READ => InstructionType::SType { opcode, rd, rs: None },
+                STypeOp::SYSCALL => self,
+            },
+            _ => self,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler::decode::decode;
+    use crate::compiler::encode::encode;
+    use crate::compiler::instruction_builder::{InstructionBuilder, registers::*};
+
+    #[test]
+    fn test_mnemonic() {
+        assert_eq!(InstructionBuilder::add(r1(), r2(), r3()).mnemonic(), "ADD");
+        assert_eq!(InstructionBuilder::branch_zero(r1(), 4).mnemonic(), "BZ");
+        assert_eq!(InstructionBuilder::print(r1()).mnemonic(), "PRINT");
+        assert_eq!(InstructionBuilder::halt().mnemonic(), "HALT");
+    }
+
+    #[test]
+    fn test_builder_output_is_canonical() {
+        for instr in [
+            InstructionBuilder::mov(r1(), r2()),
+            InstructionBuilder::not(r3(), r4()),
+            InstructionBuilder::compare(r1(), r2()),
+            InstructionBuilder::branch_zero(r5(), 8),
+            InstructionBuilder::branch_flags_zero(8),
+            InstructionBuilder::push(r6()),
+            InstructionBuilder::pop(r7()),
+            InstructionBuilder::print(r1()),
+            InstructionBuilder::ret(),
+        ] {
+            assert_eq!(instr.canonical(), instr);
+        }
+    }
+
+    #[test]
+    fn test_stray_operands_are_cleared() {
+        let mov = InstructionType::RType { opcode: RTypeOp::MOV, rd: r1(), rs: r2(), rt: r9() };
+        assert_eq!(mov.canonical(), InstructionBuilder::mov(r1(), r2()));
+
+        let bz = InstructionType::BType { opcode: BTypeOp::BZ, rs: r3(), rt: r5(), offset: 4 };
+        assert_eq!(bz.canonical(), InstructionBuilder::branch_zero(r3(), 4));
+    }
+
+    #[test]
+    fn test_round_trip_with_immediates_overlapping_register_fields() {
+        // Bits 14-15 of these immediates land in the unused rs/rt field
+        for instr in [
+            InstructionBuilder::mov(r1(), r2()),
+            InstructionBuilder::not(r5(), r6()),
+            InstructionBuilder::load_immediate(r1(), 0xFFFF),
+            InstructionBuilder::branch_zero(r3(), 0xFFF0),
+            InstructionBuilder::branch_not_zero(r3(), 0xC000),
+            InstructionBuilder::branch_flags_less_than(0xFFFC),
+        ] {
+            assert_eq!(decode(encode(instr)).unwrap(), instr);
+        }
+    }
+}
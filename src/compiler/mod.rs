@@ -4,3 +4,8 @@ pub mod instruction_type;
 pub mod instruction_builder;
 pub mod encode;
 pub mod decode;
+pub mod disassemble;
+pub mod assemble;
+pub mod program_builder;
+pub mod codegen;
+pub mod regalloc;
@@ -7,12 +7,48 @@ pub enum RTypeOp {
     MUL = 0x12, // rd = rs1 * rs2
     DIV = 0x13, // rd = rs1 / rs2
     MOV = 0x14, // rd = rs1
+    NEG = 0x15, // rd = -rs1 (wrapping)
+    ABS = 0x16, // rd = |rs1| (wrapping)
+    MULH = 0x17,  // rd = high 32 bits of rs1 * rs2 (signed)
+    MULHU = 0x18, // rd = high 32 bits of rs1 * rs2 (unsigned)
+    DIVMOD = 0x19, // rd = rs1 / rs2, rd+1 = rs1 % rs2
+
+    //Floating Point (operands name float registers unless noted)
+    FADD = 0x1A, // fd = fs1 + fs2
+    FSUB = 0x1B, // fd = fs1 - fs2
+    FMUL = 0x1C, // fd = fs1 * fs2
+    FDIV = 0x1D, // fd = fs1 / fs2
+    ITOF = 0x1E, // fd = rs1 as f32 (rs1 is an integer register)
+    FTOI = 0x1F, // rd = fs1 as i32, truncating and saturating (rd is an integer register)
 
     //Core Logical
     AND = 0x20, // rd = rs1 & rs2
     OR = 0x21,  // rd = rs1 | rs2
     XOR = 0x22, // rd = rs1 ^ rs2
     NOT = 0x23, // rd = !rs1
+    SLL = 0x24, // rd = rs1 << rs2
+    SRL = 0x25, // rd = rs1 >> rs2 (logical)
+
+    //Comparisons
+    CMP = 0x26,  // flags = compare(rs1 - rs2), rd is ignored
+    SLT = 0x27,  // rd = (rs1 < rs2) ? 1 : 0 (signed)
+    SLTU = 0x28, // rd = (rs1 < rs2) ? 1 : 0 (unsigned)
+    MIN = 0x29,  // rd = min(rs1, rs2) (signed)
+    MAX = 0x2A,  // rd = max(rs1, rs2) (signed)
+    MINU = 0x2B, // rd = min(rs1, rs2) (unsigned)
+    MAXU = 0x2C, // rd = max(rs1, rs2) (unsigned)
+
+    //Rotates
+    ROL = 0x2D, // rd = rs1 rotated left by rs2
+    ROR = 0x2E, // rd = rs1 rotated right by rs2
+
+    //Bit Manipulation
+    CLZ = 0x90,    // rd = leading zero bits of rs1
+    POPCNT = 0x91, // rd = set bits in rs1
+    SEB = 0x92,    // rd = sign_extend(low byte of rs1)
+    SEH = 0x93,    // rd = sign_extend(low halfword of rs1)
+    ZEB = 0x94,    // rd = zero_extend(low byte of rs1)
+    ZEH = 0x95,    // rd = zero_extend(low halfword of rs1)
 }
 
 #[repr(u8)]
@@ -22,9 +58,16 @@ pub enum ITypeOp {
     LI = 0x30,   // rd = immediate (load immediate)
     ADDI = 0x31, // rd = rs + immediate
 
+    //Stack Frames
+    ENTER = 0x32, // push fp, fp = sp, sp -= immediate
+
     //Memory
     LOAD = 0x40,  // rd = memory[rs + offset]
     STORE = 0x41, // memory[rs + offset] = rt
+    LB = 0x42,    // rd = sign_extend(byte memory[rs + offset])
+    SB = 0x43,    // byte memory[rs + offset] = rd
+    LH = 0x44,    // rd = sign_extend(halfword memory[rs + offset])
+    SH = 0x45,    // halfword memory[rs + offset] = rd
 }
 
 #[repr(u8)]
@@ -37,6 +80,16 @@ pub enum BTypeOp {
     BGE = 0x53, // if (rs1 >= rs2) jump to offset
     BZ = 0x54,  // if (rs == 0) jump to offset
     BNZ = 0x55, // if (rs != 0) jump to offset
+
+    //Flag-based Branches (read the flags set by CMP)
+    BZF = 0x56,  // if (zero) jump to offset
+    BNZF = 0x57, // if (!zero) jump to offset
+    BLTF = 0x58, // if (negative != overflow) jump to offset
+    BGEF = 0x59, // if (negative == overflow) jump to offset
+
+    //Unsigned Branches
+    BLTU = 0x5A, // if (rs1 < rs2) jump to offset (unsigned)
+    BGEU = 0x5B, // if (rs1 >= rs2) jump to offset (unsigned)
 }
 
 #[repr(u8)]
@@ -56,6 +109,21 @@ pub enum MTypeOp {
     FREE = 0x71,   // free(rs)
     ALOAD = 0x72,  // rd = array[rs1 + rs2]
     ASTORE = 0x73, // array[rs1 + rs2] = rt
+
+    //Stack
+    PUSH = 0x74, // push(rs)
+    POP = 0x75,  // rd = pop()
+
+    //Bulk Memory
+    MEMCPY = 0x76, // memmove(rd, rs, rt bytes)
+    MEMSET = 0x77, // memset(rd, low byte of rs, rt bytes)
+
+    //Indirect Control Flow
+    JR = 0x78,    // Jump to address in rs
+    CALLR = 0x79, // Call function at address in rs
+
+    //Atomics
+    CAS = 0x7A, // if memory[rs] == rt { memory[rs] = rd }, rd = old memory[rs]
 }
 
 #[repr(u8)]
@@ -65,6 +133,8 @@ pub enum STypeOp {
     PRINT = 0x80,   // print(rs)
     READ = 0x81,    // rd = READ()
     SYSCALL = 0x82, // System call
+    PUTC = 0x83,    // putchar(low byte of rs)
+    PRINTX = 0x84,  // print(rs) as 0x%08X
 }
 
 #[repr(u8)]
@@ -72,4 +142,209 @@ pub enum STypeOp {
 pub enum NTypeOp {
     NOP = 0x00,  // No operation
     HALT = 0x01, // Stop execution
+    LEAVE = 0x02, // sp = fp, fp = pop()
+}
+
+impl RTypeOp {
+    /// Assembly mnemonic for this opcode
+    pub fn name(&self) -> &'static str {
+        match self {
+            RTypeOp::ADD => "ADD",
+            RTypeOp::SUB => "SUB",
+            RTypeOp::MUL => "MUL",
+            RTypeOp::DIV => "DIV",
+            RTypeOp::MOV => "MOV",
+            RTypeOp::NEG => "NEG",
+            RTypeOp::ABS => "ABS",
+            RTypeOp::MULH => "MULH",
+            RTypeOp::MULHU => "MULHU",
+            RTypeOp::DIVMOD => "DIVMOD",
+            RTypeOp::FADD => "FADD",
+            RTypeOp::FSUB => "FSUB",
+            RTypeOp::FMUL => "FMUL",
+            RTypeOp::FDIV => "FDIV",
+            RTypeOp::ITOF => "ITOF",
+            RTypeOp::FTOI => "FTOI",
+            RTypeOp::AND => "AND",
+            RTypeOp::OR => "OR",
+            RTypeOp::XOR => "XOR",
+            RTypeOp::NOT => "NOT",
+            RTypeOp::SLL => "SLL",
+            RTypeOp::SRL => "SRL",
+            RTypeOp::CMP => "CMP",
+            RTypeOp::SLT => "SLT",
+            RTypeOp::SLTU => "SLTU",
+            RTypeOp::MIN => "MIN",
+            RTypeOp::MAX => "MAX",
+            RTypeOp::MINU => "MINU",
+            RTypeOp::MAXU => "MAXU",
+            RTypeOp::ROL => "ROL",
+            RTypeOp::ROR => "ROR",
+            RTypeOp::CLZ => "CLZ",
+            RTypeOp::POPCNT => "POPCNT",
+            RTypeOp::SEB => "SEB",
+            RTypeOp::SEH => "SEH",
+            RTypeOp::ZEB => "ZEB",
+            RTypeOp::ZEH => "ZEH",
+        }
+    }
+}
+
+impl std::fmt::Display for RTypeOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.pad(self.name())
+    }
+}
+
+impl ITypeOp {
+    /// Assembly mnemonic for this opcode
+    pub fn name(&self) -> &'static str {
+        match self {
+            ITypeOp::LI => "LI",
+            ITypeOp::ADDI => "ADDI",
+            ITypeOp::ENTER => "ENTER",
+            ITypeOp::LOAD => "LOAD",
+            ITypeOp::STORE => "STORE",
+            ITypeOp::LB => "LB",
+            ITypeOp::SB => "SB",
+            ITypeOp::LH => "LH",
+            ITypeOp::SH => "SH",
+        }
+    }
+}
+
+impl std::fmt::Display for ITypeOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.pad(self.name())
+    }
+}
+
+impl BTypeOp {
+    /// Assembly mnemonic for this opcode
+    pub fn name(&self) -> &'static str {
+        match self {
+            BTypeOp::BEQ => "BEQ",
+            BTypeOp::BNE => "BNE",
+            BTypeOp::BLT => "BLT",
+            BTypeOp::BGE => "BGE",
+            BTypeOp::BZ => "BZ",
+            BTypeOp::BNZ => "BNZ",
+            BTypeOp::BZF => "BZF",
+            BTypeOp::BNZF => "BNZF",
+            BTypeOp::BLTF => "BLTF",
+            BTypeOp::BGEF => "BGEF",
+            BTypeOp::BLTU => "BLTU",
+            BTypeOp::BGEU => "BGEU",
+        }
+    }
+}
+
+impl std::fmt::Display for BTypeOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.pad(self.name())
+    }
+}
+
+impl JTypeOp {
+    /// Assembly mnemonic for this opcode
+    pub fn name(&self) -> &'static str {
+        match self {
+            JTypeOp::JMP => "JMP",
+            JTypeOp::CALL => "CALL",
+            JTypeOp::RET => "RET",
+        }
+    }
+}
+
+impl std::fmt::Display for JTypeOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.pad(self.name())
+    }
+}
+
+impl MTypeOp {
+    /// Assembly mnemonic for this opcode
+    pub fn name(&self) -> &'static str {
+        match self {
+            MTypeOp::ALLOC => "ALLOC",
+            MTypeOp::FREE => "FREE",
+            MTypeOp::ALOAD => "ALOAD",
+            MTypeOp::ASTORE => "ASTORE",
+            MTypeOp::PUSH => "PUSH",
+            MTypeOp::POP => "POP",
+            MTypeOp::MEMCPY => "MEMCPY",
+            MTypeOp::MEMSET => "MEMSET",
+            MTypeOp::JR => "JR",
+            MTypeOp::CALLR => "CALLR",
+            MTypeOp::CAS => "CAS",
+        }
+    }
+}
+
+impl std::fmt::Display for MTypeOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.pad(self.name())
+    }
+}
+
+impl STypeOp {
+    /// Assembly mnemonic for this opcode
+    pub fn name(&self) -> &'static str {
+        match self {
+            STypeOp::PRINT => "PRINT",
+            STypeOp::READ => "READ",
+            STypeOp::SYSCALL => "SYSCALL",
+            STypeOp::PUTC => "PUTC",
+            STypeOp::PRINTX => "PRINTX",
+        }
+    }
+}
+
+impl std::fmt::Display for STypeOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.pad(self.name())
+    }
+}
+
+impl NTypeOp {
+    /// Assembly mnemonic for this opcode
+    pub fn name(&self) -> &'static str {
+        match self {
+            NTypeOp::NOP => "NOP",
+            NTypeOp::HALT => "HALT",
+            NTypeOp::LEAVE => "LEAVE",
+        }
+    }
+}
+
+impl std::fmt::Display for NTypeOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.pad(self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_opcode_names() {
+        assert_eq!(RTypeOp::ADD.name(), "ADD");
+        assert_eq!(RTypeOp::SLTU.name(), "SLTU");
+        assert_eq!(ITypeOp::LI.name(), "LI");
+        assert_eq!(ITypeOp::SH.name(), "SH");
+        assert_eq!(BTypeOp::BNZ.name(), "BNZ");
+        assert_eq!(BTypeOp::BGEU.name(), "BGEU");
+        assert_eq!(JTypeOp::CALL.name(), "CALL");
+        assert_eq!(MTypeOp::MEMCPY.name(), "MEMCPY");
+        assert_eq!(STypeOp::PUTC.name(), "PUTC");
+        assert_eq!(NTypeOp::HALT.name(), "HALT");
+    }
+
+    #[test]
+    fn test_display_matches_name() {
+        assert_eq!(RTypeOp::XOR.to_string(), "XOR");
+        assert_eq!(BTypeOp::BLTF.to_string(), "BLTF");
+        assert_eq!(format!("{:<6}|", JTypeOp::RET), "RET   |");
+    }
 }
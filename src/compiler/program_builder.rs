@@ -0,0 +1,312 @@
+use crate::compiler::{
+    decode::decode, encode::try_encode, instruction_builder::InstructionBuilder,
+    instruction_type::InstructionType,
+};
+use std::collections::HashMap;
+
+/// Largest address a J-Type instruction can hold
+const MAX_JUMP_ADDRESS: u32 = 0xFFFFFF;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProgramBuildError {
+    UndefinedLabel(String),
+    DuplicateLabel(String),
+    BranchOutOfRange { label: String, offset: i64 },
+    AddressOutOfRange { label: String, address: u32 },
+    NotABranch(String),
+    InvalidInstruction { address: u32, reason: String },
+}
+
+impl std::fmt::Display for ProgramBuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ProgramBuildError::UndefinedLabel(label) => write!(f, "undefined label '{}'", label),
+            ProgramBuildError::DuplicateLabel(label) => write!(f, "label '{}' defined twice", label),
+            ProgramBuildError::BranchOutOfRange { label, offset } => {
+                write!(f, "branch to '{}' needs offset {} which the branch cannot encode", label, offset)
+            }
+            ProgramBuildError::AddressOutOfRange { label, address } => {
+                write!(f, "label '{}' at 0x{:08X} is beyond the 24-bit jump range", label, address)
+            }
+            ProgramBuildError::NotABranch(label) => {
+                write!(f, "reference to '{}' is attached to an instruction that is not a branch", label)
+            }
+            ProgramBuildError::InvalidInstruction { address, reason } => {
+                write!(f, "instruction at 0x{:08X}: {}", address, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ProgramBuildError {}
+
+/// Assemble a program from instructions and named labels
+///
+/// Jumps, calls and branches may refer to labels defined before or after them;
+/// `build` fills in their addresses and offsets.
+#[derive(Debug, Default)]
+pub struct ProgramBuilder {
+    code: Vec<InstructionType>,
+    labels: HashMap<String, u32>, // label -> byte address
+    fixups: Vec<(usize, String)>, // instruction index -> label it targets
+    duplicate: Option<String>,
+}
+
+impl ProgramBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Byte address the next instruction will be placed at
+    pub fn address(&self) -> u32 {
+        (self.code.len() * 4) as u32
+    }
+
+    /// Append an instruction as is
+    pub fn instruction(&mut self, instr: InstructionType) -> &mut Self {
+        self.code.push(instr);
+        self
+    }
+
+    /// Name the address of the next instruction
+    pub fn label(&mut self, name: &str) -> &mut Self {
+        let address = self.address();
+        if self.labels.insert(name.to_string(), address).is_some() && self.duplicate.is_none() {
+            self.duplicate = Some(name.to_string());
+        }
+        self
+    }
+
+    /// Append a jump to `label`
+    pub fn jump_to(&mut self, label: &str) -> &mut Self {
+        self.instruction_to(InstructionBuilder::jump(0), label)
+    }
+
+    /// Append a call to the function at `label`
+    pub fn call_to(&mut self, label: &str) -> &mut Self {
+        self.instruction_to(InstructionBuilder::call(0), label)
+    }
+
+    /// Append `branch` with its offset replaced by the distance to `label`
+    ///
+    /// `branch` is any B-Type instruction, e.g. `InstructionBuilder::branch_zero(reg, 0)`;
+    /// anything else makes `build` fail with `NotABranch`.
+    pub fn branch_to(&mut self, branch: InstructionType, label: &str) -> &mut Self {
+        self.instruction_to(branch, label)
+    }
+
+    fn instruction_to(&mut self, instr: InstructionType, label: &str) -> &mut Self {
+        self.fixups.push((self.code.len(), label.to_string()));
+        self.instruction(instr)
+    }
+
+    /// Resolve every label reference and encode the program
+    pub fn build(&self) -> Result<Vec<u32>, ProgramBuildError> {
+        if let Some(label) = &self.duplicate {
+            return Err(ProgramBuildError::DuplicateLabel(label.clone()));
+        }
+
+        let mut code = self.code.clone();
+        for (index, label) in &self.fixups {
+            let target = *self
+                .labels
+                .get(label)
+                .ok_or_else(|| ProgramBuildError::UndefinedLabel(label.clone()))?;
+
+            code[*index] = match code[*index] {
+                InstructionType::BType { opcode, rs, rt, .. } => {
+                    // Offsets are relative to the instruction after the branch (see `execute_btype`)
+                    let offset = target as i64 - (*index as i64 + 1) * 4;
+                    let out_of_range = || ProgramBuildError::BranchOutOfRange { label: label.clone(), offset };
+                    let branch = InstructionType::BType {
+                        opcode,
+                        rs,
+                        rt,
+                        offset: i16::try_from(offset).map_err(|_| out_of_range())? as u16,
+                    };
+                    // Two-register branches have a shorter offset field
+                    let round_trips = try_encode(branch)
+                        .ok()
+                        .and_then(|bits| decode(bits).ok())
+                        .is_some_and(|decoded| decoded == branch.canonical());
+                    if !round_trips {
+                        return Err(out_of_range());
+                    }
+                    branch
+                }
+                InstructionType::JType { opcode, .. } => {
+                    if target > MAX_JUMP_ADDRESS {
+                        return Err(ProgramBuildError::AddressOutOfRange {
+                            label: label.clone(),
+                            address: target,
+                        });
+                    }
+                    InstructionType::JType { opcode, addr: target }
+                }
+                _ => return Err(ProgramBuildError::NotABranch(label.clone())),
+            };
+        }
+
+        code.into_iter()
+            .enumerate()
+            .map(|(index, instr)| {
+                try_encode(instr).map_err(|reason| ProgramBuildError::InvalidInstruction {
+                    address: (index * 4) as u32,
+                    reason,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler::encode::encode;
+    use crate::compiler::instruction_builder::registers::*;
+    use crate::vm::VM;
+    use std::io::{self, Write};
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(std::rc::Rc<std::cell::RefCell<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn run(program: &[u32]) -> String {
+        let out = SharedBuffer::default();
+        let mut vm = VM::with_io(Box::new(out.clone()), Box::new(io::empty()));
+        vm.load_program(program).unwrap();
+        vm.run().unwrap();
+        let bytes = out.0.borrow().clone();
+        String::from_utf8(bytes).unwrap()
+    }
+
+    /// The branching demo: print the larger of `a` and `b`
+    fn print_larger(a: u16, b: u16) -> Vec<u32> {
+        let mut builder = ProgramBuilder::new();
+        builder
+            .instruction(InstructionBuilder::load_immediate(r1(), a))
+            .instruction(InstructionBuilder::load_immediate(r2(), b))
+            .branch_to(InstructionBuilder::branch_less_than(r1(), r2(), 0), "second")
+            .instruction(InstructionBuilder::print(r1()))
+            .jump_to("end")
+            .label("second")
+            .instruction(InstructionBuilder::print(r2()))
+            .label("end")
+            .instruction(InstructionBuilder::halt());
+        builder.build().unwrap()
+    }
+
+    #[test]
+    fn test_labels_match_hand_offsets() {
+        for (a, b) in [(15, 10), (5, 10)] {
+            let by_hand = vec![
+                encode(InstructionBuilder::load_immediate(r1(), a)),
+                encode(InstructionBuilder::load_immediate(r2(), b)),
+                encode(InstructionBuilder::branch_less_than(r1(), r2(), 8)), // 8: to 20
+                encode(InstructionBuilder::print(r1())),
+                encode(InstructionBuilder::jump(24)), // 16: to 24
+                encode(InstructionBuilder::print(r2())),
+                encode(InstructionBuilder::halt()),
+            ];
+
+            let built = print_larger(a, b);
+            assert_eq!(built, by_hand);
+            assert_eq!(run(&built), run(&by_hand));
+            assert_eq!(run(&built), format!("{}\n", a.max(b)));
+        }
+    }
+
+    #[test]
+    fn test_backward_branch_and_call() {
+        // Count R1 down from 3, calling a function that prints it each time
+        let mut builder = ProgramBuilder::new();
+        builder
+            .instruction(InstructionBuilder::load_immediate(r1(), 3))
+            .instruction(InstructionBuilder::load_immediate(r2(), 1))
+            .label("loop")
+            .call_to("show")
+            .instruction(InstructionBuilder::sub(r1(), r1(), r2()))
+            .branch_to(InstructionBuilder::branch_not_zero(r1(), 0), "loop")
+            .instruction(InstructionBuilder::halt());
+        assert_eq!(builder.address(), 24);
+        builder
+            .label("show")
+            .instruction(InstructionBuilder::print(r1()))
+            .instruction(InstructionBuilder::ret());
+
+        let program = builder.build().unwrap();
+        assert_eq!(program[2], encode(InstructionBuilder::call(24)));
+        assert_eq!(program[4], encode(InstructionBuilder::branch_not_zero(r1(), -12i16 as u16)));
+        assert_eq!(run(&program), "3\n2\n1\n");
+    }
+
+    #[test]
+    fn test_backward_two_register_branch() {
+        // Print 0, 1, 2 while R1 < R2
+        let mut builder = ProgramBuilder::new();
+        builder
+            .instruction(InstructionBuilder::load_immediate(r2(), 3))
+            .instruction(InstructionBuilder::load_immediate(r3(), 1))
+            .label("loop")
+            .instruction(InstructionBuilder::print(r1()))
+            .instruction(InstructionBuilder::add(r1(), r1(), r3()))
+            .branch_to(InstructionBuilder::branch_less_than(r1(), r2(), 0), "loop")
+            .instruction(InstructionBuilder::halt());
+
+        let program = builder.build().unwrap();
+        assert_eq!(decode(program[4]).unwrap(), InstructionBuilder::branch_less_than(r1(), r2(), -12i16 as u16));
+        assert_eq!(run(&program), "0\n1\n2\n");
+    }
+
+    #[test]
+    fn test_label_errors() {
+        let mut builder = ProgramBuilder::new();
+        builder.jump_to("nowhere");
+        assert_eq!(builder.build(), Err(ProgramBuildError::UndefinedLabel("nowhere".to_string())));
+
+        let mut builder = ProgramBuilder::new();
+        builder.label("a").instruction(InstructionBuilder::nop()).label("a");
+        assert_eq!(builder.build(), Err(ProgramBuildError::DuplicateLabel("a".to_string())));
+
+        let mut builder = ProgramBuilder::new();
+        builder.branch_to(InstructionBuilder::branch_flags_zero(0), "far");
+        for _ in 0..0x2000 {
+            builder.instruction(InstructionBuilder::nop());
+        }
+        builder.label("far");
+        assert_eq!(
+            builder.build(),
+            Err(ProgramBuildError::BranchOutOfRange { label: "far".to_string(), offset: 0x8000 })
+        );
+
+        // Two-register branches only reach 14 bits
+        let mut builder = ProgramBuilder::new();
+        builder.label("back");
+        for _ in 0..0x800 {
+            builder.instruction(InstructionBuilder::nop());
+        }
+        builder.branch_to(InstructionBuilder::branch_equal(r1(), r2(), 0), "back");
+        assert_eq!(
+            builder.build(),
+            Err(ProgramBuildError::BranchOutOfRange { label: "back".to_string(), offset: -0x2004 })
+        );
+
+        let mut builder = ProgramBuilder::new();
+        builder.branch_to(InstructionBuilder::print(r1()), "end").label("end");
+        assert_eq!(builder.build(), Err(ProgramBuildError::NotABranch("end".to_string())));
+
+        let mut builder = ProgramBuilder::new();
+        builder.instruction(InstructionBuilder::nop()).instruction(InstructionBuilder::load(r1(), r2(), 0x4000));
+        assert!(matches!(builder.build(), Err(ProgramBuildError::InvalidInstruction { address: 4, .. })));
+    }
+}
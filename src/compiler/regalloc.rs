@@ -0,0 +1,167 @@
+use crate::compiler::{
+    codegen::{CodegenError, CodegenResult},
+    instruction_builder::InstructionBuilder,
+    instruction_type::InstructionType,
+    register::Register,
+};
+use std::collections::VecDeque;
+
+/// Register allocator for expression temporaries, spilling to the stack when full
+///
+/// Temporaries must be released in the reverse order they were allocated,
+/// which is what a recursive expression compiler does naturally. When every
+/// register is taken, the oldest live temporary is `PUSH`ed and its register
+/// reused; freeing that register again `POP`s the old value back in place, so
+/// the caller's handle is valid by the time it is consumed. Until then the
+/// spilled handle and the new one name the same register, so a caller holding
+/// two operands must compare them before emitting an instruction that reads both.
+#[derive(Debug, Clone)]
+pub struct RegAllocator {
+    free: Vec<Register>,
+    /// Temporaries currently held in registers, oldest first
+    live: VecDeque<Register>,
+    /// Registers whose previous value sits on the stack, most recent last
+    spilled: Vec<Register>,
+    spill_count: usize,
+    max_spill_depth: usize,
+}
+
+impl RegAllocator {
+    /// Create an allocator handing out registers `first..=last`
+    pub fn new(first: u8, last: u8) -> Self {
+        Self {
+            free: (first..=last).rev().map(|n| Register::new(n).unwrap()).collect(),
+            live: VecDeque::new(),
+            spilled: Vec::new(),
+            spill_count: 0,
+            max_spill_depth: 0,
+        }
+    }
+
+    /// Allocate a temporary, emitting a `PUSH` into `code` if a spill is needed
+    pub fn alloc(&mut self, code: &mut Vec<InstructionType>) -> CodegenResult<Register> {
+        let reg = match self.free.pop() {
+            Some(reg) => reg,
+            None => {
+                let victim = self.live.pop_front().ok_or(CodegenError::OutOfRegisters)?;
+                code.push(InstructionBuilder::push(victim));
+                self.spilled.push(victim);
+                self.spill_count += 1;
+                self.max_spill_depth = self.max_spill_depth.max(self.spilled.len());
+                victim
+            }
+        };
+        self.live.push_back(reg);
+        Ok(reg)
+    }
+
+    /// Allocate a register that is never spilled, e.g. one backing a variable
+    pub fn alloc_pinned(&mut self) -> CodegenResult<Register> {
+        self.free.pop().ok_or(CodegenError::OutOfRegisters)
+    }
+
+    /// Release a temporary, emitting a `POP` into `code` if it displaced a spilled value
+    pub fn free(&mut self, reg: Register, code: &mut Vec<InstructionType>) {
+        if let Some(index) = self.live.iter().rposition(|&r| r == reg) {
+            self.live.remove(index);
+        }
+
+        if self.spilled.last() == Some(&reg) {
+            self.spilled.pop();
+            code.push(InstructionBuilder::pop(reg));
+            // The restored value was the oldest one when it was spilled
+            self.live.push_front(reg);
+        } else {
+            self.free.push(reg);
+        }
+    }
+
+    /// Number of registers currently available without spilling
+    pub fn free_count(&self) -> usize {
+        self.free.len()
+    }
+
+    /// Number of values currently spilled to the stack
+    pub fn spill_depth(&self) -> usize {
+        self.spilled.len()
+    }
+
+    /// Total number of spills performed so far
+    pub fn spill_count(&self) -> usize {
+        self.spill_count
+    }
+
+    /// Largest number of values that were on the stack at once
+    pub fn max_spill_depth(&self) -> usize {
+        self.max_spill_depth
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler::opcode::MTypeOp;
+
+    #[test]
+    fn test_alloc_without_spilling() {
+        let mut regs = RegAllocator::new(1, 3);
+        let mut code = Vec::new();
+
+        let a = regs.alloc(&mut code).unwrap();
+        let b = regs.alloc(&mut code).unwrap();
+        assert_eq!((a.get_value(), b.get_value()), (1, 2));
+        assert_eq!(regs.free_count(), 1);
+
+        regs.free(b, &mut code);
+        regs.free(a, &mut code);
+        assert_eq!(regs.free_count(), 3);
+        assert!(code.is_empty());
+        assert_eq!(regs.spill_count(), 0);
+    }
+
+    #[test]
+    fn test_spill_and_restore() {
+        let mut regs = RegAllocator::new(1, 2);
+        let mut code = Vec::new();
+
+        let a = regs.alloc(&mut code).unwrap();
+        let b = regs.alloc(&mut code).unwrap();
+        let c = regs.alloc(&mut code).unwrap();
+
+        // The oldest temporary is pushed and its register reused
+        assert_eq!(c, a);
+        assert_eq!(code, vec![InstructionBuilder::push(a)]);
+        assert_eq!(regs.spill_depth(), 1);
+
+        regs.free(c, &mut code);
+        assert!(matches!(code[1], InstructionType::MType { opcode: MTypeOp::POP, rd, .. } if rd == a));
+        assert_eq!(regs.spill_depth(), 0);
+
+        regs.free(b, &mut code);
+        regs.free(a, &mut code);
+        assert_eq!(regs.free_count(), 2);
+        assert_eq!(regs.spill_count(), 1);
+        assert_eq!(regs.max_spill_depth(), 1);
+    }
+
+    #[test]
+    fn test_pinned_registers_are_never_spilled() {
+        let mut regs = RegAllocator::new(1, 2);
+        let mut code = Vec::new();
+
+        let var = regs.alloc_pinned().unwrap();
+        let tmp = regs.alloc(&mut code).unwrap();
+        let spill = regs.alloc(&mut code).unwrap();
+        assert_ne!(spill, var);
+        assert_eq!(spill, tmp);
+
+        assert_eq!(regs.alloc_pinned(), Err(CodegenError::OutOfRegisters));
+    }
+
+    #[test]
+    fn test_out_of_registers_with_nothing_to_spill() {
+        let mut regs = RegAllocator::new(1, 1);
+        regs.alloc_pinned().unwrap();
+        assert_eq!(regs.alloc(&mut Vec::new()), Err(CodegenError::OutOfRegisters));
+    }
+}
@@ -4,6 +4,7 @@ use std::ops::Range;
 
 /// Position information for a token in the source code
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Position {
     pub line: usize,
     pub column: usize,
@@ -22,6 +23,7 @@ impl Position {
 
 /// A token with its position information
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LocatedToken {
     pub token: Token,
     pub span: Range<usize>,
@@ -40,24 +42,50 @@ impl LocatedToken {
     }
 }
 
+/// Map a byte offset to a position using a table of line start offsets
+///
+/// `line_starts` is the table returned by [`WidowLexer::line_starts`]. Without the source
+/// text this can only count bytes, so the column is the 1-based byte distance from the
+/// start of the line. It matches token columns only on lines of ASCII without tabs;
+/// use [`WidowLexer::position_at`] for columns that agree with [`LocatedToken`].
+pub fn position_at(line_starts: &[usize], offset: usize) -> Position {
+    let line = line_starts.partition_point(|&start| start <= offset);
+    let line_start = line.checked_sub(1).map_or(0, |i| line_starts[i]);
+
+    Position::new(line.max(1), offset - line_start + 1, offset)
+}
+
 /// Enhanced lexer with position tracking and utility functions
 pub struct WidowLexer<'a> {
     lexer: LogosLexer<'a, Token>,
     source: &'a str,
+    line_starts: Vec<usize>,
     current_line: usize,
     current_column: usize,
     last_newline_pos: usize,
+    tab_width: usize,
 }
 
 impl<'a> WidowLexer<'a> {
     /// Create a new lexer for the given source code
     pub fn new(source: &'a str) -> Self {
+        Self::with_tab_width(source, 1)
+    }
+
+    /// Create a lexer that advances a tab to the next multiple of `tab_width` columns
+    ///
+    /// A width of 1 (the default) counts a tab as a single column; 0 is treated as 1.
+    pub fn with_tab_width(source: &'a str, tab_width: usize) -> Self {
         Self {
             lexer: Token::lexer(source),
             source,
+            line_starts: std::iter::once(0)
+                .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+                .collect(),
             current_line: 1,
             current_column: 1,
             last_newline_pos: 0,
+            tab_width: tab_width.max(1),
         }
     }
 
@@ -88,8 +116,15 @@ impl<'a> WidowLexer<'a> {
 
     /// Peek at the next token without consuming it
     pub fn peek(&self) -> Option<Result<Token, Token>> {
+        self.peek_nth(0)
+    }
+
+    /// Peek `n` tokens ahead without consuming anything (`peek_nth(0)` is `peek`)
+    ///
+    /// This clones the underlying lexer and re-lexes up to `n + 1` tokens, so it is O(n).
+    pub fn peek_nth(&self, n: usize) -> Option<Result<Token, Token>> {
         let mut clone_lexer = self.lexer.clone();
-        match clone_lexer.next()? {
+        match clone_lexer.nth(n)? {
             Ok(token) => Some(Ok(token)),
             Err(_) => Some(Err(Token::Error)),
         }
@@ -122,6 +157,18 @@ impl<'a> WidowLexer<'a> {
         tokens
     }
 
+    /// Get the bare tokens from the source, including newlines and comments
+    ///
+    /// Stops at the first invalid token and returns it along with its start position.
+    pub fn tokenize_owned(source: &str) -> Result<Vec<Token>, (Token, Position)> {
+        WidowLexer::new(source)
+            .map(|token_result| match token_result {
+                Ok(located_token) => Ok(located_token.token),
+                Err(error_token) => Err((error_token.token, error_token.start_pos)),
+            })
+            .collect()
+    }
+
     /// Filter out comments and return only code tokens
     pub fn tokenize_code_only(source: &'a str) -> Vec<Result<LocatedToken, LocatedToken>> {
         Self::tokenize_all(source)
@@ -135,6 +182,18 @@ impl<'a> WidowLexer<'a> {
             .collect()
     }
 
+    /// Lazily yield only significant tokens, skipping newlines and comments
+    ///
+    /// Errors are kept. This is the streaming counterpart of `tokenize_code_only`.
+    pub fn significant(self) -> impl Iterator<Item = Result<LocatedToken, LocatedToken>> + 'a {
+        self.filter(|token_result| match token_result {
+            Ok(located_token) => {
+                located_token.token != Token::Newline && !located_token.token.is_comment()
+            }
+            Err(_) => true, // Keep errors
+        })
+    }
+
     /// Check if we're at the end of the source
     pub fn is_at_end(&self) -> bool {
         self.lexer.remainder().is_empty()
@@ -149,22 +208,27 @@ impl<'a> WidowLexer<'a> {
         )
     }
 
+    /// Byte offsets at which each line of the source starts (the first entry is always 0)
+    pub fn line_starts(&self) -> &[usize] {
+        &self.line_starts
+    }
+
+    /// Position of a byte offset, with the column counted in characters like token positions
+    ///
+    /// Tabs advance to the next multiple of the lexer's tab width.
+    pub fn position_at(&self, offset: usize) -> Position {
+        self.calculate_position(offset)
+    }
+
     /// Calculate position from byte offset
     fn calculate_position(&self, offset: usize) -> Position {
-        let mut line = 1;
-        let mut column = 1;
+        let line = self.line_starts.partition_point(|&start| start <= offset);
+        let text = &self.source[self.line_starts[line - 1]..offset];
+        let mut column = text.chars().fold(1, |column, ch| self.advance_column(column, ch));
 
-        for (i, ch) in self.source.char_indices() {
-            if i >= offset {
-                break;
-            }
-
-            if ch == '\n' {
-                line += 1;
-                column = 1;
-            } else {
-                column += 1;
-            }
+        // The CR of a CRLF pair belongs to the line break, not the line
+        if text.ends_with('\r') && self.source[offset..].starts_with('\n') {
+            column -= 1;
         }
 
         Position::new(line, column, offset)
@@ -178,10 +242,19 @@ impl<'a> WidowLexer<'a> {
                 self.current_column = 1;
                 self.last_newline_pos = self.lexer.span().end;
             } else {
-                self.current_column += 1;
+                self.current_column = self.advance_column(self.current_column, ch);
             }
         }
     }
+
+    /// Column after `ch` when it starts at `column`
+    fn advance_column(&self, column: usize, ch: char) -> usize {
+        if ch == '\t' {
+            (column - 1) / self.tab_width * self.tab_width + self.tab_width + 1
+        } else {
+            column + 1
+        }
+    }
 }
 
 /// Iterator implementation for the lexer
@@ -260,6 +333,7 @@ impl Token {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::lexer::token::CommentMarker;
 
     #[test]
     fn test_basic_tokenization() {
@@ -291,6 +365,193 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_crlf_line_endings() {
+        let source = "a\r\nb\nc  \r\nd";
+        let tokens: Vec<_> = WidowLexer::tokenize_all(source)
+            .into_iter()
+            .map(|t| t.unwrap())
+            .collect();
+
+        let newlines: Vec<_> = tokens
+            .iter()
+            .filter(|t| t.token == Token::Newline)
+            .map(|t| t.span.clone())
+            .collect();
+        assert_eq!(newlines, vec![1..3, 4..5, 8..10]);
+
+        let lines: Vec<_> = tokens
+            .iter()
+            .filter(|t| t.token != Token::Newline)
+            .map(|t| (t.start_pos.line, t.start_pos.column))
+            .collect();
+        assert_eq!(lines, vec![(1, 1), (2, 1), (3, 1), (4, 1)]);
+    }
+
+    #[test]
+    fn test_tab_width() {
+        let source = "\tx\n  \ty\tz";
+        let columns = |lexer: WidowLexer| -> Vec<usize> {
+            lexer
+                .significant()
+                .map(|token_result| token_result.unwrap().start_pos.column)
+                .collect()
+        };
+
+        // By default a tab is one column
+        assert_eq!(columns(WidowLexer::new(source)), vec![2, 4, 6]);
+        assert_eq!(columns(WidowLexer::with_tab_width(source, 4)), vec![5, 5, 9]);
+        assert_eq!(columns(WidowLexer::with_tab_width(source, 8)), vec![9, 9, 17]);
+
+        let token = WidowLexer::with_tab_width("\tx", 4).next_token().unwrap().unwrap();
+        assert_eq!(token.end_pos.column, 6);
+    }
+
+    #[test]
+    fn test_line_starts_and_position_at() {
+        let source = "first\nsecond\r\n\nfourth";
+        let lexer = WidowLexer::new(source);
+        let line_starts = lexer.line_starts();
+        assert_eq!(line_starts, &[0, 6, 14, 15]);
+
+        for (index, &start) in line_starts.iter().enumerate() {
+            assert_eq!(position_at(line_starts, start), Position::new(index + 1, 1, start));
+        }
+
+        assert_eq!(position_at(line_starts, 9), Position::new(2, 4, 9));
+        assert_eq!(position_at(line_starts, source.len()), Position::new(4, 7, 21));
+    }
+
+    #[test]
+    fn test_position_at_counts_characters() {
+        let source = "x = \"héllo\"\n\tcafé = 1";
+        let lexer = WidowLexer::with_tab_width(source, 4);
+        let tokens: Vec<LocatedToken> = WidowLexer::with_tab_width(source, 4)
+            .filter_map(Result::ok)
+            .filter(|token| token.token.is_significant())
+            .collect();
+
+        // Every token starts where the lexer's own position_at says it does
+        for token in &tokens {
+            assert_eq!(lexer.position_at(token.span.start), token.start_pos, "{:?}", token.token);
+        }
+        let one = tokens.iter().find(|token| token.token == Token::Integer(1)).unwrap();
+        assert_eq!(one.start_pos, Position::new(2, 12, 22));
+
+        // The free function counts bytes: 2 for 'é', 1 for the tab
+        assert_eq!(position_at(lexer.line_starts(), one.span.start), Position::new(2, 10, 22));
+    }
+
+    #[test]
+    fn test_peek_nth() {
+        let source = "a + b * c";
+        let mut lexer = WidowLexer::new(source);
+
+        assert_eq!(lexer.peek_nth(0), lexer.peek());
+        assert_eq!(lexer.peek_nth(0), Some(Ok(Token::Identifier("a".to_string()))));
+        assert_eq!(lexer.peek_nth(1), Some(Ok(Token::Plus)));
+        assert_eq!(lexer.peek_nth(3), Some(Ok(Token::Multiply)));
+        assert_eq!(lexer.peek_nth(5), None);
+
+        // Peeking must not consume anything
+        let first = lexer.next_token().unwrap().unwrap();
+        assert_eq!(first.token, Token::Identifier("a".to_string()));
+        assert_eq!(lexer.peek_nth(2), Some(Ok(Token::Multiply)));
+    }
+
+    #[test]
+    fn test_significant_tokens() {
+        let tokens: Vec<Token> = WidowLexer::new("a // c\n + b")
+            .significant()
+            .map(|token_result| token_result.unwrap().token)
+            .collect();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Identifier("a".to_string()),
+                Token::Plus,
+                Token::Identifier("b".to_string()),
+            ]
+        );
+
+        // Errors still come through, in order
+        let mut tokens = WidowLexer::new("/* x */ \"abc\n b").significant();
+        assert_eq!(tokens.next().unwrap().unwrap_err().token, Token::Error);
+        assert!(tokens.all(|token_result| token_result.is_ok()));
+    }
+
+    #[test]
+    fn test_keyword_lookup() {
+        assert_eq!(Token::from_keyword("func"), Some(Token::Func));
+        assert_eq!(Token::from_keyword("ret"), Some(Token::Return));
+        assert_eq!(Token::from_keyword("fibonacci"), None);
+        assert_eq!(Token::from_keyword("Func"), None);
+        assert_eq!(Token::from_keyword("i32"), None);
+
+        // The table agrees with the lexer
+        for &keyword in Token::all_keywords() {
+            let token = Token::from_keyword(keyword).unwrap();
+            assert!(token.is_keyword());
+            assert_eq!(WidowLexer::tokenize_owned(keyword).unwrap(), vec![token]);
+        }
+        assert_eq!(Token::all_keywords().len(), 33);
+    }
+
+    #[test]
+    fn test_tokenize_owned() {
+        let tokens = WidowLexer::tokenize_owned("func x // name\n").unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Func,
+                Token::Identifier("x".to_string()),
+                Token::LineComment("// name".to_string()),
+                Token::Newline,
+            ]
+        );
+
+        let (token, position) = WidowLexer::tokenize_owned("x\n  \"abc").unwrap_err();
+        assert_eq!(token, Token::Error);
+        assert_eq!(position, Position::new(2, 3, 4));
+    }
+
+    #[test]
+    fn test_unicode_identifiers() {
+        let tokens = WidowLexer::tokenize_owned("π = 3").unwrap();
+        assert_eq!(tokens[0], Token::Identifier("π".to_string()));
+
+        let tokens = WidowLexer::tokenize_owned("名前 = \"x\"").unwrap();
+        assert_eq!(tokens[0], Token::Identifier("名前".to_string()));
+
+        let tokens = WidowLexer::tokenize_owned("café Δx _tmp1").unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Identifier("café".to_string()),
+                Token::Identifier("Δx".to_string()),
+                Token::Identifier("_tmp1".to_string()),
+            ]
+        );
+
+        // Emoji are not XID_Start
+        assert!(WidowLexer::tokenize_owned("😀").is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let tokens: Vec<LocatedToken> = WidowLexer::tokenize_all("x = \"hi\" // note\n3.5 'c'")
+            .into_iter()
+            .map(|t| t.unwrap())
+            .collect();
+
+        let json = serde_json::to_string(&tokens).unwrap();
+        assert!(json.contains(r#""span":{"start":0,"end":1}"#));
+
+        let decoded: Vec<LocatedToken> = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, tokens);
+    }
+
     #[test]
     fn test_string_literals() {
         let source = r#""hello world" r"raw string" `template ${var}`"#;
@@ -310,6 +571,27 @@ mod tests {
         assert_eq!(string_count, 3);
     }
 
+    #[test]
+    fn test_unterminated_string_literals() {
+        for source in [r#"x = "abc"#, r#"x = r"abc"#, "x = `abc"] {
+            let tokens = WidowLexer::tokenize_all(source);
+            let error = tokens.last().unwrap().as_ref().unwrap_err();
+            assert_eq!(error.token, Token::Error);
+            assert_eq!(error.span, 4..source.len());
+        }
+
+        // The error stops at end of line and lexing resumes on the next one
+        let source = "x = \"abc\ny";
+        let tokens = WidowLexer::tokenize_all(source);
+        let error = tokens[2].as_ref().unwrap_err();
+        assert_eq!(error.span, 4..8);
+        assert_eq!(tokens[3].as_ref().unwrap().token, Token::Newline);
+        assert_eq!(
+            tokens[4].as_ref().unwrap().token,
+            Token::Identifier("y".to_string())
+        );
+    }
+
     #[test]
     fn test_numeric_literals() {
         let source = "42 3.14 123.456e10";
@@ -373,4 +655,53 @@ mod tests {
 
         assert!(comment_count >= 3, "Should have at least 3 comments");
     }
+
+    #[test]
+    fn test_comment_markers() {
+        let marker = |source: &str| {
+            let tokens = WidowLexer::tokenize_owned(source).unwrap();
+            tokens[0].comment_marker()
+        };
+
+        assert_eq!(marker("// TODO: fix"), Some(CommentMarker::Todo));
+        assert_eq!(marker("/* fixme later */"), Some(CommentMarker::Fixme));
+        assert_eq!(marker("/** Note: see below **/"), Some(CommentMarker::Note));
+        assert_eq!(marker("// quick hack"), Some(CommentMarker::Hack));
+        assert_eq!(marker("// todos are fun"), None);
+        assert_eq!(marker("// plain comment"), None);
+        assert_eq!(Token::Identifier("TODO".to_string()).comment_marker(), None);
+    }
+
+    #[test]
+    fn test_nested_block_comments() {
+        let source = "/* outer /* inner */ outer */ x";
+        let tokens = WidowLexer::tokenize_all(source);
+        assert_eq!(
+            tokens[0].as_ref().unwrap().token,
+            Token::BlockComment("/* outer /* inner */ outer */".to_string())
+        );
+        assert_eq!(
+            tokens[1].as_ref().unwrap().token,
+            Token::Identifier("x".to_string())
+        );
+
+        let source = "/* a /* b /* c */ b */ a */";
+        let tokens = WidowLexer::tokenize_all(source);
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(
+            tokens[0].as_ref().unwrap().token,
+            Token::BlockComment(source.to_string())
+        );
+    }
+
+    #[test]
+    fn test_unterminated_nested_block_comment() {
+        let source = "/* outer /* inner */ never closed";
+        let tokens = WidowLexer::tokenize_all(source);
+        assert_eq!(tokens.len(), 1);
+
+        let error = tokens[0].as_ref().unwrap_err();
+        assert_eq!(error.token, Token::Error);
+        assert_eq!(error.span, 0..source.len());
+    }
 }
@@ -1,5 +1,5 @@
 pub mod lexer;
 pub mod token;
 
-pub use lexer::{WidowLexer, LocatedToken, Position};
-pub use token::Token;
+pub use lexer::{WidowLexer, LocatedToken, Position, position_at};
+pub use token::{CommentMarker, Token};
@@ -1,7 +1,8 @@
-use logos::Logos;
+use logos::{Lexer, Logos};
 
 #[derive(Logos, Debug, Clone, PartialEq)]
-#[logos(skip r"[ \t\r\f]+")] // Skip whitespace but not newlines
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[logos(skip r"[ \t\f]+|\r")] // Skip whitespace but not newlines
 pub enum Token {
     // Keywords - order matters for longest match
     #[token("async")]
@@ -205,20 +206,14 @@ pub enum Token {
     #[token("$")]
     Dollar,
 
-    // String literals - raw strings must come before regular strings
-    #[regex(r#"r"([^"]*)""#, |lex| lex.slice()[2..lex.slice().len()-1].to_string())]
+    // String literals - scanned by hand so unterminated ones can be reported
+    #[token("r\"", |lex| lex_quoted(lex, b'"', false))]
     RawString(String),
 
-    #[regex(r#""([^"\\]|\\.)*""#, |lex| {
-        let slice = lex.slice();
-        slice[1..slice.len()-1].to_string()
-    })]
+    #[token("\"", |lex| lex_quoted(lex, b'"', true))]
     String(String),
 
-    #[regex(r"`([^`\\]|\\.)*`", |lex| {
-        let slice = lex.slice();
-        slice[1..slice.len()-1].to_string()
-    })]
+    #[token("`", |lex| lex_quoted(lex, b'`', true))]
     TemplateString(String),
 
     // Character literals
@@ -236,20 +231,22 @@ pub enum Token {
     Integer(i64),
 
     // Identifiers (must come after keywords)
-    #[regex(r"[a-zA-Z_][a-zA-Z0-9_]*", |lex| lex.slice().to_string())]
+    #[regex(r"[\p{XID_Start}_]\p{XID_Continue}*", |lex| lex.slice().to_string())]
     Identifier(String),
 
     // Comments - order matters for proper matching
-    #[regex(r"(?s)/\*\*([^*]|\*+[^*/])*\*+/", |lex| lex.slice().to_string())]
+    #[token("/**", lex_nested_comment)]
     DocComment(String),
 
-    #[regex(r"(?s)/\*([^*]|\*+[^*/])*\*+/", |lex| lex.slice().to_string())]
+    #[token("/*", lex_nested_comment)]
+    #[token("/**/", |lex| lex.slice().to_string())]
     BlockComment(String),
 
     #[regex(r"//[^\n\r]*", |lex| lex.slice().to_string())]
     LineComment(String),
 
-    // Newlines (significant for parsing)
+    // Newlines (significant for parsing) - CRLF must come before LF
+    #[token("\r\n")]
     #[token("\n")]
     Newline,
 
@@ -257,6 +254,70 @@ pub enum Token {
     Error,
 }
 
+/// Action-item marker found in a comment body
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommentMarker {
+    Todo,
+    Fixme,
+    Hack,
+    Note,
+}
+
+/// Scan a quoted literal after its opening delimiter, returning the body
+///
+/// When `escapes` is set a backslash skips the character after it. A literal with
+/// no closing `quote` is an error spanning from the opening quote to end of line.
+fn lex_quoted(lex: &mut Lexer<Token>, quote: u8, escapes: bool) -> Option<String> {
+    let rest = lex.remainder();
+    let bytes = rest.as_bytes();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' if escapes => i += 2,
+            b if b == quote => {
+                lex.bump(i + 1);
+                return Some(rest[..i].to_string());
+            }
+            _ => i += 1,
+        }
+    }
+
+    lex.bump(rest.find('\n').unwrap_or(rest.len()));
+    None
+}
+
+/// Scan a block or doc comment after its opener, tracking `/* */` nesting depth
+///
+/// Logos regexes can't count, so the body is consumed by hand. An unterminated
+/// comment swallows the rest of the input and is reported as an error.
+fn lex_nested_comment(lex: &mut Lexer<Token>) -> Option<String> {
+    let bytes = lex.remainder().as_bytes();
+    let mut depth = 1;
+    let mut i = 0;
+
+    while i + 1 < bytes.len() {
+        match (bytes[i], bytes[i + 1]) {
+            (b'/', b'*') => {
+                depth += 1;
+                i += 2;
+            }
+            (b'*', b'/') => {
+                depth -= 1;
+                i += 2;
+                if depth == 0 {
+                    lex.bump(i);
+                    return Some(lex.slice().to_string());
+                }
+            }
+            _ => i += 1,
+        }
+    }
+
+    lex.bump(bytes.len());
+    None
+}
+
 impl Token {
     /// Returns true if this token is a keyword
     pub fn is_keyword(&self) -> bool {
@@ -272,6 +333,59 @@ impl Token {
         )
     }
 
+    /// Look up the keyword token spelled `s`, or `None` if it isn't a keyword
+    ///
+    /// Covers the same words as `is_keyword`; type names are not included.
+    pub fn from_keyword(s: &str) -> Option<Token> {
+        match s {
+            "async" => Some(Token::Async),
+            "await" => Some(Token::Await),
+            "break" => Some(Token::Break),
+            "case" => Some(Token::Case),
+            "const" => Some(Token::Const),
+            "continue" => Some(Token::Continue),
+            "default" => Some(Token::Default),
+            "elif" => Some(Token::Elif),
+            "else" => Some(Token::Else),
+            "enumerate" => Some(Token::Enumerate),
+            "false" => Some(Token::False),
+            "for" => Some(Token::For),
+            "from" => Some(Token::From),
+            "func" => Some(Token::Func),
+            "if" => Some(Token::If),
+            "impl" => Some(Token::Impl),
+            "import" => Some(Token::Import),
+            "in" => Some(Token::In),
+            "match" => Some(Token::Match),
+            "module" => Some(Token::Module),
+            "nil" => Some(Token::Nil),
+            "ret" => Some(Token::Return),
+            "self" => Some(Token::SelfKeyword),
+            "spawn" => Some(Token::Spawn),
+            "step" => Some(Token::Step),
+            "struct" => Some(Token::Struct),
+            "switch" => Some(Token::Switch),
+            "then" => Some(Token::Then),
+            "trait" => Some(Token::Trait),
+            "true" => Some(Token::True),
+            "while" => Some(Token::While),
+            "with" => Some(Token::With),
+            "as" => Some(Token::As),
+            _ => None,
+        }
+    }
+
+    /// Every keyword spelling accepted by `from_keyword`, in declaration order
+    pub fn all_keywords() -> &'static [&'static str] {
+        &[
+            "async", "await", "break", "case", "const", "continue", "default", "elif",
+            "else", "enumerate", "false", "for", "from", "func", "if", "impl",
+            "import", "in", "match", "module", "nil", "ret", "self", "spawn",
+            "step", "struct", "switch", "then", "trait", "true", "while", "with",
+            "as",
+        ]
+    }
+
     /// Returns true if this token is a literal value
     pub fn is_literal(&self) -> bool {
         matches!(self,
@@ -315,6 +429,23 @@ impl Token {
         )
     }
 
+    /// Returns the first TODO/FIXME/HACK/NOTE marker in a comment, matched as a whole word
+    pub fn comment_marker(&self) -> Option<CommentMarker> {
+        let body = match self {
+            Token::LineComment(s) | Token::BlockComment(s) | Token::DocComment(s) => s,
+            _ => return None,
+        };
+
+        body.split(|c: char| !c.is_alphanumeric() && c != '_')
+            .find_map(|word| match word.to_ascii_uppercase().as_str() {
+                "TODO" => Some(CommentMarker::Todo),
+                "FIXME" => Some(CommentMarker::Fixme),
+                "HACK" => Some(CommentMarker::Hack),
+                "NOTE" => Some(CommentMarker::Note),
+                _ => None,
+            })
+    }
+
     /// Returns true if this token can start an expression
     pub fn can_start_expression(&self) -> bool {
         matches!(self,
@@ -391,6 +522,21 @@ impl Token {
             Token::While => "while",
             Token::With => "with",
             Token::As => "as",
+            Token::BoolType => "bool",
+            Token::CharType => "char",
+            Token::F32 => "f32",
+            Token::F64 => "f64",
+            Token::I8 => "i8",
+            Token::I16 => "i16",
+            Token::I32 => "i32",
+            Token::I64 => "i64",
+            Token::MapType => "map",
+            Token::SetType => "set",
+            Token::StringType => "String",
+            Token::U8 => "u8",
+            Token::U16 => "u16",
+            Token::U32 => "u32",
+            Token::U64 => "u64",
             Token::Plus => "+",
             Token::Minus => "-",
             Token::Multiply => "*",
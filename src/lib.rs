@@ -1,10 +1,13 @@
 pub mod compiler;
 pub mod lexer;
+pub mod parser;
 
 pub mod vm;
 
 pub use compiler::decode::decode;
 pub use compiler::encode::encode;
 pub use compiler::instruction_builder::InstructionBuilder;
+pub use compiler::program_builder::ProgramBuilder;
 pub use lexer::{LocatedToken, Position, Token, WidowLexer};
+pub use parser::{Expr, ParseError, Parser};
 pub use vm::{VM, VMError};
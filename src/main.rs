@@ -1,4 +1,4 @@
-use widow_lang::{VM, InstructionBuilder, encode, vm::GCConfig};
+use widow_lang::{VM, InstructionBuilder, encode, vm::{GCConfig, GcStrategy}};
 use widow_lang::compiler::instruction_builder::registers::*;
 use widow_lang::lexer::{WidowLexer, Token, LocatedToken};
 use std::env;
@@ -188,6 +188,11 @@ fn demo_garbage_collection(vm: &mut VM) {
         generational: true,
         max_heap_size: 2000, // Adjusted to trigger GC with demo allocations
         concurrent: false,
+        scan_memory: false,
+        strategy: GcStrategy::MarkSweep,
+        incremental_budget: None,
+        collect_every_n_allocations: None,
+        large_object_threshold: 64 * 1024,
     };
     
     // Create a new VM with custom GC config
@@ -240,7 +245,7 @@ fn demo_garbage_collection(vm: &mut VM) {
     println!("  Objects collected: {}", stats_after.objects_collected);
     println!("  Bytes collected: {}", stats_after.bytes_collected);
     println!("  Objects still tracked: {}", gc_vm.get_gc().object_count());
-    println!("  Total pause time: {} ms", stats_after.total_pause_time_ms);
+    println!("  Total pause time: {} us", stats_after.total_pause_time_us);
     
     // Demonstrate manual GC
     println!("\nForcing manual garbage collection...");
@@ -0,0 +1,153 @@
+use crate::lexer::Token;
+
+/// A literal value appearing directly in the source
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal {
+    Integer(i64),
+    Float(f64),
+    String(String),
+    Character(char),
+    Bool(bool),
+    Nil,
+}
+
+/// Expression node
+///
+/// Operators are kept as their lexer tokens, e.g. `Token::Plus` for `a + b`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Literal(Literal),
+    Identifier(String),
+    Unary {
+        op: Token,
+        operand: Box<Expr>,
+    },
+    Binary {
+        op: Token,
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+    Call {
+        callee: Box<Expr>,
+        args: Vec<Expr>,
+    },
+    /// A parenthesized expression
+    Grouping(Box<Expr>),
+    /// `[a, b, c]`
+    Array(Vec<Expr>),
+    /// `{key: value, ...}`, entries in source order
+    Map(Vec<(Expr, Expr)>),
+    /// `{ statements }` in expression position
+    Block(Vec<Stmt>),
+    /// `match value { pattern: result ... }`
+    Match {
+        scrutinee: Box<Expr>,
+        arms: Vec<MatchArm>,
+    },
+}
+
+/// Pattern on the left of a match arm
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pattern {
+    Literal(Literal),
+    /// Matches anything, binding it to the name
+    Binding(String),
+    /// `_`, which matches anything without binding
+    Wildcard,
+}
+
+/// One arm of a match: `2, 3: "few"` or `n if n > 10: "big"`
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchArm {
+    /// Alternatives; the arm applies if any of them matches
+    pub patterns: Vec<Pattern>,
+    pub guard: Option<Expr>,
+    pub body: Expr,
+}
+
+/// Type named in an annotation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypeName {
+    I8,
+    I16,
+    I32,
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+    F32,
+    F64,
+    String,
+    Bool,
+    Char,
+    Map,
+    Set,
+    /// A user-defined type referred to by name
+    Named(String),
+}
+
+impl TypeName {
+    /// Convert a type keyword or identifier token into a type name
+    pub fn from_token(token: &Token) -> Option<Self> {
+        match token {
+            Token::Identifier(name) => Some(TypeName::Named(name.clone())),
+            _ if token.is_type() => TypeName::from_keyword(token.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Look up a built-in type by its keyword spelling
+    pub fn from_keyword(keyword: &str) -> Option<Self> {
+        let type_name = match keyword {
+            "i8" => TypeName::I8,
+            "i16" => TypeName::I16,
+            "i32" => TypeName::I32,
+            "i64" => TypeName::I64,
+            "u8" => TypeName::U8,
+            "u16" => TypeName::U16,
+            "u32" => TypeName::U32,
+            "u64" => TypeName::U64,
+            "f32" => TypeName::F32,
+            "f64" => TypeName::F64,
+            "String" => TypeName::String,
+            "bool" => TypeName::Bool,
+            "char" => TypeName::Char,
+            "map" => TypeName::Map,
+            "set" => TypeName::Set,
+            _ => return None,
+        };
+        Some(type_name)
+    }
+}
+
+/// Statement node
+#[derive(Debug, Clone, PartialEq)]
+pub enum Stmt {
+    Expr(Expr),
+    /// `ret` with an optional value
+    Return(Option<Expr>),
+    /// `if cond { ... } elif cond { ... } else { ... }`
+    If {
+        condition: Expr,
+        then_branch: Vec<Stmt>,
+        elif_branches: Vec<(Expr, Vec<Stmt>)>,
+        else_branch: Option<Vec<Stmt>>,
+    },
+    /// `while cond { ... }`
+    While {
+        condition: Expr,
+        body: Vec<Stmt>,
+    },
+    Func(FuncDecl),
+}
+
+/// Function declaration, e.g. `func add(a: i32, b: i32) -> i32 { ret a + b }`
+#[derive(Debug, Clone, PartialEq)]
+pub struct FuncDecl {
+    pub name: String,
+    /// Parameter names with their type annotations, if given
+    pub params: Vec<(String, Option<TypeName>)>,
+    pub return_type: Option<TypeName>,
+    pub body: Vec<Stmt>,
+}
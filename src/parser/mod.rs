@@ -0,0 +1,5 @@
+pub mod ast;
+pub mod parser;
+
+pub use ast::{Expr, FuncDecl, Literal, MatchArm, Pattern, Stmt, TypeName};
+pub use parser::{ParseError, Parser};
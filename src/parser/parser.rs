@@ -0,0 +1,971 @@
+use crate::lexer::{LocatedToken, Position, Token, WidowLexer};
+use crate::parser::ast::{Expr, FuncDecl, Literal, MatchArm, Pattern, Stmt, TypeName};
+
+/// Binding power of prefix operators (`-x`, `!x`, `~x`)
+///
+/// Tighter than `*` but looser than `**` and member access, so `-a ** b` is `-(a ** b)`.
+const PREFIX_BP: u8 = 23;
+
+/// Binding power of a call's opening parenthesis
+const CALL_BP: u8 = 27;
+
+/// Error produced when the token stream doesn't match the grammar
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    /// What the parser expected, e.g. "expected expression"
+    pub message: String,
+    pub position: Position,
+    /// The offending token, or `None` at end of input
+    pub found: Option<Token>,
+}
+
+impl ParseError {
+    pub fn new(message: impl Into<String>, position: Position, found: Option<Token>) -> Self {
+        Self {
+            message: message.into(),
+            position,
+            found,
+        }
+    }
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}, found ",
+            self.position.line, self.position.column, self.message
+        )?;
+        match &self.found {
+            Some(Token::Error) => write!(f, "invalid token"),
+            Some(token) => write!(f, "{}", token),
+            None => write!(f, "end of input"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Recursive-descent parser with Pratt-style expression parsing
+pub struct Parser {
+    tokens: Vec<LocatedToken>,
+    current: usize,
+    end_pos: Position,
+}
+
+impl Parser {
+    /// Tokenize `source` and create a parser over the tokens
+    ///
+    /// Comments are dropped; invalid tokens are kept as `Token::Error` and reported
+    /// when the parser reaches them.
+    pub fn new(source: &str) -> Self {
+        let tokens: Vec<LocatedToken> = WidowLexer::new(source)
+            .map(|token_result| token_result.unwrap_or_else(|error_token| error_token))
+            .filter(|located_token| !located_token.token.is_comment())
+            .collect();
+        let end_pos = tokens
+            .last()
+            .map_or_else(|| Position::new(1, 1, 0), |last| last.end_pos.clone());
+
+        Self {
+            tokens,
+            current: 0,
+            end_pos,
+        }
+    }
+
+    /// Parse a function declaration: `func name(a: i32, b) -> i32 { ... }`
+    ///
+    /// Parameter and return type annotations are optional.
+    pub fn parse_function(&mut self) -> Result<FuncDecl, ParseError> {
+        self.expect(Token::Func, "'func'")?;
+        let name = self.expect_identifier("function name")?;
+
+        self.expect(Token::LeftParen, "'(' after function name")?;
+        let params = self.parse_delimited(Token::RightParen, |parser| {
+            let name = parser.expect_identifier("parameter name")?;
+            let type_name = if parser.check(&Token::Colon) {
+                parser.advance();
+                Some(parser.parse_type()?)
+            } else {
+                None
+            };
+            Ok((name, type_name))
+        })?;
+
+        let return_type = if self.check(&Token::Arrow) {
+            self.advance();
+            Some(self.parse_type()?)
+        } else {
+            None
+        };
+
+        let body = self.parse_block("function body")?;
+
+        Ok(FuncDecl {
+            name,
+            params,
+            return_type,
+            body,
+        })
+    }
+
+    /// Parse a type annotation
+    fn parse_type(&mut self) -> Result<TypeName, ParseError> {
+        match self.peek().and_then(TypeName::from_token) {
+            Some(type_name) => {
+                self.advance();
+                Ok(type_name)
+            }
+            None => Err(self.error("expected type")),
+        }
+    }
+
+    /// Parse a `{ ... }` block of statements separated by newlines or semicolons
+    fn parse_block(&mut self, what: &str) -> Result<Vec<Stmt>, ParseError> {
+        self.skip_newlines();
+        self.expect(Token::LeftBrace, &format!("'{{' to open {}", what))?;
+        self.parse_block_rest(Vec::new(), what)
+    }
+
+    /// Parse the statements of a block after `statements`, up to and including its `}`
+    fn parse_block_rest(
+        &mut self,
+        mut statements: Vec<Stmt>,
+        what: &str,
+    ) -> Result<Vec<Stmt>, ParseError> {
+        loop {
+            self.skip_separators();
+
+            match self.peek() {
+                Some(Token::RightBrace) => break,
+                None => return Err(self.error(&format!("expected '}}' to close {}", what))),
+                Some(_) => {}
+            }
+
+            statements.push(self.parse_statement()?);
+            self.expect_statement_end()?;
+        }
+
+        self.advance();
+        Ok(statements)
+    }
+
+    /// Parse a whole program, recovering from errors to report as many as possible
+    ///
+    /// After an error the parser skips to the next statement boundary and carries
+    /// on, so the returned statements exclude any that failed to parse.
+    pub fn parse_program(&mut self) -> (Vec<Stmt>, Vec<ParseError>) {
+        let mut statements = Vec::new();
+        let mut errors = Vec::new();
+
+        loop {
+            self.skip_separators();
+            if self.peek().is_none() {
+                break;
+            }
+
+            match self
+                .parse_statement()
+                .and_then(|stmt| self.expect_statement_end().map(|()| stmt))
+            {
+                Ok(stmt) => statements.push(stmt),
+                Err(err) => {
+                    errors.push(err);
+                    self.synchronize();
+                }
+            }
+        }
+
+        (statements, errors)
+    }
+
+    /// Skip tokens up to and including the next statement boundary
+    ///
+    /// Boundaries are a newline, `;`, or `}`.
+    pub fn synchronize(&mut self) {
+        while let Some(located) = self.advance() {
+            if matches!(
+                located.token,
+                Token::Newline | Token::Semicolon | Token::RightBrace
+            ) {
+                break;
+            }
+        }
+    }
+
+    /// Parse a single statement
+    pub fn parse_statement(&mut self) -> Result<Stmt, ParseError> {
+        match self.peek() {
+            Some(Token::Func) => Ok(Stmt::Func(self.parse_function()?)),
+            Some(Token::If) => self.parse_if(),
+            Some(Token::While) => {
+                self.advance();
+                let condition = self.parse_expression()?;
+                let body = self.parse_block("while body")?;
+                Ok(Stmt::While { condition, body })
+            }
+            Some(Token::Return) => {
+                self.advance();
+                if matches!(
+                    self.peek(),
+                    None | Some(Token::Newline | Token::Semicolon | Token::RightBrace)
+                ) {
+                    Ok(Stmt::Return(None))
+                } else {
+                    Ok(Stmt::Return(Some(self.parse_expression()?)))
+                }
+            }
+            _ => Ok(Stmt::Expr(self.parse_expression()?)),
+        }
+    }
+
+    /// Parse an `if` statement with any `elif` and `else` branches
+    ///
+    /// `elif` and `else` may start on the line after the preceding `}`.
+    fn parse_if(&mut self) -> Result<Stmt, ParseError> {
+        self.expect(Token::If, "'if'")?;
+        let condition = self.parse_expression()?;
+        let then_branch = self.parse_block("if body")?;
+
+        let mut elif_branches = Vec::new();
+        while self.peek_past_newlines() == Some(&Token::Elif) {
+            self.skip_newlines();
+            self.advance();
+            let condition = self.parse_expression()?;
+            elif_branches.push((condition, self.parse_block("elif body")?));
+        }
+
+        let else_branch = if self.peek_past_newlines() == Some(&Token::Else) {
+            self.skip_newlines();
+            self.advance();
+            Some(self.parse_block("else body")?)
+        } else {
+            None
+        };
+
+        Ok(Stmt::If {
+            condition,
+            then_branch,
+            elif_branches,
+            else_branch,
+        })
+    }
+
+    /// Parse a single expression
+    pub fn parse_expression(&mut self) -> Result<Expr, ParseError> {
+        self.parse_expr_bp(0)
+    }
+
+    /// Parse an expression whose operators bind at least as tightly as `min_bp`
+    fn parse_expr_bp(&mut self, min_bp: u8) -> Result<Expr, ParseError> {
+        let mut lhs = self.parse_prefix()?;
+
+        while let Some(op) = self.peek().cloned() {
+            if op == Token::LeftParen {
+                if CALL_BP < min_bp {
+                    break;
+                }
+                self.advance();
+                let args = self.parse_delimited(Token::RightParen, Self::parse_expression)?;
+                lhs = Expr::Call {
+                    callee: Box::new(lhs),
+                    args,
+                };
+                continue;
+            }
+
+            let Some((l_bp, r_bp)) = op.binding_power() else {
+                break;
+            };
+            if l_bp < min_bp {
+                break;
+            }
+
+            self.advance();
+            self.skip_newlines();
+            let rhs = self.parse_expr_bp(r_bp)?;
+            lhs = Expr::Binary {
+                op,
+                left: Box::new(lhs),
+                right: Box::new(rhs),
+            };
+        }
+
+        Ok(lhs)
+    }
+
+    /// Parse a literal, identifier, parenthesized expression, or prefix operator
+    fn parse_prefix(&mut self) -> Result<Expr, ParseError> {
+        let Some(token) = self.peek().cloned() else {
+            return Err(self.error("expected expression"));
+        };
+
+        let expr = match token {
+            Token::Integer(n) => Expr::Literal(Literal::Integer(n)),
+            Token::Float(n) => Expr::Literal(Literal::Float(n)),
+            Token::String(s) | Token::RawString(s) | Token::TemplateString(s) => {
+                Expr::Literal(Literal::String(s))
+            }
+            Token::Character(c) => Expr::Literal(Literal::Character(c)),
+            Token::True => Expr::Literal(Literal::Bool(true)),
+            Token::False => Expr::Literal(Literal::Bool(false)),
+            Token::Nil => Expr::Literal(Literal::Nil),
+            Token::Identifier(name) => Expr::Identifier(name),
+            Token::LeftParen => {
+                self.advance();
+                self.skip_newlines();
+                let inner = self.parse_expression()?;
+                self.skip_newlines();
+                self.expect(Token::RightParen, "')' to close grouping")?;
+                return Ok(Expr::Grouping(Box::new(inner)));
+            }
+            Token::Match => return self.parse_match(),
+            Token::LeftBracket => {
+                self.advance();
+                let elements = self.parse_delimited(Token::RightBracket, Self::parse_expression)?;
+                return Ok(Expr::Array(elements));
+            }
+            Token::LeftBrace => return self.parse_brace(),
+            Token::Minus | Token::Plus | Token::Not | Token::BitwiseNot => {
+                self.advance();
+                let operand = self.parse_expr_bp(PREFIX_BP)?;
+                return Ok(Expr::Unary {
+                    op: token,
+                    operand: Box::new(operand),
+                });
+            }
+            _ => return Err(self.error("expected expression")),
+        };
+
+        self.advance();
+        Ok(expr)
+    }
+
+    /// Parse a `{` in expression position as a map or a block
+    ///
+    /// `{}` and a first expression followed by `:` are maps, while a first entry
+    /// that is a statement or ends at a statement boundary makes a block. The
+    /// first expression is parsed once and becomes the first key or statement.
+    fn parse_brace(&mut self) -> Result<Expr, ParseError> {
+        self.advance();
+        self.skip_newlines();
+
+        match self.peek() {
+            Some(Token::RightBrace) => {
+                self.advance();
+                return Ok(Expr::Map(Vec::new()));
+            }
+            None | Some(Token::Semicolon | Token::Func | Token::If | Token::While | Token::Return) => {
+                return Ok(Expr::Block(self.parse_block_rest(Vec::new(), "block")?));
+            }
+            Some(_) => {}
+        }
+
+        let first = self.parse_expression()?;
+        if self.peek_past_newlines() != Some(&Token::Colon) && self.expect_statement_end().is_ok() {
+            return Ok(Expr::Block(self.parse_block_rest(vec![Stmt::Expr(first)], "block")?));
+        }
+
+        let mut entries = vec![self.parse_map_value(first)?];
+        self.skip_newlines();
+        if self.check(&Token::Comma) {
+            self.advance();
+            entries.extend(self.parse_delimited(Token::RightBrace, |parser| {
+                let key = parser.parse_expression()?;
+                parser.parse_map_value(key)
+            })?);
+        } else {
+            self.expect(Token::RightBrace, "'}'")?;
+        }
+        Ok(Expr::Map(entries))
+    }
+
+    /// Parse the `: value` half of a map entry whose key has been parsed
+    fn parse_map_value(&mut self, key: Expr) -> Result<(Expr, Expr), ParseError> {
+        self.skip_newlines();
+        self.expect(Token::Colon, "':' after map key")?;
+        self.skip_newlines();
+        Ok((key, self.parse_expression()?))
+    }
+
+    /// Parse a match expression, one arm per line
+    ///
+    /// Each arm is a comma-separated list of patterns, an optional `if` guard, a `:`,
+    /// and the arm's result expression.
+    pub fn parse_match(&mut self) -> Result<Expr, ParseError> {
+        self.expect(Token::Match, "'match'")?;
+        let scrutinee = self.parse_expression()?;
+        self.skip_newlines();
+        self.expect(Token::LeftBrace, "'{' to open match arms")?;
+
+        let mut arms = Vec::new();
+        loop {
+            self.skip_separators();
+
+            match self.peek() {
+                Some(Token::RightBrace) => break,
+                None => return Err(self.error("expected '}' to close match arms")),
+                Some(_) => {}
+            }
+
+            let mut patterns = vec![self.parse_pattern()?];
+            while self.check(&Token::Comma) {
+                self.advance();
+                patterns.push(self.parse_pattern()?);
+            }
+
+            let guard = if self.check(&Token::If) {
+                self.advance();
+                Some(self.parse_expression()?)
+            } else {
+                None
+            };
+
+            self.expect(Token::Colon, "':' after match pattern")?;
+            self.skip_newlines();
+            let body = self.parse_expression()?;
+            arms.push(MatchArm {
+                patterns,
+                guard,
+                body,
+            });
+
+            if !matches!(
+                self.peek(),
+                None | Some(Token::Newline | Token::Semicolon | Token::RightBrace)
+            ) {
+                return Err(self.error("expected newline after match arm"));
+            }
+        }
+
+        self.advance();
+        Ok(Expr::Match {
+            scrutinee: Box::new(scrutinee),
+            arms,
+        })
+    }
+
+    /// Parse a literal, binding, or wildcard pattern
+    fn parse_pattern(&mut self) -> Result<Pattern, ParseError> {
+        let negative = self.check(&Token::Minus);
+        if negative {
+            self.advance();
+        }
+
+        let pattern = match (self.peek(), negative) {
+            (Some(Token::Integer(n)), _) => {
+                Pattern::Literal(Literal::Integer(if negative { -n } else { *n }))
+            }
+            (Some(Token::Float(n)), _) => {
+                Pattern::Literal(Literal::Float(if negative { -n } else { *n }))
+            }
+            (Some(Token::String(s) | Token::RawString(s)), false) => {
+                Pattern::Literal(Literal::String(s.clone()))
+            }
+            (Some(Token::Character(c)), false) => Pattern::Literal(Literal::Character(*c)),
+            (Some(Token::True), false) => Pattern::Literal(Literal::Bool(true)),
+            (Some(Token::False), false) => Pattern::Literal(Literal::Bool(false)),
+            (Some(Token::Nil), false) => Pattern::Literal(Literal::Nil),
+            (Some(Token::Identifier(name)), false) if name == "_" => Pattern::Wildcard,
+            (Some(Token::Identifier(name)), false) => Pattern::Binding(name.clone()),
+            _ => return Err(self.error("expected pattern")),
+        };
+
+        self.advance();
+        Ok(pattern)
+    }
+
+    /// Parse comma-separated items up to and including `close`, allowing a trailing comma
+    fn parse_delimited<T>(
+        &mut self,
+        close: Token,
+        mut parse_item: impl FnMut(&mut Self) -> Result<T, ParseError>,
+    ) -> Result<Vec<T>, ParseError> {
+        let mut items = Vec::new();
+        self.skip_newlines();
+
+        while !self.check(&close) {
+            items.push(parse_item(self)?);
+            self.skip_newlines();
+
+            if self.check(&Token::Comma) {
+                self.advance();
+                self.skip_newlines();
+            } else {
+                break;
+            }
+        }
+
+        self.expect(close.clone(), &format!("'{}'", close))?;
+        Ok(items)
+    }
+
+    /// Current token, or `None` at end of input
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.current).map(|located| &located.token)
+    }
+
+    /// First token that isn't a newline, without consuming anything
+    fn peek_past_newlines(&self) -> Option<&Token> {
+        self.tokens[self.current..]
+            .iter()
+            .map(|located| &located.token)
+            .find(|token| **token != Token::Newline)
+    }
+
+    fn check(&self, token: &Token) -> bool {
+        self.peek() == Some(token)
+    }
+
+    fn advance(&mut self) -> Option<&LocatedToken> {
+        let located = self.tokens.get(self.current)?;
+        self.current += 1;
+        Some(located)
+    }
+
+    /// Consume an identifier and return its name
+    fn expect_identifier(&mut self, what: &str) -> Result<String, ParseError> {
+        match self.peek() {
+            Some(Token::Identifier(name)) => {
+                let name = name.clone();
+                self.advance();
+                Ok(name)
+            }
+            _ => Err(self.error(&format!("expected {}", what))),
+        }
+    }
+
+    /// Consume `token` or fail with "expected {what}"
+    fn expect(&mut self, token: Token, what: &str) -> Result<(), ParseError> {
+        if self.check(&token) {
+            self.advance();
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected {}", what)))
+        }
+    }
+
+    /// Skip blank lines and `;` between statements
+    fn skip_separators(&mut self) {
+        while matches!(self.peek(), Some(Token::Newline | Token::Semicolon)) {
+            self.advance();
+        }
+    }
+
+    /// Require a newline, `;`, `}`, or end of input after a statement
+    fn expect_statement_end(&self) -> Result<(), ParseError> {
+        if matches!(
+            self.peek(),
+            None | Some(Token::Newline | Token::Semicolon | Token::RightBrace)
+        ) {
+            Ok(())
+        } else {
+            Err(self.error("expected newline or ';' after statement"))
+        }
+    }
+
+    fn skip_newlines(&mut self) {
+        while self.check(&Token::Newline) {
+            self.advance();
+        }
+    }
+
+    /// Position of the current token, or of the end of input
+    fn position(&self) -> Position {
+        self.tokens
+            .get(self.current)
+            .map_or_else(|| self.end_pos.clone(), |located| located.start_pos.clone())
+    }
+
+    /// Build an error at the current token
+    fn error(&self, message: &str) -> ParseError {
+        ParseError::new(message, self.position(), self.peek().cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> Expr {
+        Parser::new(source).parse_expression().unwrap()
+    }
+
+    fn int(n: i64) -> Box<Expr> {
+        Box::new(Expr::Literal(Literal::Integer(n)))
+    }
+
+    fn binary(op: Token, left: Box<Expr>, right: Box<Expr>) -> Box<Expr> {
+        Box::new(Expr::Binary { op, left, right })
+    }
+
+    #[test]
+    fn test_precedence() {
+        assert_eq!(
+            parse("1 + 2 * 3"),
+            *binary(Token::Plus, int(1), binary(Token::Multiply, int(2), int(3)))
+        );
+        assert_eq!(
+            parse("1 * 2 - 3"),
+            *binary(Token::Minus, binary(Token::Multiply, int(1), int(2)), int(3))
+        );
+    }
+
+    #[test]
+    fn test_power_is_right_associative() {
+        assert_eq!(
+            parse("2 ** 3 ** 2"),
+            *binary(Token::Power, int(2), binary(Token::Power, int(3), int(2)))
+        );
+        assert_eq!(
+            parse("8 - 4 - 2"),
+            *binary(Token::Minus, binary(Token::Minus, int(8), int(4)), int(2))
+        );
+    }
+
+    #[test]
+    fn test_unary_and_grouping() {
+        assert_eq!(
+            parse("-(1 + 2) * 3"),
+            *binary(
+                Token::Multiply,
+                Box::new(Expr::Unary {
+                    op: Token::Minus,
+                    operand: Box::new(Expr::Grouping(binary(Token::Plus, int(1), int(2)))),
+                }),
+                int(3)
+            )
+        );
+        assert_eq!(
+            parse("!done && x"),
+            *binary(
+                Token::And,
+                Box::new(Expr::Unary {
+                    op: Token::Not,
+                    operand: Box::new(Expr::Identifier("done".to_string())),
+                }),
+                Box::new(Expr::Identifier("x".to_string()))
+            )
+        );
+    }
+
+    #[test]
+    fn test_calls() {
+        assert_eq!(
+            parse("fib(n - 1, \"x\",)"),
+            Expr::Call {
+                callee: Box::new(Expr::Identifier("fib".to_string())),
+                args: vec![
+                    *binary(Token::Minus, Box::new(Expr::Identifier("n".to_string())), int(1)),
+                    Expr::Literal(Literal::String("x".to_string())),
+                ],
+            }
+        );
+        assert_eq!(
+            parse("f()"),
+            Expr::Call {
+                callee: Box::new(Expr::Identifier("f".to_string())),
+                args: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn test_function_without_parameters() {
+        let func = Parser::new("func main() {\n    ret\n}").parse_function().unwrap();
+        assert_eq!(
+            func,
+            FuncDecl {
+                name: "main".to_string(),
+                params: vec![],
+                return_type: None,
+                body: vec![Stmt::Return(None)],
+            }
+        );
+    }
+
+    #[test]
+    fn test_function_with_typed_parameters() {
+        let source = "func add(a: i32, b: i32, label) -> i32 { ret a + b }";
+        let func = Parser::new(source).parse_function().unwrap();
+
+        assert_eq!(func.name, "add");
+        assert_eq!(
+            func.params,
+            vec![
+                ("a".to_string(), Some(TypeName::I32)),
+                ("b".to_string(), Some(TypeName::I32)),
+                ("label".to_string(), None),
+            ]
+        );
+        assert_eq!(func.return_type, Some(TypeName::I32));
+        assert_eq!(
+            func.body,
+            vec![Stmt::Return(Some(*binary(
+                Token::Plus,
+                Box::new(Expr::Identifier("a".to_string())),
+                Box::new(Expr::Identifier("b".to_string()))
+            )))]
+        );
+    }
+
+    #[test]
+    fn test_type_keywords_convert_to_type_names() {
+        let source = "i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 String bool char map set Point func";
+        let types: Vec<Option<TypeName>> = WidowLexer::tokenize_owned(source)
+            .unwrap()
+            .iter()
+            .map(TypeName::from_token)
+            .collect();
+
+        assert_eq!(
+            types,
+            vec![
+                Some(TypeName::I8),
+                Some(TypeName::I16),
+                Some(TypeName::I32),
+                Some(TypeName::I64),
+                Some(TypeName::U8),
+                Some(TypeName::U16),
+                Some(TypeName::U32),
+                Some(TypeName::U64),
+                Some(TypeName::F32),
+                Some(TypeName::F64),
+                Some(TypeName::String),
+                Some(TypeName::Bool),
+                Some(TypeName::Char),
+                Some(TypeName::Map),
+                Some(TypeName::Set),
+                Some(TypeName::Named("Point".to_string())),
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_function_missing_closing_brace() {
+        let err = Parser::new("func f() {\n    ret 1\n").parse_function().unwrap_err();
+        assert_eq!(err.message, "expected '}' to close function body");
+        assert_eq!(err.found, None);
+        assert_eq!(err.position.line, 3);
+    }
+
+    fn ident(name: &str) -> Box<Expr> {
+        Box::new(Expr::Identifier(name.to_string()))
+    }
+
+    #[test]
+    fn test_if_elif_else_chain() {
+        let source = "if a {\n    if b { ret 1 }\n}\nelif c {\n\n    ret 2\n} elif d {\n} else {\n    ret 3\n}";
+        let stmt = Parser::new(source).parse_statement().unwrap();
+
+        assert_eq!(
+            stmt,
+            Stmt::If {
+                condition: *ident("a"),
+                then_branch: vec![Stmt::If {
+                    condition: *ident("b"),
+                    then_branch: vec![Stmt::Return(Some(*int(1)))],
+                    elif_branches: vec![],
+                    else_branch: None,
+                }],
+                elif_branches: vec![
+                    (*ident("c"), vec![Stmt::Return(Some(*int(2)))]),
+                    (*ident("d"), vec![]),
+                ],
+                else_branch: Some(vec![Stmt::Return(Some(*int(3)))]),
+            }
+        );
+    }
+
+    #[test]
+    fn test_if_without_else_leaves_following_newline() {
+        let mut parser = Parser::new("if a { ret }\nx");
+        assert!(matches!(
+            parser.parse_statement().unwrap(),
+            Stmt::If { else_branch: None, .. }
+        ));
+        assert_eq!(parser.peek(), Some(&Token::Newline));
+    }
+
+    #[test]
+    fn test_while_with_compound_condition() {
+        let source = "while i < 10 && running {\n    tick(i)\n}";
+        let stmt = Parser::new(source).parse_statement().unwrap();
+
+        assert_eq!(
+            stmt,
+            Stmt::While {
+                condition: *binary(
+                    Token::And,
+                    binary(Token::Less, ident("i"), int(10)),
+                    ident("running")
+                ),
+                body: vec![Stmt::Expr(Expr::Call {
+                    callee: ident("tick"),
+                    args: vec![*ident("i")],
+                })],
+            }
+        );
+    }
+
+    #[test]
+    fn test_match_arms() {
+        let source = "match value {\n    1: \"one\"\n    2, 3: \"two\"\n    n if n > 10: \"big\"\n    _: \"else\"\n}";
+        let expr = Parser::new(source).parse_expression().unwrap();
+
+        let string = |s: &str| Expr::Literal(Literal::String(s.to_string()));
+        assert_eq!(
+            expr,
+            Expr::Match {
+                scrutinee: ident("value"),
+                arms: vec![
+                    MatchArm {
+                        patterns: vec![Pattern::Literal(Literal::Integer(1))],
+                        guard: None,
+                        body: string("one"),
+                    },
+                    MatchArm {
+                        patterns: vec![
+                            Pattern::Literal(Literal::Integer(2)),
+                            Pattern::Literal(Literal::Integer(3)),
+                        ],
+                        guard: None,
+                        body: string("two"),
+                    },
+                    MatchArm {
+                        patterns: vec![Pattern::Binding("n".to_string())],
+                        guard: Some(*binary(Token::Greater, ident("n"), int(10))),
+                        body: string("big"),
+                    },
+                    MatchArm {
+                        patterns: vec![Pattern::Wildcard],
+                        guard: None,
+                        body: string("else"),
+                    },
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn test_match_literal_patterns() {
+        let source = "match c { 'a', -1, true, nil: 0 }";
+        let Expr::Match { arms, .. } = Parser::new(source).parse_match().unwrap() else {
+            panic!("expected a match expression");
+        };
+
+        assert_eq!(
+            arms[0].patterns,
+            vec![
+                Pattern::Literal(Literal::Character('a')),
+                Pattern::Literal(Literal::Integer(-1)),
+                Pattern::Literal(Literal::Bool(true)),
+                Pattern::Literal(Literal::Nil),
+            ]
+        );
+
+        let err = Parser::new("match c { 1 \"one\" }").parse_match().unwrap_err();
+        assert_eq!(err.message, "expected ':' after match pattern");
+        assert_eq!(err.found, Some(Token::String("one".to_string())));
+    }
+
+    #[test]
+    fn test_array_literals() {
+        assert_eq!(parse("[]"), Expr::Array(vec![]));
+        assert_eq!(
+            parse("[1, [2, 3], [],\n]"),
+            Expr::Array(vec![
+                *int(1),
+                Expr::Array(vec![*int(2), *int(3)]),
+                Expr::Array(vec![]),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_map_literals() {
+        assert_eq!(parse("{}"), Expr::Map(vec![]));
+
+        let string = |s: &str| Expr::Literal(Literal::String(s.to_string()));
+        assert_eq!(
+            parse("{\"a\": 1,\n \"b\": [2],}"),
+            Expr::Map(vec![
+                (string("a"), *int(1)),
+                (string("b"), Expr::Array(vec![*int(2)])),
+            ])
+        );
+
+        let err = Parser::new("{\"a\" 1}").parse_expression().unwrap_err();
+        assert_eq!(err.message, "expected ':' after map key");
+    }
+
+    #[test]
+    fn test_block_in_expression_position() {
+        assert_eq!(
+            parse("{ x = 1\n x + 2 }"),
+            Expr::Block(vec![
+                Stmt::Expr(*binary(Token::Assign, ident("x"), int(1))),
+                Stmt::Expr(*binary(Token::Plus, ident("x"), int(2))),
+            ])
+        );
+        assert_eq!(parse("{\n  y\n}"), Expr::Block(vec![Stmt::Expr(*ident("y"))]));
+        assert!(matches!(parse("{ if a { b } }"), Expr::Block(body) if matches!(body[0], Stmt::If { .. })));
+
+        // A key on its own line before the ':' is still a map
+        assert_eq!(parse("{\n  k\n  : 1\n}"), Expr::Map(vec![(*ident("k"), *int(1))]));
+
+        // Each brace is parsed once, so deep nesting stays linear
+        let depth = 64;
+        let nested = format!("{}x{}", "{ ".repeat(depth), " }".repeat(depth));
+        let mut expr = parse(&nested);
+        for _ in 0..depth {
+            expr = match expr {
+                Expr::Block(mut body) if body.len() == 1 => match body.remove(0) {
+                    Stmt::Expr(inner) => inner,
+                    stmt => panic!("unexpected statement {:?}", stmt),
+                },
+                other => panic!("expected a block, got {:?}", other),
+            };
+        }
+        assert_eq!(expr, *ident("x"));
+
+        let (statements, errors) = Parser::new("v = { w }\n").parse_program();
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(
+            statements,
+            vec![Stmt::Expr(*binary(
+                Token::Assign,
+                ident("v"),
+                Box::new(Expr::Block(vec![Stmt::Expr(*ident("w"))]))
+            ))]
+        );
+    }
+
+    #[test]
+    fn test_program_reports_multiple_errors() {
+        let source = "x = 1 )\ny = 2\nz = * 3\nfunc f() { ret w }\n";
+        let (statements, errors) = Parser::new(source).parse_program();
+
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].position.line, 1);
+        assert_eq!(errors[0].message, "expected newline or ';' after statement");
+        assert_eq!(errors[0].found, Some(Token::RightParen));
+        assert_eq!(errors[1].position.line, 3);
+        assert_eq!(errors[1].to_string(), "3:5: expected expression, found *");
+
+        assert_eq!(statements.len(), 2);
+        assert!(matches!(&statements[1], Stmt::Func(func) if func.name == "f"));
+    }
+
+    #[test]
+    fn test_expression_errors() {
+        let err = Parser::new("1 + ").parse_expression().unwrap_err();
+        assert_eq!(err.to_string(), "1:4: expected expression, found end of input");
+
+        let err = Parser::new("(1 + 2").parse_expression().unwrap_err();
+        assert_eq!(err.message, "expected ')' to close grouping");
+        assert_eq!(err.position.column, 7);
+    }
+}
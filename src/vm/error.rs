@@ -1,9 +1,32 @@
+use std::io;
+use std::sync::Arc;
+
+/// An I/O error shared behind an `Arc` so `VMError` stays `Clone`
+///
+/// Two `IoError`s compare equal when their `io::ErrorKind`s match.
+#[derive(Debug, Clone)]
+pub struct IoError(Arc<io::Error>);
+
+impl IoError {
+    /// Kind of the underlying I/O error
+    pub fn kind(&self) -> io::ErrorKind {
+        self.0.kind()
+    }
+}
+
+impl PartialEq for IoError {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind() == other.kind()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum VMError {
     // Memory related errors
     InvalidMemoryAddress(u32),
     MemoryAccessViolation(u32),
     OutOfMemory,
+    InvalidMemoryLayout(String),
     
     // Register related errors
     InvalidRegister(u8),
@@ -19,18 +42,25 @@ pub enum VMError {
     InvalidBranchOffset(i16),
     
     // System errors
-    IOError(String),
+    Io(IoError),
+    InvalidInput(String), // READ got a line that isn't an integer
     SystemCallError(String),
     
     // Runtime errors
     ProgramHalted,
     InvalidOpcode(u8),
+    MalformedProgram(String),
+    InstructionLimitExceeded(u64),
+    CallStackOverflow(u32), // Call depth limit that was exceeded
+    ExecutedPastProgram(u32), // PC reached or passed the end of the loaded program
     
     // Memory allocation errors
     AllocationFailed(u32), // Failed to allocate N bytes
     FreeFailed(u32),       // Failed to free address
     DoubleFree(u32),       // Attempted to free already freed memory
     UseAfterFree(u32),     // Attempted to use freed memory
+    HeapCorruption(u32),   // Guard word after the block at this address was overwritten
+    UnknownSnapshot,       // Snapshot was already restored or released
 }
 
 impl std::fmt::Display for VMError {
@@ -43,6 +73,7 @@ impl std::fmt::Display for VMError {
                 write!(f, "Memory access violation at address: 0x{:08X}", addr)
             }
             VMError::OutOfMemory => write!(f, "Out of memory"),
+            VMError::InvalidMemoryLayout(msg) => write!(f, "Invalid memory layout: {}", msg),
             VMError::InvalidRegister(reg) => write!(f, "Invalid register: R{}", reg),
             VMError::DivisionByZero => write!(f, "Division by zero"),
             VMError::InvalidInstruction(bits) => {
@@ -56,10 +87,21 @@ impl std::fmt::Display for VMError {
             VMError::InvalidBranchOffset(offset) => {
                 write!(f, "Invalid branch offset: {}", offset)
             }
-            VMError::IOError(msg) => write!(f, "I/O error: {}", msg),
+            VMError::Io(err) => write!(f, "I/O error: {}", err.0),
+            VMError::InvalidInput(msg) => write!(f, "Invalid input: {}", msg),
             VMError::SystemCallError(msg) => write!(f, "System call error: {}", msg),
             VMError::ProgramHalted => write!(f, "Program execution halted"),
             VMError::InvalidOpcode(opcode) => write!(f, "Invalid opcode: 0x{:02X}", opcode),
+            VMError::MalformedProgram(msg) => write!(f, "Malformed program: {}", msg),
+            VMError::InstructionLimitExceeded(limit) => {
+                write!(f, "Instruction limit of {} exceeded", limit)
+            }
+            VMError::CallStackOverflow(limit) => {
+                write!(f, "Call stack overflow: depth limit of {} exceeded", limit)
+            }
+            VMError::ExecutedPastProgram(pc) => {
+                write!(f, "Executed past the end of the program at 0x{:08X} (missing HALT?)", pc)
+            }
             VMError::AllocationFailed(size) => {
                 write!(f, "Memory allocation failed for {} bytes", size)
             }
@@ -72,10 +114,86 @@ impl std::fmt::Display for VMError {
             VMError::UseAfterFree(addr) => {
                 write!(f, "Use after free detected at address: 0x{:08X}", addr)
             }
+            VMError::HeapCorruption(addr) => {
+                write!(f, "Heap corruption detected: guard word after block 0x{:08X} was overwritten", addr)
+            }
+            VMError::UnknownSnapshot => {
+                write!(f, "Snapshot was already restored or released")
+            }
         }
     }
 }
 
-impl std::error::Error for VMError {}
+impl std::error::Error for VMError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            VMError::Io(err) => Some(&*err.0),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for VMError {
+    fn from(err: io::Error) -> Self {
+        VMError::Io(IoError(Arc::new(err)))
+    }
+}
+
+pub type VMResult<T> = Result<T, VMError>;
+
+/// A `VMError` raised while executing, with where it happened
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeError {
+    /// Address of the instruction that faulted
+    pub pc: u32,
+    /// Instructions executed so far, including the faulting one
+    pub instruction_count: u64,
+    pub kind: VMError,
+}
+
+impl std::fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{} at PC 0x{:08X} (after {} instructions)",
+            self.kind, self.pc, self.instruction_count
+        )
+    }
+}
+
+impl std::error::Error for RuntimeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.kind)
+    }
+}
+
+pub type RuntimeResult<T> = Result<T, RuntimeError>;
+
+/// A problem found by `VM::validate_program`, located by instruction address
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationError {
+    /// The word at `address` is not a valid instruction
+    InvalidInstruction { address: u32, word: u32 },
+    /// A jump or branch at `address` targets an address that is not word-aligned
+    MisalignedTarget { address: u32, target: i64 },
+    /// A jump or branch at `address` targets an address outside the program
+    TargetOutOfRange { address: u32, target: i64 },
+}
+
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ValidationError::InvalidInstruction { address, word } => {
+                write!(f, "Invalid instruction 0x{:08X} at 0x{:08X}", word, address)
+            }
+            ValidationError::MisalignedTarget { address, target } => {
+                write!(f, "Misaligned jump target {} at 0x{:08X}", target, address)
+            }
+            ValidationError::TargetOutOfRange { address, target } => {
+                write!(f, "Jump target {} outside the program at 0x{:08X}", target, address)
+            }
+        }
+    }
+}
 
-pub type VMResult<T> = Result<T, VMError>;
\ No newline at end of file
+impl std::error::Error for ValidationError {}
\ No newline at end of file
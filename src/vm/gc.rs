@@ -18,6 +18,21 @@ pub struct ObjectMetadata {
     pub marked: bool,
     pub generation: u8,       // For generational GC (0 = young, higher = older)
     pub references: Vec<u32>, // Addresses this object references
+    pub weak_references: Vec<u32>, // Referenced addresses that don't keep the target alive
+}
+
+/// How the collector reclaims unreachable objects
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GcStrategy {
+    /// Free unreachable objects in place
+    #[default]
+    MarkSweep,
+    /// Free unreachable objects, then slide survivors down to close the gaps
+    MarkCompact,
+    /// Free objects as soon as their reference count drops to zero
+    ///
+    /// Cycles are never freed by counting alone; `collect` still traces them.
+    RefCount,
 }
 
 /// Garbage collector configuration
@@ -31,6 +46,16 @@ pub struct GCConfig {
     pub max_heap_size: u32,
     /// Enable concurrent collection (simulated)
     pub concurrent: bool,
+    /// Treat heap words that point into the heap as references while marking
+    pub scan_memory: bool,
+    /// Sweep in place or compact the heap after sweeping
+    pub strategy: GcStrategy,
+    /// Collect incrementally, scanning at most this many objects per VM step
+    pub incremental_budget: Option<usize>,
+    /// Also collect after this many allocations, regardless of heap usage
+    pub collect_every_n_allocations: Option<u64>,
+    /// Objects of at least this many bytes go in the large-object space
+    pub large_object_threshold: u32,
 }
 
 impl Default for GCConfig {
@@ -40,20 +65,27 @@ impl Default for GCConfig {
             generational: true,
             max_heap_size: 64 * 1024 * 1024, // 64MB
             concurrent: false,               // Keep simple for now
+            scan_memory: false,              // Only follow explicit add_reference edges
+            strategy: GcStrategy::MarkSweep,
+            incremental_budget: None,        // Stop-the-world collections
+            collect_every_n_allocations: None, // Trigger on heap usage only
+            large_object_threshold: 64 * 1024, // 64KB
         }
     }
 }
 
 /// Garbage collector statistics
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct GCStats {
     pub collections_performed: u64,
     pub objects_collected: u64,
     pub bytes_collected: u64,
-    pub last_collection_time_ms: u64,
-    pub total_pause_time_ms: u64,
+    pub last_collection_time_us: u64,
+    pub total_pause_time_us: u64,
     pub heap_size_before: u32,
     pub heap_size_after: u32,
+    /// Pause counts by duration; bucket 0 holds 0us pauses, bucket i holds [2^(i-1), 2^i) us
+    pub pause_histogram: [u64; PAUSE_BUCKETS],
 }
 
 impl Default for GCStats {
@@ -62,16 +94,74 @@ impl Default for GCStats {
             collections_performed: 0,
             objects_collected: 0,
             bytes_collected: 0,
-            last_collection_time_ms: 0,
-            total_pause_time_ms: 0,
+            last_collection_time_us: 0,
+            total_pause_time_us: 0,
             heap_size_before: 0,
             heap_size_after: 0,
+            pause_histogram: [0; PAUSE_BUCKETS],
+        }
+    }
+}
+
+/// Number of power-of-two buckets in the pause histogram
+pub const PAUSE_BUCKETS: usize = 32;
+
+impl GCStats {
+    /// Record a collection pause in the histogram
+    fn record_pause(&mut self, pause_us: u64) {
+        let bucket = (u64::BITS - pause_us.leading_zeros()) as usize;
+        self.pause_histogram[bucket.min(PAUSE_BUCKETS - 1)] += 1;
+    }
+
+    /// Pause time in microseconds at or below which `p` percent of pauses fall
+    ///
+    /// The result is the upper bound of the matching histogram bucket, so it
+    /// overestimates by less than a factor of two. Returns 0 before any collection.
+    pub fn percentile(&self, p: f64) -> u64 {
+        let total: u64 = self.pause_histogram.iter().sum();
+        if total == 0 {
+            return 0;
+        }
+
+        let rank = ((p.clamp(0.0, 100.0) / 100.0 * total as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (bucket, &count) in self.pause_histogram.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return if bucket == 0 { 0 } else { (1u64 << bucket) - 1 };
+            }
         }
+
+        u64::MAX
     }
 }
 
+/// First generation treated as old by minor collections
+const OLD_GENERATION: u8 = 2;
+
+/// Progress of an incremental collection spread over several calls
+#[derive(Debug, Clone, Copy)]
+struct IncrementalCycle {
+    heap_before: u32,
+    elapsed: std::time::Duration,
+}
+
+/// Something the collector did, reported to an event listener
+#[derive(Debug, Clone, PartialEq)]
+pub enum GcEvent {
+    CollectionStarted,
+    /// An object was found reachable
+    ObjectMarked(u32),
+    /// An object was freed: (address, size)
+    ObjectSwept(u32, u32),
+    /// A collection completed, with the statistics after it
+    CollectionFinished(Box<GCStats>),
+}
+
+/// Callback receiving collector events
+pub type GcEventListener = Box<dyn FnMut(GcEvent)>;
+
 /// Tricolor mark-and-sweep garbage collector
-#[derive(Debug)]
 pub struct GarbageCollector {
     /// Object metadata table
     objects: HashMap<u32, ObjectMetadata>,
@@ -87,9 +177,76 @@ pub struct GarbageCollector {
     write_barrier_log: Vec<(u32, u32)>, // (object, new_reference)
     /// Generation counters
     generation_sizes: [u32; 8], // Support up to 8 generations
+    /// Objects moved by compaction (old -> new address) not yet taken by the host
+    relocations: HashMap<u32, u32>,
+    /// Weak references (from, to) whose target was collected, not yet taken by the host
+    cleared_weaks: Vec<(u32, u32)>,
+    /// In-progress incremental collection, if any
+    incremental: Option<IncrementalCycle>,
+    /// Old objects that may hold references to young objects
+    remembered_set: HashSet<u32>,
+    /// Objects that are never collected or moved while pinned
+    pinned: HashSet<u32>,
+    /// Incoming reference counts, maintained under `GcStrategy::RefCount`
+    ref_counts: HashMap<u32, u32>,
+    /// Optional observer notified as collections progress
+    event_listener: Option<GcEventListener>,
+    /// Large objects, which start old and are never promoted or moved
+    large_objects: HashSet<u32>,
+}
+
+impl std::fmt::Debug for GarbageCollector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GarbageCollector")
+            .field("objects", &self.objects)
+            .field("config", &self.config)
+            .field("stats", &self.stats)
+            .field("gray_queue", &self.gray_queue)
+            .field("root_set", &self.root_set)
+            .field("write_barrier_log", &self.write_barrier_log)
+            .field("generation_sizes", &self.generation_sizes)
+            .field("relocations", &self.relocations)
+            .field("cleared_weaks", &self.cleared_weaks)
+            .field("incremental", &self.incremental)
+            .field("remembered_set", &self.remembered_set)
+            .field("pinned", &self.pinned)
+            .field("ref_counts", &self.ref_counts)
+            .field("large_objects", &self.large_objects)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Clones carry the full collector state but not the event listener
+impl Clone for GarbageCollector {
+    fn clone(&self) -> Self {
+        Self {
+            objects: self.objects.clone(),
+            config: self.config.clone(),
+            stats: self.stats.clone(),
+            gray_queue: self.gray_queue.clone(),
+            root_set: self.root_set.clone(),
+            write_barrier_log: self.write_barrier_log.clone(),
+            generation_sizes: self.generation_sizes,
+            relocations: self.relocations.clone(),
+            cleared_weaks: self.cleared_weaks.clone(),
+            incremental: self.incremental,
+            remembered_set: self.remembered_set.clone(),
+            pinned: self.pinned.clone(),
+            ref_counts: self.ref_counts.clone(),
+            event_listener: None,
+            large_objects: self.large_objects.clone(),
+        }
+    }
 }
 
 impl GarbageCollector {
+    /// Replace this collector's state with a copy of `other`, keeping the current event listener
+    pub fn copy_from(&mut self, other: &GarbageCollector) {
+        let listener = self.event_listener.take();
+        *self = other.clone();
+        self.event_listener = listener;
+    }
+
     /// Create a new garbage collector
     pub fn new(config: GCConfig) -> Self {
         Self {
@@ -100,6 +257,14 @@ impl GarbageCollector {
             root_set: HashSet::new(),
             write_barrier_log: Vec::new(),
             generation_sizes: [0; 8],
+            relocations: HashMap::new(),
+            cleared_weaks: Vec::new(),
+            incremental: None,
+            remembered_set: HashSet::new(),
+            pinned: HashSet::new(),
+            ref_counts: HashMap::new(),
+            event_listener: None,
+            large_objects: HashSet::new(),
         }
     }
 
@@ -110,21 +275,83 @@ impl GarbageCollector {
 
     /// Register a new heap object
     pub fn register_object(&mut self, address: u32, size: u32) {
+        // Objects allocated during an incremental cycle survive it
+        let allocated_black = self.incremental.is_some();
+
+        // Large objects skip the nursery so minor collections never copy them
+        let large = size >= self.config.large_object_threshold;
+        let generation = if large { OLD_GENERATION } else { 0 }; // New objects start in generation 0
+        if large {
+            self.large_objects.insert(address);
+        }
+
         let metadata = ObjectMetadata {
             address,
             size,
-            color: ObjectColor::White,
-            marked: false,
-            generation: 0, // New objects start in generation 0
+            color: if allocated_black { ObjectColor::Black } else { ObjectColor::White },
+            marked: allocated_black,
+            generation,
             references: Vec::new(),
+            weak_references: Vec::new(),
         };
 
         self.objects.insert(address, metadata);
-        self.generation_sizes[0] += size;
+        self.generation_sizes[generation as usize] += size;
+    }
+
+    /// Check whether an object lives in the large-object space
+    pub fn is_large_object(&self, address: u32) -> bool {
+        self.large_objects.contains(&address)
+    }
+
+    /// Get number of tracked large objects
+    pub fn large_object_count(&self) -> usize {
+        self.large_objects.len()
+    }
+
+    /// Follow an object to a new address and size after it was reallocated
+    ///
+    /// Generation, color, pin state, reference count and references to and from
+    /// the object all carry over. An address the collector does not track is
+    /// registered as a new object.
+    pub fn move_object(&mut self, address: u32, new_address: u32, new_size: u32) {
+        let Some(mut obj) = self.objects.remove(&address) else {
+            self.register_object(new_address, new_size);
+            return;
+        };
+
+        let generation = obj.generation as usize;
+        self.generation_sizes[generation] =
+            self.generation_sizes[generation].saturating_sub(obj.size) + new_size;
+        obj.size = new_size;
+        self.objects.insert(address, obj);
+
+        let moved = HashMap::from([(address, new_address)]);
+        self.objects = self
+            .objects
+            .drain()
+            .map(|(_, mut obj)| {
+                relocate_object(&mut obj, &moved);
+                (obj.address, obj)
+            })
+            .collect();
+
+        for set in [&mut self.pinned, &mut self.large_objects, &mut self.remembered_set, &mut self.root_set] {
+            if set.remove(&address) {
+                set.insert(new_address);
+            }
+        }
+        if let Some(count) = self.ref_counts.remove(&address) {
+            self.ref_counts.insert(new_address, count);
+        }
     }
 
     /// Remove an object (when manually freed)
     pub fn unregister_object(&mut self, address: u32) {
+        self.remembered_set.remove(&address);
+        self.pinned.remove(&address);
+        self.ref_counts.remove(&address);
+        self.large_objects.remove(&address);
         if let Some(obj) = self.objects.remove(&address) {
             if (obj.generation as usize) < self.generation_sizes.len() {
                 self.generation_sizes[obj.generation as usize] =
@@ -138,31 +365,186 @@ impl GarbageCollector {
         if let Some(obj) = self.objects.get_mut(&from) {
             if !obj.references.contains(&to) {
                 obj.references.push(to);
+                if self.config.strategy == GcStrategy::RefCount {
+                    self.retain(to);
+                }
             }
         }
 
-        // Write barrier for concurrent collection
-        if self.config.concurrent {
+        // Write barrier for concurrent and incremental collection
+        if self.config.concurrent || self.incremental.is_some() {
             self.write_barrier_log.push((from, to));
         }
+
+        // Remember old -> young references for minor collections
+        if self.is_old(from) && !self.is_old(to) {
+            self.remembered_set.insert(from);
+        }
+    }
+
+    /// Write barrier for a word the program stored at `address`
+    ///
+    /// With `scan_memory`, object words are references too, so a heap address
+    /// stored into an object that an incremental cycle has already scanned would
+    /// otherwise go unnoticed and its target could be swept. Stores of young
+    /// objects into old ones are remembered for minor collections.
+    pub fn record_store(&mut self, address: u32, value: u32) {
+        if !self.config.scan_memory || !self.objects.contains_key(&value) {
+            return;
+        }
+
+        let container = self
+            .objects
+            .values()
+            .find(|obj| (obj.address..obj.address + obj.size).contains(&address))
+            .map(|obj| obj.address);
+        let Some(from) = container else {
+            return;
+        };
+
+        if self.config.concurrent || self.incremental.is_some() {
+            self.write_barrier_log.push((from, value));
+        }
+        if self.is_old(from) && !self.is_old(value) {
+            self.remembered_set.insert(from);
+        }
     }
 
     /// Remove a reference
+    ///
+    /// Under `GcStrategy::RefCount` the target's count drops, but an object left
+    /// unreferenced is only reclaimed by the next collection; use
+    /// `release_reference` to free it straight away.
     pub fn remove_reference(&mut self, from: u32, to: u32) {
+        if self.unlink(from, to)
+            && self.config.strategy == GcStrategy::RefCount
+            && let Some(count) = self.ref_counts.get_mut(&to)
+        {
+            *count = count.saturating_sub(1);
+        }
+    }
+
+    /// Remove a reference, freeing the target immediately under
+    /// `GcStrategy::RefCount` if this was its last one
+    pub fn release_reference(&mut self, from: u32, to: u32, memory: &mut Memory) -> VMResult<()> {
+        if self.unlink(from, to) && self.config.strategy == GcStrategy::RefCount {
+            self.release(to, memory)?;
+        }
+        Ok(())
+    }
+
+    /// Drop the edge `from -> to`, returning whether there was one
+    fn unlink(&mut self, from: u32, to: u32) -> bool {
+        match self.objects.get_mut(&from) {
+            Some(obj) => {
+                let before = obj.references.len();
+                obj.references.retain(|&addr| addr != to);
+                obj.references.len() != before
+            }
+            None => false,
+        }
+    }
+
+    /// Count a reference to an object held outside the heap, such as by the host
+    pub fn retain(&mut self, address: u32) {
+        if self.objects.contains_key(&address) {
+            *self.ref_counts.entry(address).or_insert(0) += 1;
+        }
+    }
+
+    /// Drop a counted reference, freeing the object and its now-unreferenced
+    /// children when counts reach zero
+    pub fn release(&mut self, address: u32, memory: &mut Memory) -> VMResult<()> {
+        let mut pending = vec![address];
+
+        while let Some(addr) = pending.pop() {
+            let Some(count) = self.ref_counts.get_mut(&addr) else {
+                continue;
+            };
+            *count = count.saturating_sub(1);
+            if *count > 0 || self.pinned.contains(&addr) {
+                continue;
+            }
+
+            if let Some(obj) = self.objects.get(&addr) {
+                pending.extend(obj.references.iter().copied());
+                self.stats.objects_collected += 1;
+                self.stats.bytes_collected += obj.size as u64;
+            }
+            self.unregister_object(addr);
+            memory.free(addr)?;
+        }
+
+        Ok(())
+    }
+
+    /// Get the reference count of an object
+    pub fn ref_count(&self, address: u32) -> u32 {
+        self.ref_counts.get(&address).copied().unwrap_or(0)
+    }
+
+    /// Objects kept alive by `retain` calls, i.e. counted references that do not
+    /// come from another object's `add_reference` edge
+    fn retained_objects(&self) -> Vec<u32> {
+        let mut external = self.ref_counts.clone();
+        if self.config.strategy == GcStrategy::RefCount {
+            for to in self.objects.values().flat_map(|obj| &obj.references) {
+                if let Some(count) = external.get_mut(to) {
+                    *count = count.saturating_sub(1);
+                }
+            }
+        }
+        external.into_iter().filter(|&(_, count)| count > 0).map(|(addr, _)| addr).collect()
+    }
+
+    /// Pin an object so it is treated as a root and never moved by compaction
+    pub fn pin(&mut self, address: u32) {
+        self.pinned.insert(address);
+    }
+
+    /// Unpin an object, returning whether it was pinned
+    pub fn unpin(&mut self, address: u32) -> bool {
+        self.pinned.remove(&address)
+    }
+
+    /// Check whether an object is pinned
+    pub fn is_pinned(&self, address: u32) -> bool {
+        self.pinned.contains(&address)
+    }
+
+    /// Add a weak reference, which the mark phase does not follow
+    pub fn add_weak_reference(&mut self, from: u32, to: u32) {
+        if let Some(obj) = self.objects.get_mut(&from)
+            && !obj.weak_references.contains(&to)
+        {
+            obj.weak_references.push(to);
+        }
+    }
+
+    /// Remove a weak reference
+    pub fn remove_weak_reference(&mut self, from: u32, to: u32) {
         if let Some(obj) = self.objects.get_mut(&from) {
-            obj.references.retain(|&addr| addr != to);
+            obj.weak_references.retain(|&addr| addr != to);
         }
     }
 
+    /// Take the weak references (from, to) cleared because their target was collected
+    pub fn take_cleared_weaks(&mut self) -> Vec<(u32, u32)> {
+        std::mem::take(&mut self.cleared_weaks)
+    }
+
     /// Check if garbage collection should be triggered
     pub fn should_collect(&self, memory: &Memory) -> bool {
         let stats = memory.get_stats();
 
         if self.config.max_heap_size > 0 {
             // Proceed only if max_heap_size is configured
-            // Calculate the threshold in absolute bytes based on configured max_heap_size
-            let heap_used_trigger_point =
-                (self.config.max_heap_size as f32 * self.config.gc_threshold) as u32;
+            // The heap can't grow past its region, however large max_heap_size is
+            let (heap_start, heap_end) = memory.heap_region();
+            let max_heap_size = self.config.max_heap_size.min(heap_end - heap_start);
+
+            // Calculate the threshold in absolute bytes based on the effective max heap size
+            let heap_used_trigger_point = (max_heap_size as f32 * self.config.gc_threshold) as u32;
 
             // Condition 1: Trigger if heap usage reaches the calculated trigger point
             if stats.heap_used >= heap_used_trigger_point {
@@ -171,7 +553,7 @@ impl GarbageCollector {
 
             // Condition 2: Trigger if heap usage meets or exceeds the absolute max_heap_size
             // This is a safeguard and handles cases where threshold might be >= 1.0
-            if stats.heap_used >= self.config.max_heap_size {
+            if stats.heap_used >= max_heap_size {
                 return true;
             }
         }
@@ -184,17 +566,122 @@ impl GarbageCollector {
         let start_time = std::time::Instant::now();
         let heap_before = memory.get_stats().heap_used;
 
+        // A full collection supersedes any incremental cycle in progress
+        self.incremental = None;
+        self.emit(GcEvent::CollectionStarted);
+
         // Phase 1: Build root set
         self.build_root_set(memory, registers)?;
 
         // Phase 2: Mark phase (tricolor algorithm)
-        self.mark_phase()?;
+        self.mark_phase(memory)?;
+
+        self.finish_collection(memory, heap_before, start_time.elapsed())
+    }
+
+    /// Run one increment of a collection, scanning at most `budget` gray objects
+    ///
+    /// Returns `true` once the cycle has finished marking and swept the heap. Roots
+    /// are rescanned before sweeping, references added through `add_reference`
+    /// between increments are caught by the write barrier, and objects registered
+    /// mid-cycle survive it.
+    pub fn collect_incremental(
+        &mut self,
+        memory: &mut Memory,
+        registers: &RegisterFile,
+        budget: usize,
+    ) -> VMResult<bool> {
+        let start_time = std::time::Instant::now();
+
+        let cycle = match self.incremental {
+            Some(cycle) => cycle,
+            None => {
+                self.emit(GcEvent::CollectionStarted);
+                let heap_before = memory.get_stats().heap_used;
+                self.build_root_set(memory, registers)?;
+                self.start_marking();
+                IncrementalCycle { heap_before, elapsed: std::time::Duration::ZERO }
+            }
+        };
+
+        // Shade the targets of references added since the last increment
+        for (_, to) in std::mem::take(&mut self.write_barrier_log) {
+            self.mark_gray(to);
+        }
+
+        for _ in 0..budget {
+            match self.gray_queue.pop_front() {
+                Some(addr) => self.mark_black(addr, memory)?,
+                None => break,
+            }
+        }
+
+        if self.gray_queue.is_empty() {
+            // Roots may have changed between increments
+            self.build_root_set(memory, registers)?;
+            let roots: Vec<u32> = self.root_set.iter().copied().collect();
+            for root in roots {
+                self.mark_gray(root);
+            }
+
+            if self.gray_queue.is_empty() {
+                self.incremental = None;
+                self.finish_collection(memory, cycle.heap_before, cycle.elapsed + start_time.elapsed())?;
+                return Ok(true);
+            }
+        }
+
+        self.incremental = Some(IncrementalCycle {
+            elapsed: cycle.elapsed + start_time.elapsed(),
+            ..cycle
+        });
+        Ok(false)
+    }
+
+    /// Install a listener notified of collection progress
+    pub fn set_event_listener(&mut self, listener: GcEventListener) {
+        self.event_listener = Some(listener);
+    }
+
+    /// Remove the event listener
+    pub fn clear_event_listener(&mut self) {
+        self.event_listener = None;
+    }
+
+    /// Report an event to the listener, if one is installed
+    fn emit(&mut self, event: GcEvent) {
+        if let Some(listener) = self.event_listener.as_mut() {
+            listener(event);
+        }
+    }
+
+    /// Check whether an incremental collection is in progress
+    pub fn is_collecting(&self) -> bool {
+        self.incremental.is_some()
+    }
+
+    /// Sweep, compact, and record statistics once marking is complete
+    fn finish_collection(
+        &mut self,
+        memory: &mut Memory,
+        heap_before: u32,
+        mark_time: std::time::Duration,
+    ) -> VMResult<()> {
+        let start_time = std::time::Instant::now();
+
+        // Refuse to sweep or move a heap that has been overrun
+        memory.check_canaries()?;
 
         // Phase 3: Sweep phase
         let collected = self.sweep_phase(memory)?;
 
+        // Phase 3b: Compact survivors towards the heap base
+        if self.config.strategy == GcStrategy::MarkCompact {
+            self.compact_phase(memory);
+        }
+
         // Phase 4: Update statistics
-        let collection_time = start_time.elapsed().as_millis() as u64;
+        let collection_time = (mark_time + start_time.elapsed()).as_micros() as u64;
         let heap_after = memory.get_stats().heap_used;
 
         self.update_stats(
@@ -207,7 +694,11 @@ impl GarbageCollector {
 
         // Phase 5: Promote surviving objects to next generation
         if self.config.generational {
-            self.promote_survivors();
+            self.promote_survivors(memory);
+        }
+
+        if self.event_listener.is_some() {
+            self.emit(GcEvent::CollectionFinished(Box::new(self.stats.clone())));
         }
 
         Ok(())
@@ -227,10 +718,8 @@ impl GarbageCollector {
             }
         }
 
-        // Add addresses from stack
-        let sp = memory.get_stack_pointer();
-        let stats = memory.get_stats();
-        let stack_base = stats.total_memory - (stats.total_memory / 4); // Approximate stack base
+        // Add addresses from the live stack region
+        let (stack_base, sp) = memory.stack_region();
 
         let mut current_sp = sp;
         while current_sp < stack_base {
@@ -242,11 +731,28 @@ impl GarbageCollector {
             current_sp += 4;
         }
 
+        // Pinned and retained objects are always reachable
+        self.root_set.extend(self.pinned.iter().copied());
+        let retained = self.retained_objects();
+        self.root_set.extend(retained);
+
         Ok(())
     }
 
     /// Mark phase using tricolor algorithm
-    fn mark_phase(&mut self) -> VMResult<()> {
+    fn mark_phase(&mut self, memory: &Memory) -> VMResult<()> {
+        self.start_marking();
+
+        // Process gray queue
+        while let Some(addr) = self.gray_queue.pop_front() {
+            self.mark_black(addr, memory)?;
+        }
+
+        Ok(())
+    }
+
+    /// Whiten all objects and seed the gray queue from the root set
+    fn start_marking(&mut self) {
         // Initialize: all objects are white, roots become gray
         for obj in self.objects.values_mut() {
             obj.color = ObjectColor::White;
@@ -270,13 +776,6 @@ impl GarbageCollector {
             }
         }
         self.write_barrier_log.clear();
-
-        // Process gray queue
-        while let Some(addr) = self.gray_queue.pop_front() {
-            self.mark_black(addr)?;
-        }
-
-        Ok(())
     }
 
     /// Mark an object as gray (reachable but not scanned)
@@ -286,18 +785,30 @@ impl GarbageCollector {
                 obj.color = ObjectColor::Gray;
                 obj.marked = true;
                 self.gray_queue.push_back(addr);
+                self.emit(GcEvent::ObjectMarked(addr));
             }
         }
     }
 
     /// Mark an object as black (reachable and scanned)
-    fn mark_black(&mut self, addr: u32) -> VMResult<()> {
+    fn mark_black(&mut self, addr: u32, memory: &Memory) -> VMResult<()> {
         if let Some(obj) = self.objects.get(&addr).cloned() {
             // Mark all referenced objects as gray
             for &ref_addr in &obj.references {
                 self.mark_gray(ref_addr);
             }
 
+            // Conservatively treat any word holding a heap address as a reference
+            if self.config.scan_memory {
+                for offset in (0..obj.size / 4).map(|i| i * 4) {
+                    if let Ok(value) = memory.read_word(addr + offset)
+                        && self.is_valid_heap_address(value, memory)
+                    {
+                        self.mark_gray(value);
+                    }
+                }
+            }
+
             // Mark this object as black
             if let Some(obj) = self.objects.get_mut(&addr) {
                 obj.color = ObjectColor::Black;
@@ -328,17 +839,122 @@ impl GarbageCollector {
         }
 
         // Remove collected objects from tracking
-        for addr in to_remove {
+        for &addr in &to_remove {
+            let size = self.objects.get(&addr).map_or(0, |obj| obj.size);
+            // Edges from a collected object no longer count towards its targets
+            if self.config.strategy == GcStrategy::RefCount
+                && let Some(obj) = self.objects.get(&addr)
+            {
+                for to in &obj.references {
+                    if let Some(count) = self.ref_counts.get_mut(to) {
+                        *count = count.saturating_sub(1);
+                    }
+                }
+            }
             self.unregister_object(addr);
+            self.emit(GcEvent::ObjectSwept(addr, size));
+        }
+
+        // Clear weak references to collected objects so the host can be notified
+        let collected: HashSet<u32> = to_remove.into_iter().collect();
+        for (&from, obj) in self.objects.iter_mut() {
+            obj.weak_references.retain(|to| {
+                let cleared = collected.contains(to);
+                if cleared {
+                    self.cleared_weaks.push((from, *to));
+                }
+                !cleared
+            });
         }
 
         Ok((objects_collected, bytes_collected))
     }
 
+    /// Compact the heap and move object metadata to the new addresses
+    ///
+    /// Only words known to hold a reference are rewritten: the slots of an object
+    /// that hold the address of one of its `add_reference` or weak targets. Roots
+    /// found in registers and on the stack, and targets found by `scan_memory`,
+    /// may just be integers that look like addresses, so their objects stay put.
+    fn compact_phase(&mut self, memory: &mut Memory) {
+        // Large objects are swept in place like pinned ones, and blocks the
+        // collector does not track have no known references to fix up
+        let mut fixed: HashSet<u32> = self.pinned.union(&self.large_objects).copied().collect();
+        fixed.extend(self.root_set.iter().copied());
+        fixed.extend(
+            memory
+                .live_allocations()
+                .into_iter()
+                .map(|(address, _, _)| address)
+                .filter(|address| !self.objects.contains_key(address)),
+        );
+
+        let mut pointer_slots = Vec::new();
+        for obj in self.objects.values() {
+            let targets: HashSet<u32> =
+                obj.references.iter().chain(&obj.weak_references).copied().collect();
+            for slot in (0..obj.size / 4).map(|i| obj.address + i * 4) {
+                let Ok(value) = memory.read_word(slot) else {
+                    continue;
+                };
+                if targets.contains(&value) {
+                    pointer_slots.push(slot);
+                } else if self.config.scan_memory && self.objects.contains_key(&value) {
+                    fixed.insert(value);
+                }
+            }
+        }
+
+        let relocations = memory.compact(&fixed, &pointer_slots);
+        if relocations.is_empty() {
+            return;
+        }
+
+        self.objects = self
+            .objects
+            .drain()
+            .map(|(_, mut obj)| {
+                relocate_object(&mut obj, &relocations);
+                (obj.address, obj)
+            })
+            .collect();
+        self.root_set = self
+            .root_set
+            .iter()
+            .map(|addr| relocations.get(addr).copied().unwrap_or(*addr))
+            .collect();
+        self.remembered_set = self
+            .remembered_set
+            .iter()
+            .map(|addr| relocations.get(addr).copied().unwrap_or(*addr))
+            .collect();
+
+        // Chain with relocations the host hasn't taken yet
+        for new_address in self.relocations.values_mut() {
+            if let Some(&moved) = relocations.get(new_address) {
+                *new_address = moved;
+            }
+        }
+        for (old, new) in relocations {
+            self.relocations.entry(old).or_insert(new);
+        }
+    }
+
+    /// Take the old -> new address map of objects moved since the last call
+    ///
+    /// Reference slots inside objects are fixed up by the collector; callers are
+    /// responsible for any host-side copies of object addresses. Objects reachable
+    /// from registers or the stack are never moved.
+    pub fn take_relocations(&mut self) -> HashMap<u32, u32> {
+        std::mem::take(&mut self.relocations)
+    }
+
     /// Promote surviving objects to next generation
-    fn promote_survivors(&mut self) {
+    fn promote_survivors(&mut self, memory: &Memory) {
+        let mut newly_old = Vec::new();
+
         for obj in self.objects.values_mut() {
-            if obj.marked && obj.generation < 7 {
+            if obj.marked && obj.generation < 7 && !self.large_objects.contains(&obj.address) {
                 // Move size from old generation to new
                 if (obj.generation as usize) < self.generation_sizes.len() {
                     self.generation_sizes[obj.generation as usize] =
@@ -350,8 +966,37 @@ impl GarbageCollector {
                 if (obj.generation as usize) < self.generation_sizes.len() {
                     self.generation_sizes[obj.generation as usize] += obj.size;
                 }
+
+                if obj.generation == OLD_GENERATION {
+                    newly_old.push(obj.address);
+                }
             }
         }
+
+        // Objects that just became old may already point at young objects
+        for addr in newly_old {
+            if !self.young_targets(addr, memory).is_empty() {
+                self.remembered_set.insert(addr);
+            }
+        }
+    }
+
+    /// Young objects that `addr` refers to, including words it holds when `scan_memory` is on
+    fn young_targets(&self, addr: u32, memory: &Memory) -> Vec<u32> {
+        let obj = &self.objects[&addr];
+        let mut targets = obj.references.clone();
+        if self.config.scan_memory {
+            targets.extend((0..obj.size / 4).filter_map(|i| memory.read_word(addr + i * 4).ok()));
+        }
+        targets.retain(|to| self.objects.contains_key(to) && !self.is_old(*to));
+        targets
+    }
+
+    /// Check whether a tracked object belongs to an old generation
+    fn is_old(&self, addr: u32) -> bool {
+        self.objects
+            .get(&addr)
+            .is_some_and(|obj| obj.generation >= OLD_GENERATION)
     }
 
     /// Update GC statistics
@@ -366,27 +1011,17 @@ impl GarbageCollector {
         self.stats.collections_performed += 1;
         self.stats.objects_collected += objects_collected;
         self.stats.bytes_collected += bytes_collected;
-        self.stats.last_collection_time_ms = collection_time;
-        self.stats.total_pause_time_ms += collection_time;
+        self.stats.last_collection_time_us = collection_time;
+        self.stats.total_pause_time_us += collection_time;
+        self.stats.record_pause(collection_time);
         self.stats.heap_size_before = heap_before;
         self.stats.heap_size_after = heap_after;
     }
 
     /// Check if an address is a valid heap address
     fn is_valid_heap_address(&self, addr: u32, memory: &Memory) -> bool {
-        let stats = memory.get_stats();
-        let heap_base = if stats.total_memory > 0x10000 {
-            0x10000
-        } else {
-            stats.total_memory / 4
-        };
-        let stack_base = if stats.total_memory > 0x100000 {
-            stats.total_memory - 0x100000
-        } else {
-            stats.total_memory * 3 / 4
-        };
-
-        addr >= heap_base && addr < stack_base
+        let (heap_start, heap_end) = memory.heap_region();
+        addr >= heap_start && addr < heap_end
     }
 
     /// Force garbage collection
@@ -400,25 +1035,57 @@ impl GarbageCollector {
             return self.collect(memory, registers);
         }
 
+        // Young objects referenced from the remembered set act as extra roots
+        self.remembered_set.retain(|addr| {
+            self.objects
+                .get(addr)
+                .is_some_and(|obj| obj.generation >= OLD_GENERATION)
+        });
+        let remembered_roots: Vec<u32> = self
+            .remembered_set
+            .iter()
+            .flat_map(|&addr| self.young_targets(addr, memory))
+            .collect();
+
         // Only collect generation 0 and 1 objects
         let old_objects: HashMap<u32, ObjectMetadata> = self.objects.clone();
 
         // Temporarily filter to only young objects
-        self.objects.retain(|_, obj| obj.generation <= 1);
+        self.objects.retain(|_, obj| obj.generation < OLD_GENERATION);
 
         // Perform collection
-        let result = self.collect(memory, registers);
+        let result = self.collect_young(memory, registers, remembered_roots);
 
-        // Restore old objects that weren't collected
-        for (addr, obj) in old_objects {
-            if obj.generation > 1 && !self.objects.contains_key(&addr) {
-                self.objects.insert(addr, obj);
+        // Restore old objects that weren't collected, following any compaction moves
+        for (_, mut obj) in old_objects {
+            if obj.generation > 1 {
+                relocate_object(&mut obj, &self.relocations);
+                self.objects.entry(obj.address).or_insert(obj);
             }
         }
 
         result
     }
 
+    /// Collect the young objects, treating `extra_roots` as reachable
+    fn collect_young(
+        &mut self,
+        memory: &mut Memory,
+        registers: &RegisterFile,
+        extra_roots: Vec<u32>,
+    ) -> VMResult<()> {
+        let start_time = std::time::Instant::now();
+        let heap_before = memory.get_stats().heap_used;
+        self.incremental = None;
+        self.emit(GcEvent::CollectionStarted);
+
+        self.build_root_set(memory, registers)?;
+        self.root_set.extend(extra_roots);
+        self.mark_phase(memory)?;
+
+        self.finish_collection(memory, heap_before, start_time.elapsed())
+    }
+
     /// Get GC configuration
     pub fn get_config(&self) -> &GCConfig {
         &self.config
@@ -471,8 +1138,10 @@ impl GarbageCollector {
             self.stats.bytes_collected
         ));
         output.push_str(&format!(
-            "Total pause time: {} ms\n",
-            self.stats.total_pause_time_ms
+            "Total pause time: {} us (p50 {} us, p99 {} us)\n",
+            self.stats.total_pause_time_us,
+            self.stats.percentile(50.0),
+            self.stats.percentile(99.0)
         ));
 
         if self.config.generational {
@@ -522,9 +1191,20 @@ impl GarbageCollector {
     }
 }
 
+/// Rewrite an object's address and references after compaction
+fn relocate_object(obj: &mut ObjectMetadata, relocations: &HashMap<u32, u32>) {
+    let relocate = |addr: u32| relocations.get(&addr).copied().unwrap_or(addr);
+
+    obj.address = relocate(obj.address);
+    for reference in obj.references.iter_mut().chain(obj.weak_references.iter_mut()) {
+        *reference = relocate(*reference);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::vm::error::VMError;
     use crate::vm::memory::Memory;
     use crate::vm::registers::RegisterFile;
 
@@ -581,6 +1261,20 @@ mod tests {
         assert!(gc.get_stats().collections_performed > 0);
     }
 
+    #[test]
+    fn test_collection_detects_heap_corruption() {
+        let mut memory = Memory::new(1024 * 1024);
+        let registers = RegisterFile::new();
+        let mut gc = GarbageCollector::new_default();
+        memory.set_heap_canaries(true);
+
+        let addr = memory.allocate(16).unwrap();
+        gc.register_object(addr, 16);
+        memory.write_word(addr + 16, 0).unwrap();
+
+        assert_eq!(gc.collect(&mut memory, &registers), Err(VMError::HeapCorruption(addr)));
+    }
+
     #[test]
     fn test_reachable_objects() {
         let mut memory = Memory::new(1024 * 1024);
@@ -608,16 +1302,548 @@ mod tests {
         assert_eq!(gc.object_count(), 2);
     }
 
+    #[test]
+    fn test_stack_roots_survive_collection() {
+        let mut memory = Memory::new(1024 * 1024);
+        let registers = RegisterFile::new();
+        let mut gc = GarbageCollector::new_default();
+
+        let on_stack = memory.allocate(64).unwrap();
+        let garbage = memory.allocate(64).unwrap();
+        gc.register_object(on_stack, 64);
+        gc.register_object(garbage, 64);
+
+        // Only referenced from the stack
+        memory.stack_push(on_stack).unwrap();
+
+        gc.collect(&mut memory, &registers).unwrap();
+
+        assert_eq!(gc.object_count(), 1);
+        assert!(gc.objects.contains_key(&on_stack));
+        assert!(memory.is_valid_address(on_stack));
+        assert!(!memory.is_valid_address(garbage));
+    }
+
+    #[test]
+    fn test_mark_compact() {
+        let mut memory = Memory::new(1024 * 1024);
+        let mut registers = RegisterFile::new();
+        let config = GCConfig {
+            strategy: GcStrategy::MarkCompact,
+            ..Default::default()
+        };
+        let mut gc = GarbageCollector::new(config);
+
+        // Fragment the heap: live, garbage, live, garbage
+        let addrs: Vec<u32> = (0..4).map(|_| memory.allocate(64).unwrap()).collect();
+        for &addr in &addrs {
+            gc.register_object(addr, 64);
+        }
+        memory.write_word(addrs[2] + 4, 0xDEADBEEF).unwrap();
+        memory.write_word(addrs[0], addrs[2]).unwrap(); // in-object pointer
+        gc.add_reference(addrs[0], addrs[2]);
+        registers.write(1, addrs[0] as i32).unwrap();
+        let heap_before = memory.get_stats().heap_used;
+
+        gc.collect(&mut memory, &registers).unwrap();
+
+        let relocations = gc.take_relocations();
+        assert_eq!(relocations.len(), 1);
+        let moved = relocations[&addrs[2]];
+        assert_eq!(moved, addrs[1]);
+
+        // Data and pointers follow the moved object
+        assert_eq!(memory.read_word(moved + 4).unwrap(), 0xDEADBEEF);
+        assert_eq!(memory.read_word(addrs[0]).unwrap(), moved);
+        assert!(gc.objects[&addrs[0]].references.contains(&moved));
+        assert!(gc.objects.contains_key(&moved));
+        assert_eq!(gc.object_count(), 2);
+
+        assert_eq!(memory.get_stats().heap_used, 128);
+        assert!(memory.get_stats().heap_used < heap_before);
+        assert!(gc.take_relocations().is_empty());
+    }
+
+    #[test]
+    fn test_mark_compact_leaves_integers_alone() {
+        let mut memory = Memory::new(1024 * 1024);
+        let mut registers = RegisterFile::new();
+        let mut gc = GarbageCollector::new(GCConfig {
+            strategy: GcStrategy::MarkCompact,
+            ..GCConfig::default()
+        });
+
+        // root -> moved, with `counter` holding an integer equal to moved's address
+        let root = memory.allocate(16).unwrap();
+        let garbage = memory.allocate(16).unwrap();
+        let moved = memory.allocate(16).unwrap();
+        let counter = memory.allocate(16).unwrap();
+        for addr in [root, garbage, moved, counter] {
+            gc.register_object(addr, 16);
+        }
+        memory.write_word(root, moved).unwrap();
+        memory.write_word(root + 4, moved).unwrap();
+        gc.add_reference(root, moved);
+        gc.add_reference(root, counter);
+        memory.write_word(counter, moved).unwrap();
+        registers.write(1, root as i32).unwrap();
+        memory.stack_push(moved + 4).unwrap();
+        memory.stack_push(moved).unwrap();
+
+        gc.collect(&mut memory, &registers).unwrap();
+        let relocations = gc.take_relocations();
+
+        // The stack word keeps `moved` in place; the register keeps `root` in place
+        assert!(relocations.is_empty());
+        assert_eq!(memory.stack_pop().unwrap(), moved);
+        memory.stack_pop().unwrap();
+
+        gc.collect(&mut memory, &registers).unwrap();
+        let relocations = gc.take_relocations();
+        assert_eq!(relocations.get(&moved), Some(&garbage));
+        let new_counter = relocations[&counter];
+
+        // Reference slots follow the object; an equal integer elsewhere does not
+        assert_eq!(memory.read_word(root).unwrap(), garbage);
+        assert_eq!(memory.read_word(root + 4).unwrap(), garbage);
+        assert_eq!(memory.read_word(new_counter).unwrap(), moved);
+        assert_eq!(registers.read(1).unwrap() as u32, root);
+    }
+
+    #[test]
+    fn test_sweep_reclaims_space_for_allocation() {
+        // Room for four 64-byte objects and their headers
+        let heap_size = 4 * (64 + crate::vm::memory::OBJECT_HEADER_SIZE);
+        let layout = crate::vm::memory::MemoryLayout { code_size: 256, heap_size, stack_size: 256 };
+        let mut memory = Memory::with_layout(1024, layout).unwrap();
+        let mut registers = RegisterFile::new();
+        let config = GCConfig {
+            gc_threshold: 0.75,
+            max_heap_size: 256,
+            ..Default::default()
+        };
+        let mut gc = GarbageCollector::new(config);
+
+        // Allocate until the collector wants to run
+        let mut addrs = Vec::new();
+        while !gc.should_collect(&memory) {
+            let addr = memory.allocate(64).unwrap();
+            gc.register_object(addr, 64);
+            addrs.push(addr);
+        }
+        assert_eq!(addrs.len(), 3);
+
+        // Only the newest object is live, so the older ones leave a hole below it
+        registers.write(1, *addrs.last().unwrap() as i32).unwrap();
+        gc.collect(&mut memory, &registers).unwrap();
+        assert_eq!(gc.object_count(), 1);
+        assert!(!gc.should_collect(&memory));
+
+        // The heap only has room for one more block past the live one without reuse
+        assert_eq!(memory.allocate(64).unwrap(), addrs[0]);
+        assert_eq!(memory.allocate(64).unwrap(), addrs[1]);
+        assert!(memory.allocate(64).is_ok());
+    }
+
+    #[test]
+    fn test_weak_references() {
+        let mut memory = Memory::new(1024 * 1024);
+        let mut registers = RegisterFile::new();
+        let mut gc = GarbageCollector::new_default();
+
+        let cache = memory.allocate(16).unwrap();
+        let strong = memory.allocate(16).unwrap();
+        let weak = memory.allocate(16).unwrap();
+        for addr in [cache, strong, weak] {
+            gc.register_object(addr, 16);
+        }
+
+        registers.write(1, cache as i32).unwrap();
+        gc.add_reference(cache, strong);
+        gc.add_weak_reference(cache, weak);
+
+        gc.collect(&mut memory, &registers).unwrap();
+
+        // The weak-only target is collected, the strong one survives
+        assert!(gc.objects.contains_key(&strong));
+        assert!(!gc.objects.contains_key(&weak));
+        assert!(gc.objects[&cache].weak_references.is_empty());
+        assert_eq!(gc.take_cleared_weaks(), vec![(cache, weak)]);
+        assert!(gc.take_cleared_weaks().is_empty());
+    }
+
+    /// Build a heap with a rooted chain, a cycle of garbage, and a lone garbage object
+    fn build_incremental_heap() -> (Memory, RegisterFile, GarbageCollector, Vec<u32>) {
+        let mut memory = Memory::new(1024 * 1024);
+        let mut registers = RegisterFile::new();
+        let mut gc = GarbageCollector::new_default();
+
+        let addrs: Vec<u32> = (0..7).map(|_| memory.allocate(32).unwrap()).collect();
+        for &addr in &addrs {
+            gc.register_object(addr, 32);
+        }
+        for pair in addrs[..4].windows(2) {
+            gc.add_reference(pair[0], pair[1]);
+        }
+        gc.add_reference(addrs[4], addrs[5]);
+        gc.add_reference(addrs[5], addrs[4]);
+        registers.write(1, addrs[0] as i32).unwrap();
+
+        (memory, registers, gc, addrs)
+    }
+
+    #[test]
+    fn test_incremental_collection_matches_full() {
+        let (mut memory, registers, mut full, addrs) = build_incremental_heap();
+        full.collect(&mut memory, &registers).unwrap();
+
+        let (mut memory, registers, mut incremental, _) = build_incremental_heap();
+        let mut increments = 1;
+        while !incremental.collect_incremental(&mut memory, &registers, 1).unwrap() {
+            assert!(incremental.is_collecting());
+            increments += 1;
+        }
+
+        assert!(increments > 1);
+        assert!(!incremental.is_collecting());
+
+        let mut full_live: Vec<u32> = full.objects.keys().copied().collect();
+        let mut incremental_live: Vec<u32> = incremental.objects.keys().copied().collect();
+        full_live.sort_unstable();
+        incremental_live.sort_unstable();
+        assert_eq!(incremental_live, full_live);
+        assert_eq!(incremental_live, addrs[..4].to_vec());
+        assert_eq!(
+            incremental.get_stats().objects_collected,
+            full.get_stats().objects_collected
+        );
+    }
+
+    #[test]
+    fn test_incremental_write_barrier() {
+        let (mut memory, registers, mut gc, addrs) = build_incremental_heap();
+
+        // Scan the root, then link a garbage object to it mid-cycle
+        assert!(!gc.collect_incremental(&mut memory, &registers, 1).unwrap());
+        gc.add_reference(addrs[0], addrs[6]);
+        let late = memory.allocate(32).unwrap();
+        gc.register_object(late, 32);
+
+        while !gc.collect_incremental(&mut memory, &registers, 1).unwrap() {}
+
+        assert!(gc.objects.contains_key(&addrs[6]));
+        assert!(gc.objects.contains_key(&late));
+        assert!(!gc.objects.contains_key(&addrs[4]));
+    }
+
+    #[test]
+    fn test_minor_collect_keeps_young_object_referenced_by_old() {
+        let mut memory = Memory::new(1024 * 1024);
+        let mut registers = RegisterFile::new();
+        let mut gc = GarbageCollector::new_default();
+
+        // Age a rooted object into the old generation
+        let old = memory.allocate(32).unwrap();
+        gc.register_object(old, 32);
+        registers.write(1, old as i32).unwrap();
+        for _ in 0..OLD_GENERATION {
+            gc.collect(&mut memory, &registers).unwrap();
+        }
+        assert!(gc.is_old(old));
+
+        // The old object holds the only reference to a young one
+        let young = memory.allocate(32).unwrap();
+        gc.register_object(young, 32);
+        gc.add_reference(old, young);
+        let garbage = memory.allocate(32).unwrap();
+        gc.register_object(garbage, 32);
+
+        gc.minor_collect(&mut memory, &registers).unwrap();
+
+        assert!(gc.objects.contains_key(&old));
+        assert!(gc.objects.contains_key(&young));
+        assert!(!gc.objects.contains_key(&garbage));
+    }
+
+    #[test]
+    fn test_pause_histogram() {
+        let mut memory = Memory::new(1024 * 1024);
+        let registers = RegisterFile::new();
+        let mut gc = GarbageCollector::new_default();
+        assert_eq!(gc.get_stats().percentile(99.0), 0);
+
+        for _ in 0..5 {
+            let addr = memory.allocate(32).unwrap();
+            gc.register_object(addr, 32);
+            gc.collect(&mut memory, &registers).unwrap();
+        }
+
+        let stats = gc.get_stats();
+        let recorded: u64 = stats.pause_histogram.iter().sum();
+        assert_eq!(recorded, stats.collections_performed);
+        assert!(stats.percentile(50.0) <= stats.percentile(99.0));
+        assert!(stats.percentile(100.0) >= stats.last_collection_time_us);
+    }
+
+    #[test]
+    fn test_pause_percentile_buckets() {
+        let mut stats = GCStats::default();
+        for pause in [0, 3, 3, 100] {
+            stats.record_pause(pause);
+        }
+
+        assert_eq!(stats.pause_histogram[0], 1);
+        assert_eq!(stats.pause_histogram[2], 2);
+        assert_eq!(stats.pause_histogram[7], 1);
+        assert_eq!(stats.percentile(25.0), 0);
+        assert_eq!(stats.percentile(50.0), 3);
+        assert_eq!(stats.percentile(99.0), 127);
+    }
+
+    #[test]
+    fn test_pinned_object_survives_until_unpinned() {
+        let mut memory = Memory::new(1024 * 1024);
+        let registers = RegisterFile::new();
+        let mut gc = GarbageCollector::new_default();
+
+        let addr = memory.allocate(32).unwrap();
+        gc.register_object(addr, 32);
+        gc.pin(addr);
+
+        gc.collect(&mut memory, &registers).unwrap();
+        assert!(gc.objects.contains_key(&addr));
+        assert!(gc.is_pinned(addr));
+
+        assert!(gc.unpin(addr));
+        gc.collect(&mut memory, &registers).unwrap();
+        assert!(!gc.objects.contains_key(&addr));
+    }
+
+    #[test]
+    fn test_pinned_object_not_moved_by_compaction() {
+        let mut memory = Memory::new(1024 * 1024);
+        let mut registers = RegisterFile::new();
+        let mut gc = GarbageCollector::new(GCConfig {
+            strategy: GcStrategy::MarkCompact,
+            ..GCConfig::default()
+        });
+
+        let garbage = memory.allocate(64).unwrap();
+        let pinned = memory.allocate(32).unwrap();
+        let live = memory.allocate(32).unwrap();
+        for (addr, size) in [(garbage, 64), (pinned, 32), (live, 32)] {
+            gc.register_object(addr, size);
+        }
+        gc.pin(pinned);
+        registers.write(1, live as i32).unwrap();
+
+        gc.collect(&mut memory, &registers).unwrap();
+        let relocations = gc.take_relocations();
+
+        assert!(!relocations.contains_key(&pinned));
+        assert!(gc.objects.contains_key(&pinned));
+        assert!(!gc.objects.contains_key(&garbage));
+        assert!(!relocations.contains_key(&live));
+
+        // The gap in front of the pinned object is reusable
+        assert_eq!(memory.allocate(64).unwrap(), garbage);
+    }
+
+    #[test]
+    fn test_refcount_frees_chain_without_collect() {
+        let mut memory = Memory::new(1024 * 1024);
+        let mut gc = GarbageCollector::new(GCConfig {
+            strategy: GcStrategy::RefCount,
+            ..GCConfig::default()
+        });
+
+        let nodes: Vec<u32> = (0..4).map(|_| memory.allocate(16).unwrap()).collect();
+        for &node in &nodes {
+            gc.register_object(node, 16);
+        }
+        for pair in nodes.windows(2) {
+            gc.add_reference(pair[0], pair[1]);
+        }
+        gc.retain(nodes[0]);
+        assert_eq!(gc.ref_count(nodes[1]), 1);
+
+        // Dropping the head frees the whole list
+        gc.release(nodes[0], &mut memory).unwrap();
+
+        assert_eq!(gc.object_count(), 0);
+        assert_eq!(memory.get_stats().heap_used, 0);
+        assert_eq!(gc.get_stats().objects_collected, 4);
+        assert_eq!(gc.get_stats().collections_performed, 0);
+    }
+
+    #[test]
+    fn test_refcount_remove_reference() {
+        let mut memory = Memory::new(1024 * 1024);
+        let mut gc = GarbageCollector::new(GCConfig {
+            strategy: GcStrategy::RefCount,
+            ..GCConfig::default()
+        });
+
+        let parent = memory.allocate(16).unwrap();
+        let child = memory.allocate(16).unwrap();
+        gc.register_object(parent, 16);
+        gc.register_object(child, 16);
+        gc.add_reference(parent, child);
+        gc.add_reference(parent, child);
+        assert_eq!(gc.ref_count(child), 1);
+
+        gc.release_reference(parent, child, &mut memory).unwrap();
+        assert_eq!(gc.object_count(), 1);
+        assert!(gc.objects.contains_key(&parent));
+
+        // Without releasing, the orphan waits for the next collection
+        let orphan = memory.allocate(16).unwrap();
+        gc.register_object(orphan, 16);
+        gc.add_reference(parent, orphan);
+        gc.remove_reference(parent, orphan);
+        assert_eq!(gc.ref_count(orphan), 0);
+        assert!(gc.objects.contains_key(&orphan));
+
+        let mut registers = RegisterFile::new();
+        registers.write(1, parent as i32).unwrap();
+        gc.collect(&mut memory, &registers).unwrap();
+        assert!(!gc.objects.contains_key(&orphan));
+    }
+
+    #[test]
+    fn test_retained_objects_survive_tracing() {
+        let mut memory = Memory::new(1024 * 1024);
+        let registers = RegisterFile::new();
+
+        for strategy in [GcStrategy::MarkSweep, GcStrategy::RefCount] {
+            let mut gc = GarbageCollector::new(GCConfig { strategy, ..GCConfig::default() });
+            let held = memory.allocate(16).unwrap();
+            let child = memory.allocate(16).unwrap();
+            let cycle: Vec<u32> = (0..2).map(|_| memory.allocate(16).unwrap()).collect();
+            for addr in [held, child, cycle[0], cycle[1]] {
+                gc.register_object(addr, 16);
+            }
+            gc.add_reference(held, child);
+            gc.add_reference(cycle[0], cycle[1]);
+            gc.add_reference(cycle[1], cycle[0]);
+
+            // Only the host holds `held`; the cycle's counts come from its own edges
+            gc.retain(held);
+            gc.collect(&mut memory, &registers).unwrap();
+
+            assert!(gc.objects.contains_key(&held), "{:?}", strategy);
+            assert!(gc.objects.contains_key(&child), "{:?}", strategy);
+            assert_eq!(gc.object_count(), 2, "{:?}", strategy);
+
+            gc.release(held, &mut memory).unwrap();
+            gc.collect(&mut memory, &registers).unwrap();
+            assert_eq!(gc.object_count(), 0, "{:?}", strategy);
+        }
+    }
+
+    #[test]
+    fn test_event_listener() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        let mut memory = Memory::new(1024 * 1024);
+        let mut registers = RegisterFile::new();
+        let mut gc = GarbageCollector::new_default();
+
+        let live = memory.allocate(16).unwrap();
+        let dead = memory.allocate(32).unwrap();
+        gc.register_object(live, 16);
+        gc.register_object(dead, 32);
+        registers.write(1, live as i32).unwrap();
+
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let sink = Rc::clone(&events);
+        gc.set_event_listener(Box::new(move |event| sink.borrow_mut().push(event)));
+        gc.collect(&mut memory, &registers).unwrap();
+
+        assert_eq!(
+            *events.borrow(),
+            vec![
+                GcEvent::CollectionStarted,
+                GcEvent::ObjectMarked(live),
+                GcEvent::ObjectSwept(dead, 32),
+                GcEvent::CollectionFinished(Box::new(gc.get_stats().clone())),
+            ]
+        );
+
+        gc.clear_event_listener();
+        gc.collect(&mut memory, &registers).unwrap();
+        assert_eq!(events.borrow().len(), 4);
+    }
+
+    #[test]
+    fn test_large_object_space() {
+        let mut memory = Memory::new(4 * 1024 * 1024);
+        let mut registers = RegisterFile::new();
+        let mut gc = GarbageCollector::new(GCConfig {
+            large_object_threshold: 1024,
+            strategy: GcStrategy::MarkCompact,
+            ..GCConfig::default()
+        });
+
+        let garbage = memory.allocate(16).unwrap();
+        gc.register_object(garbage, 16);
+        let huge = memory.allocate(256 * 1024).unwrap();
+        gc.register_object(huge, 256 * 1024);
+        let small: Vec<u32> = (0..3).map(|_| memory.allocate(16).unwrap()).collect();
+        for &addr in &small {
+            gc.register_object(addr, 16);
+        }
+
+        assert!(gc.is_large_object(huge));
+        assert!(small.iter().all(|&addr| !gc.is_large_object(addr)));
+        assert_eq!(gc.large_object_count(), 1);
+        assert_eq!(gc.objects[&huge].generation, OLD_GENERATION);
+        assert_eq!(gc.objects[&small[0]].generation, 0);
+
+        // Survives without being promoted or moved, while small objects slide past it
+        registers.write(1, huge as i32).unwrap();
+        memory.write_word(huge, small[1]).unwrap();
+        gc.add_reference(huge, small[1]);
+        gc.collect(&mut memory, &registers).unwrap();
+        let relocations = gc.take_relocations();
+
+        assert!(!relocations.contains_key(&huge));
+        assert_eq!(gc.objects[&huge].generation, OLD_GENERATION);
+        assert_eq!(relocations.get(&small[1]), Some(&small[0]));
+        assert_eq!(gc.object_count(), 2);
+    }
+
     #[test]
     fn test_gc_threshold() {
-        let memory = Memory::new(1000);
+        let mut memory = Memory::new(1000);
         let config = GCConfig {
             gc_threshold: 0.5,
             ..Default::default()
         };
         let gc = GarbageCollector::new(config);
+        assert!(!gc.should_collect(&memory));
 
-        // With small memory, should trigger collection
+        // With small memory, filling half the heap should trigger collection
+        let (heap_start, heap_end) = memory.heap_region();
+        memory.allocate((heap_end - heap_start) / 2).unwrap();
         assert!(gc.should_collect(&memory));
     }
+
+    #[test]
+    fn test_gc_threshold_clamped_to_heap_region() {
+        // The default 64MB max heap is far larger than this VM's memory
+        let mut memory = Memory::new(2 * 1024 * 1024);
+        let gc = GarbageCollector::new_default();
+        let (heap_start, heap_end) = memory.heap_region();
+        let trigger = ((heap_end - heap_start) as f32 * gc.get_config().gc_threshold) as u32;
+
+        while !gc.should_collect(&memory) {
+            memory.allocate(1024).unwrap();
+        }
+
+        let heap_used = memory.get_stats().heap_used;
+        assert!(heap_used >= trigger);
+        assert!(heap_used < trigger + 1024);
+    }
 }
@@ -1,11 +1,103 @@
 use crate::vm::error::{VMError, VMResult};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::cell::RefCell;
+use std::cmp::Reverse;
+
+/// Sizes of the code, heap and stack regions
+///
+/// Regions are laid out in that order: code starts at address 0, the heap follows
+/// it, and the stack occupies the top `stack_size` bytes of memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryLayout {
+    pub code_size: u32,
+    pub heap_size: u32,
+    pub stack_size: u32,
+}
+
+/// Bytes reserved in front of every heap object for its header
+pub const OBJECT_HEADER_SIZE: u32 = 8;
+
+/// Header stored in memory just before each heap object
+///
+/// The first word holds the object's size in bytes (rounded up to a word), the
+/// second a type tag chosen by the allocating code (0 when untagged).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ObjectHeader {
+    pub size: u32,
+    pub type_tag: u32,
+}
+
+/// Call site reported by `Memory::live_allocations` for blocks allocated outside a program
+pub const NO_ALLOC_SITE: u32 = u32::MAX;
+
+/// Guard word written just past each allocation while heap canaries are enabled
+pub const HEAP_CANARY: u32 = 0xC0DE_CAFE;
+
+/// Size in bytes of the guard word following a guarded block
+const CANARY_SIZE: u32 = 4;
+
+/// Granularity at which snapshots save the original contents of written memory
+const SNAPSHOT_PAGE_SIZE: u32 = 4096;
+
+/// Handle to a memory snapshot taken with `Memory::snapshot`
+///
+/// Consumed by `Memory::restore` or `Memory::release`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct MemorySnapshot {
+    id: u64,
+}
+
+/// Allocator bookkeeping saved with each snapshot
+#[derive(Debug, Clone)]
+struct AllocatorState {
+    layout: MemoryLayout,
+    memory_size: u32,
+    stack_pointer: u32,
+    stack_base: u32,
+    heap_pointer: u32,
+    allocated_blocks: HashMap<u32, u32>,
+    free_blocks: BTreeMap<u32, u32>,
+    alloc_sites: HashMap<u32, u32>,
+    guarded_blocks: HashSet<u32>,
+}
+
+/// Pages first written after a snapshot was taken, with their contents at that time
+#[derive(Debug, Clone)]
+struct SnapshotLayer {
+    id: u64,
+    pages: HashMap<u32, Box<[u8]>>, // page number -> original bytes
+    state: AllocatorState,
+}
+
+/// Most recent accesses kept by the access log; older entries are dropped
+pub const ACCESS_LOG_CAPACITY: usize = 4096;
+
+/// Direction of a logged memory access
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessKind {
+    Read,
+    Write,
+}
+
+/// How `Memory::allocate` chooses among freed blocks that are large enough
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AllocStrategy {
+    /// The lowest-addressed block that fits
+    #[default]
+    FirstFit,
+    /// The smallest block that fits, keeping large holes intact
+    BestFit,
+    /// The largest block, leaving the biggest usable remainder
+    WorstFit,
+}
 
 /// Memory subsystem for the VM with heap and stack management
 #[derive(Debug, Clone)]
 pub struct Memory {
     /// Main memory storage
     memory: Vec<u8>,
+    /// Region sizes this memory was created with
+    layout: MemoryLayout,
     /// Current stack pointer
     stack_pointer: u32,
     /// Stack base (grows downward from here)
@@ -15,7 +107,29 @@ pub struct Memory {
     /// Heap base
     heap_base: u32,
     /// Allocated blocks tracking for heap management
-    allocated_blocks: HashMap<u32, u32>, // address -> size
+    allocated_blocks: HashMap<u32, u32>, // object address -> size, excluding the header
+    /// Freed heap blocks below the heap pointer, available for reuse
+    free_blocks: BTreeMap<u32, u32>, // block address -> size
+    /// How freed blocks are chosen for reuse
+    alloc_strategy: AllocStrategy,
+    /// Whether newly allocated blocks are cleared before being handed out
+    zero_on_alloc: bool,
+    /// PC of the instruction that allocated each live block, when known
+    alloc_sites: HashMap<u32, u32>, // object address -> pc
+    /// Whether new allocations get a guard word after their data
+    heap_canaries: bool,
+    /// Blocks followed by a guard word
+    guarded_blocks: HashSet<u32>, // object address
+    /// Open snapshots, oldest first
+    snapshots: Vec<SnapshotLayer>,
+    /// Id given to the next snapshot
+    next_snapshot_id: u64,
+    /// Whether `check_access` confines heap accesses to allocated blocks
+    strict_bounds: bool,
+    /// Whether accesses are recorded in `access_log`
+    access_logging: bool,
+    /// Ring buffer of recent accesses; a `RefCell` so reads through `&self` can log
+    access_log: RefCell<VecDeque<(AccessKind, u32, u32)>>, // (kind, address, size)
     /// Memory size in bytes
     memory_size: u32,
 }
@@ -25,33 +139,372 @@ impl Memory {
     /// 
     /// Memory layout:
     /// ```text
-    /// 0x00000000 - 0x00010000: Code section (64KB)
-    /// 0x00010000 - heap_top:   Heap (grows upward)
-    /// stack_base - 0xFFFFFFFF: Stack (grows downward)
+    /// 0x00000000 - 0x00010000:  Code section (64KB)
+    /// 0x00010000 - heap_limit:  Heap (grows upward)
+    /// stack_limit - stack_base: Stack (1MB, grows downward from the top of memory)
     /// ```
     pub fn new(memory_size: u32) -> Self {
-        let code_section_size = if memory_size > 0x10000 { 0x10000 } else { memory_size / 4 }; // 64KB for code or 1/4 of total
+        let code_size = if memory_size > 0x10000 { 0x10000 } else { memory_size / 4 }; // 64KB for code or 1/4 of total
         let stack_size = if memory_size > 0x100000 { 0x100000 } else { memory_size / 4 }; // 1MB for stack or 1/4 of total
+        let heap_size = memory_size.saturating_sub(code_size + stack_size);
         
-        let heap_base = code_section_size;
-        let stack_base = if memory_size > stack_size { memory_size - stack_size } else { memory_size * 3 / 4 };
-        
-        Self {
+        Self::with_layout(memory_size, MemoryLayout { code_size, heap_size, stack_size })
+            .expect("default memory layout always fits")
+    }
+
+    /// Create a memory subsystem with explicit region sizes
+    ///
+    /// Fails if the regions don't fit in `memory_size` bytes.
+    pub fn with_layout(memory_size: u32, layout: MemoryLayout) -> VMResult<Self> {
+        let required = layout
+            .code_size
+            .checked_add(layout.heap_size)
+            .and_then(|size| size.checked_add(layout.stack_size));
+
+        match required {
+            Some(required) if required <= memory_size => {}
+            _ => {
+                return Err(VMError::InvalidMemoryLayout(format!(
+                    "code ({}) + heap ({}) + stack ({}) bytes exceed total memory of {} bytes",
+                    layout.code_size, layout.heap_size, layout.stack_size, memory_size
+                )));
+            }
+        }
+
+        let heap_base = layout.code_size;
+        let stack_base = memory_size;
+
+        Ok(Self {
             memory: vec![0; memory_size as usize],
+            layout,
             stack_pointer: stack_base,
             stack_base,
             heap_pointer: heap_base,
             heap_base,
             allocated_blocks: HashMap::new(),
+            free_blocks: BTreeMap::new(),
+            alloc_strategy: AllocStrategy::default(),
+            zero_on_alloc: false,
+            alloc_sites: HashMap::new(),
+            heap_canaries: false,
+            guarded_blocks: HashSet::new(),
+            snapshots: Vec::new(),
+            next_snapshot_id: 0,
+            strict_bounds: false,
+            access_logging: false,
+            access_log: RefCell::new(VecDeque::new()),
             memory_size,
+        })
+    }
+
+    /// Get the region sizes of this memory
+    pub fn layout(&self) -> MemoryLayout {
+        self.layout
+    }
+
+    /// Add `additional` bytes of memory, all of which go to the heap
+    ///
+    /// The stack region moves up by `additional` bytes so the heap can extend into
+    /// the space it leaves behind; heap and code addresses are unchanged. Stack words
+    /// are copied as they are, so any stack address held on the stack or elsewhere
+    /// (such as a saved frame pointer) must be shifted by the caller; `VM::grow_memory`
+    /// does this for the registers and frame chain it manages.
+    pub fn grow(&mut self, additional: u32) -> VMResult<()> {
+        let new_size = self.memory_size.checked_add(additional).ok_or_else(|| {
+            VMError::InvalidMemoryLayout(format!(
+                "growing {} bytes of memory by {} exceeds the address space",
+                self.memory_size, additional
+            ))
+        })?;
+
+        let old_limit = self.stack_limit();
+        let old_base = self.stack_base;
+
+        self.record_pages(old_limit, old_base);
+        self.memory.resize(new_size as usize, 0);
+        self.memory.copy_within(old_limit as usize..old_base as usize, (old_limit + additional) as usize);
+        self.memory[old_limit as usize..(old_limit + additional) as usize].fill(0);
+
+        self.memory_size = new_size;
+        self.layout.heap_size += additional;
+        self.stack_base += additional;
+        self.stack_pointer += additional;
+
+        Ok(())
+    }
+
+    /// Choose how freed blocks are picked for reuse
+    pub fn set_alloc_strategy(&mut self, strategy: AllocStrategy) {
+        self.alloc_strategy = strategy;
+    }
+
+    /// Current free-block selection strategy
+    pub fn alloc_strategy(&self) -> AllocStrategy {
+        self.alloc_strategy
+    }
+
+    /// Clear every newly allocated block (and the grown part of a reallocated one)
+    ///
+    /// `free` already zeroes released blocks, but anything written directly into
+    /// unallocated heap memory would otherwise show through.
+    pub fn set_zero_on_alloc(&mut self, enabled: bool) {
+        self.zero_on_alloc = enabled;
+    }
+
+    /// Whether newly allocated blocks are cleared
+    pub fn zero_on_alloc(&self) -> bool {
+        self.zero_on_alloc
+    }
+
+    /// Enable or disable guard words after new allocations
+    ///
+    /// Blocks allocated while enabled keep their guard word until freed, even if
+    /// canaries are later turned off.
+    pub fn set_heap_canaries(&mut self, enabled: bool) {
+        self.heap_canaries = enabled;
+    }
+
+    /// Whether new allocations get a guard word
+    pub fn heap_canaries(&self) -> bool {
+        self.heap_canaries
+    }
+
+    /// Verify the guard word of every live guarded block
+    ///
+    /// Reports the lowest corrupted block as `HeapCorruption`.
+    pub fn check_canaries(&self) -> VMResult<()> {
+        let mut guarded: Vec<u32> = self.guarded_blocks.iter().copied().collect();
+        guarded.sort_unstable();
+        for address in guarded {
+            self.check_canary(address, self.allocated_blocks[&address])?;
+        }
+        Ok(())
+    }
+
+    fn check_canary(&self, address: u32, size: u32) -> VMResult<()> {
+        if !self.guarded_blocks.contains(&address) {
+            return Ok(());
+        }
+        let guard = (address + size) as usize;
+        let bytes = [
+            self.memory[guard],
+            self.memory[guard + 1],
+            self.memory[guard + 2],
+            self.memory[guard + 3],
+        ];
+        if u32::from_le_bytes(bytes) == HEAP_CANARY {
+            Ok(())
+        } else {
+            Err(VMError::HeapCorruption(address))
         }
     }
 
+    /// Bytes of guard word following the block at `address`
+    fn guard_size(&self, address: u32) -> u32 {
+        if self.guarded_blocks.contains(&address) { CANARY_SIZE } else { 0 }
+    }
+
+    fn write_canary(&mut self, address: u32, size: u32) {
+        let guard = address + size;
+        self.bytes_mut(guard, guard + CANARY_SIZE).copy_from_slice(&HEAP_CANARY.to_le_bytes());
+    }
+
+    /// Enable or disable strict bounds checking in `check_access`
+    pub fn set_strict_bounds(&mut self, enabled: bool) {
+        self.strict_bounds = enabled;
+    }
+
+    /// Whether strict bounds checking is enabled
+    pub fn strict_bounds(&self) -> bool {
+        self.strict_bounds
+    }
+
+    /// Check a program access of `len` bytes at `address` against strict bounds
+    ///
+    /// With strict bounds on, an access touching the heap must lie entirely inside
+    /// one allocated block's data (headers included as off-limits). Code and stack
+    /// accesses are always allowed, as is everything when strict bounds are off.
+    pub fn check_access(&self, address: u32, len: u32) -> VMResult<()> {
+        if !self.strict_bounds {
+            return Ok(());
+        }
+
+        let (heap_start, heap_end) = self.heap_region();
+        let end = address.saturating_add(len);
+        if end <= heap_start || address >= heap_end {
+            return Ok(());
+        }
+
+        let inside_block = self
+            .allocated_blocks
+            .iter()
+            .any(|(&block, &size)| address >= block && end <= block + size);
+        if inside_block {
+            Ok(())
+        } else {
+            Err(VMError::MemoryAccessViolation(address))
+        }
+    }
+
+    /// Start or stop recording accesses; the existing log is kept either way
+    pub fn set_access_logging(&mut self, enabled: bool) {
+        self.access_logging = enabled;
+    }
+
+    /// Logged accesses as `(kind, address, size)`, oldest first
+    ///
+    /// Only the last `ACCESS_LOG_CAPACITY` accesses are kept.
+    pub fn access_log(&self) -> Vec<(AccessKind, u32, u32)> {
+        self.access_log.borrow().iter().copied().collect()
+    }
+
+    /// Discard all logged accesses
+    pub fn clear_access_log(&mut self) {
+        self.access_log.get_mut().clear();
+    }
+
+    fn log_access(&self, kind: AccessKind, address: u32, size: u32) {
+        if !self.access_logging {
+            return;
+        }
+        let mut log = self.access_log.borrow_mut();
+        if log.len() == ACCESS_LOG_CAPACITY {
+            log.pop_front();
+        }
+        log.push_back((kind, address, size));
+    }
+
+    /// Address range `[start, end)` reserved for the heap
+    pub fn heap_region(&self) -> (u32, u32) {
+        (self.heap_base, self.heap_limit())
+    }
+
+    /// End of the heap region (exclusive)
+    fn heap_limit(&self) -> u32 {
+        self.heap_base + self.layout.heap_size
+    }
+
+    /// Lowest address the stack may grow down to
+    fn stack_limit(&self) -> u32 {
+        self.stack_base - self.layout.stack_size
+    }
+
+    /// Start tracking changes so memory can later be put back the way it is now
+    ///
+    /// Only pages written after the snapshot are copied, each the first time it is
+    /// written. Allocator state is saved too, so restoring also undoes allocations,
+    /// frees and `grow`. Snapshots nest; settings such as the allocation strategy
+    /// and the access log are not affected.
+    pub fn snapshot(&mut self) -> MemorySnapshot {
+        let id = self.next_snapshot_id;
+        self.next_snapshot_id += 1;
+        self.snapshots.push(SnapshotLayer {
+            id,
+            pages: HashMap::new(),
+            state: AllocatorState {
+                layout: self.layout,
+                memory_size: self.memory_size,
+                stack_pointer: self.stack_pointer,
+                stack_base: self.stack_base,
+                heap_pointer: self.heap_pointer,
+                allocated_blocks: self.allocated_blocks.clone(),
+                free_blocks: self.free_blocks.clone(),
+                alloc_sites: self.alloc_sites.clone(),
+                guarded_blocks: self.guarded_blocks.clone(),
+            },
+        });
+        MemorySnapshot { id }
+    }
+
+    /// Revert every change made since `snapshot` was taken
+    ///
+    /// Snapshots taken after it are discarded as well.
+    pub fn restore(&mut self, snapshot: MemorySnapshot) -> VMResult<()> {
+        let position = self.snapshot_position(&snapshot)?;
+
+        // Undo the newest changes first so older originals win
+        while self.snapshots.len() > position {
+            let layer = self.snapshots.pop().expect("position is within the open snapshots");
+            for (page, original) in layer.pages {
+                let start = (page * SNAPSHOT_PAGE_SIZE) as usize;
+                self.memory[start..start + original.len()].copy_from_slice(&original);
+            }
+
+            let state = layer.state;
+            self.memory.truncate(state.memory_size as usize);
+            self.layout = state.layout;
+            self.memory_size = state.memory_size;
+            self.stack_pointer = state.stack_pointer;
+            self.stack_base = state.stack_base;
+            self.heap_pointer = state.heap_pointer;
+            self.allocated_blocks = state.allocated_blocks;
+            self.free_blocks = state.free_blocks;
+            self.alloc_sites = state.alloc_sites;
+            self.guarded_blocks = state.guarded_blocks;
+        }
+
+        Ok(())
+    }
+
+    /// Keep the changes made since `snapshot` and stop tracking them for it
+    ///
+    /// Older snapshots can still be restored.
+    pub fn release(&mut self, snapshot: MemorySnapshot) -> VMResult<()> {
+        let position = self.snapshot_position(&snapshot)?;
+        let layer = self.snapshots.remove(position);
+
+        // Originals the older snapshot hasn't saved yet are still valid for it
+        if let Some(older) = position.checked_sub(1).map(|i| &mut self.snapshots[i]) {
+            for (page, original) in layer.pages {
+                older.pages.entry(page).or_insert(original);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Number of snapshots that can still be restored
+    pub fn open_snapshots(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    fn snapshot_position(&self, snapshot: &MemorySnapshot) -> VMResult<usize> {
+        self.snapshots
+            .iter()
+            .position(|layer| layer.id == snapshot.id)
+            .ok_or(VMError::UnknownSnapshot)
+    }
+
+    /// Save the original contents of pages in `[start, end)` for the newest snapshot
+    fn record_pages(&mut self, start: u32, end: u32) {
+        let Some(layer) = self.snapshots.last_mut() else {
+            return;
+        };
+        if start >= end {
+            return;
+        }
+
+        for page in start / SNAPSHOT_PAGE_SIZE..=(end - 1) / SNAPSHOT_PAGE_SIZE {
+            layer.pages.entry(page).or_insert_with(|| {
+                let page_start = (page * SNAPSHOT_PAGE_SIZE) as usize;
+                let page_end = (page_start + SNAPSHOT_PAGE_SIZE as usize).min(self.memory.len());
+                self.memory[page_start..page_end].into()
+            });
+        }
+    }
+
+    /// Writable view of `[start, end)`, saved for the newest snapshot first
+    fn bytes_mut(&mut self, start: u32, end: u32) -> &mut [u8] {
+        self.record_pages(start, end);
+        &mut self.memory[start as usize..end as usize]
+    }
+
     /// Read a byte from memory
     pub fn read_byte(&self, address: u32) -> VMResult<u8> {
         if address >= self.memory_size {
             return Err(VMError::InvalidMemoryAddress(address));
         }
+        self.log_access(AccessKind::Read, address, 1);
         Ok(self.memory[address as usize])
     }
 
@@ -62,12 +515,40 @@ impl Memory {
         }
         
         // Check if writing to code section (might want to prevent this)
-        if address < 0x10000 {
+        if address < self.heap_base {
             // For now, allow writes to code section (for loading programs)
             // Could add a protection flag later
         }
         
-        self.memory[address as usize] = value;
+        self.log_access(AccessKind::Write, address, 1);
+        self.bytes_mut(address, address + 1)[0] = value;
+        Ok(())
+    }
+
+    /// Read a 16-bit halfword from memory (little-endian)
+    pub fn read_halfword(&self, address: u32) -> VMResult<u16> {
+        if address.saturating_add(1) >= self.memory_size {
+            return Err(VMError::InvalidMemoryAddress(address));
+        }
+
+        self.log_access(AccessKind::Read, address, 2);
+        let bytes = [
+            self.memory[address as usize],
+            self.memory[(address + 1) as usize],
+        ];
+
+        Ok(u16::from_le_bytes(bytes))
+    }
+
+    /// Write a 16-bit halfword to memory (little-endian)
+    pub fn write_halfword(&mut self, address: u32, value: u16) -> VMResult<()> {
+        if address.saturating_add(1) >= self.memory_size {
+            return Err(VMError::InvalidMemoryAddress(address));
+        }
+
+        self.log_access(AccessKind::Write, address, 2);
+        self.bytes_mut(address, address + 2).copy_from_slice(&value.to_le_bytes());
+
         Ok(())
     }
 
@@ -77,6 +558,7 @@ impl Memory {
             return Err(VMError::InvalidMemoryAddress(address));
         }
         
+        self.log_access(AccessKind::Read, address, 4);
         let bytes = [
             self.memory[address as usize],
             self.memory[(address + 1) as usize],
@@ -93,18 +575,62 @@ impl Memory {
             return Err(VMError::InvalidMemoryAddress(address));
         }
         
-        let bytes = value.to_le_bytes();
-        for (i, &byte) in bytes.iter().enumerate() {
-            self.memory[(address + i as u32) as usize] = byte;
-        }
+        self.log_access(AccessKind::Write, address, 4);
+        self.bytes_mut(address, address + 4).copy_from_slice(&value.to_le_bytes());
         
         Ok(())
     }
 
+    /// Read `len` bytes starting at `address`
+    pub fn read_bytes(&self, address: u32, len: u32) -> VMResult<Vec<u8>> {
+        self.check_range(address, len)?;
+        self.log_access(AccessKind::Read, address, len);
+        Ok(self.memory[address as usize..(address + len) as usize].to_vec())
+    }
+
+    /// Write `data` starting at `address`; nothing is written if the range is out of bounds
+    pub fn write_bytes(&mut self, address: u32, data: &[u8]) -> VMResult<()> {
+        let len = u32::try_from(data.len()).map_err(|_| VMError::InvalidMemoryAddress(address))?;
+        self.check_range(address, len)?;
+
+        self.log_access(AccessKind::Write, address, len);
+        self.bytes_mut(address, address + len).copy_from_slice(data);
+        Ok(())
+    }
+
+    /// Copy `len` bytes from `src` to `dst`; overlapping ranges behave like `memmove`
+    pub fn mem_copy(&mut self, dst: u32, src: u32, len: u32) -> VMResult<()> {
+        self.check_range(src, len)?;
+        self.check_range(dst, len)?;
+
+        self.log_access(AccessKind::Read, src, len);
+        self.log_access(AccessKind::Write, dst, len);
+        self.record_pages(dst, dst + len);
+        self.memory.copy_within(src as usize..(src + len) as usize, dst as usize);
+        Ok(())
+    }
+
+    /// Fill `len` bytes starting at `dst` with `byte`
+    pub fn mem_set(&mut self, dst: u32, byte: u8, len: u32) -> VMResult<()> {
+        self.check_range(dst, len)?;
+
+        self.log_access(AccessKind::Write, dst, len);
+        self.bytes_mut(dst, dst + len).fill(byte);
+        Ok(())
+    }
+
+    /// Ensure `[address, address + len)` lies entirely within memory
+    fn check_range(&self, address: u32, len: u32) -> VMResult<()> {
+        match address.checked_add(len) {
+            Some(end) if end <= self.memory_size => Ok(()),
+            _ => Err(VMError::InvalidMemoryAddress(address)),
+        }
+    }
+
     /// Load bytecode into the code section
     pub fn load_program(&mut self, bytecode: &[u32]) -> VMResult<()> {
         let required_size = bytecode.len() * 4;
-        if required_size > 0x10000 {
+        if required_size > self.layout.code_size as usize {
             return Err(VMError::OutOfMemory);
         }
         
@@ -117,43 +643,301 @@ impl Memory {
     }
 
     /// Allocate memory on the heap
+    ///
+    /// Returns the address of the object's data, just past its header.
     pub fn allocate(&mut self, size: u32) -> VMResult<u32> {
-        if size == 0 {
+        self.allocate_tagged(size, 0)
+    }
+
+    /// Allocate memory on the heap, recording `type_tag` in the object header
+    pub fn allocate_tagged(&mut self, size: u32, type_tag: u32) -> VMResult<u32> {
+        if size == 0 || size > u32::MAX - OBJECT_HEADER_SIZE - CANARY_SIZE - 3 {
             return Err(VMError::AllocationFailed(size));
         }
         
         // Align to 4-byte boundary
         let aligned_size = (size + 3) & !3;
-        
-        // Check if we have enough space
-        if self.heap_pointer + aligned_size >= self.stack_base {
-            return Err(VMError::OutOfMemory);
-        }
-        
-        let address = self.heap_pointer;
-        self.heap_pointer += aligned_size;
-        
+        let guard = if self.heap_canaries { CANARY_SIZE } else { 0 };
+        let block_size = aligned_size + OBJECT_HEADER_SIZE + guard;
+
+        // Reuse a freed block that fits, splitting off any remainder
+        let mut fitting = self
+            .free_blocks
+            .iter()
+            .filter(|&(_, &free_size)| free_size >= block_size)
+            .map(|(&block, &free_size)| (block, free_size));
+        // Ties go to the lowest address
+        let reusable = match self.alloc_strategy {
+            AllocStrategy::FirstFit => fitting.next(),
+            AllocStrategy::BestFit => fitting.min_by_key(|&(_, free_size)| free_size),
+            AllocStrategy::WorstFit => fitting.max_by_key(|&(block, free_size)| (free_size, Reverse(block))),
+        };
+        let block = if let Some((block, free_size)) = reusable {
+            self.free_blocks.remove(&block);
+            if free_size > block_size {
+                self.free_blocks.insert(block + block_size, free_size - block_size);
+            }
+            block
+        } else {
+            // Check if we have enough space
+            if block_size > self.heap_limit() - self.heap_pointer {
+                return Err(VMError::OutOfMemory);
+            }
+
+            let block = self.heap_pointer;
+            self.heap_pointer += block_size;
+            block
+        };
+
         // Track the allocation
+        let address = block + OBJECT_HEADER_SIZE;
+        if self.zero_on_alloc {
+            self.bytes_mut(address, address + aligned_size).fill(0);
+        }
         self.allocated_blocks.insert(address, aligned_size);
+        if self.heap_canaries {
+            self.guarded_blocks.insert(address);
+            self.write_canary(address, aligned_size);
+        }
+        self.write_header(address, ObjectHeader { size: aligned_size, type_tag })?;
         
         Ok(address)
     }
 
-    /// Free memory on the heap
-    pub fn free(&mut self, address: u32) -> VMResult<()> {
-        if let Some(size) = self.allocated_blocks.remove(&address) {
-            // Zero out the freed memory for security
-            for i in 0..size {
-                if let Ok(()) = self.write_byte(address + i, 0) {
-                    // Continue zeroing
+    /// Record `pc` as the instruction that allocated the live block at `address`
+    pub fn record_alloc_site(&mut self, address: u32, pc: u32) -> VMResult<()> {
+        if !self.allocated_blocks.contains_key(&address) {
+            return Err(VMError::InvalidMemoryAddress(address));
+        }
+        self.alloc_sites.insert(address, pc);
+        Ok(())
+    }
+
+    /// Every live allocation as `(address, size, pc)`, sorted by address
+    ///
+    /// `pc` is the allocating instruction recorded with `record_alloc_site`,
+    /// or `NO_ALLOC_SITE` for blocks allocated directly by the host.
+    pub fn live_allocations(&self) -> Vec<(u32, u32, u32)> {
+        let mut allocations: Vec<(u32, u32, u32)> = self
+            .allocated_blocks
+            .iter()
+            .map(|(&address, &size)| {
+                let pc = self.alloc_sites.get(&address).copied().unwrap_or(NO_ALLOC_SITE);
+                (address, size, pc)
+            })
+            .collect();
+        allocations.sort_unstable();
+        allocations
+    }
+
+    /// Read the header of the heap object at `address`
+    pub fn object_header(&self, address: u32) -> VMResult<ObjectHeader> {
+        if !self.allocated_blocks.contains_key(&address) {
+            return Err(VMError::InvalidMemoryAddress(address));
+        }
+
+        Ok(ObjectHeader {
+            size: self.read_word(address - OBJECT_HEADER_SIZE)?,
+            type_tag: self.read_word(address - OBJECT_HEADER_SIZE + 4)?,
+        })
+    }
+
+    /// Change the type tag of the heap object at `address`
+    pub fn set_type_tag(&mut self, address: u32, type_tag: u32) -> VMResult<()> {
+        let header = self.object_header(address)?;
+        self.write_header(address, ObjectHeader { type_tag, ..header })
+    }
+
+    fn write_header(&mut self, address: u32, header: ObjectHeader) -> VMResult<()> {
+        self.write_word(address - OBJECT_HEADER_SIZE, header.size)?;
+        self.write_word(address - OBJECT_HEADER_SIZE + 4, header.type_tag)
+    }
+
+    /// Resize a heap block, moving it if it can't be resized in place
+    ///
+    /// When the block moves, the first `min(old, new)` bytes are copied to the new
+    /// block and the old one is freed. Returns the (possibly new) block address.
+    pub fn reallocate(&mut self, address: u32, new_size: u32) -> VMResult<u32> {
+        let old_size = *self
+            .allocated_blocks
+            .get(&address)
+            .ok_or(VMError::InvalidMemoryAddress(address))?;
+
+        if new_size == 0 {
+            return Err(VMError::AllocationFailed(new_size));
+        }
+
+        self.check_canary(address, old_size)?;
+
+        let aligned_size = (new_size + 3) & !3;
+        let guard = self.guard_size(address);
+        let is_last_block = address + old_size + guard == self.heap_pointer;
+
+        let type_tag = self.object_header(address)?.type_tag;
+        let resized = ObjectHeader { size: aligned_size, type_tag };
+
+        // Shrink in place, zeroing and releasing the tail
+        if aligned_size <= old_size {
+            self.bytes_mut(address + aligned_size, address + old_size + guard).fill(0);
+            self.allocated_blocks.insert(address, aligned_size);
+            if guard > 0 {
+                self.write_canary(address, aligned_size);
+            }
+            if aligned_size < old_size {
+                self.release_block(address + aligned_size + guard, old_size - aligned_size);
+            }
+            self.write_header(address, resized)?;
+            return Ok(address);
+        }
+
+        // Grow in place when the block sits at the top of the heap
+        if is_last_block && aligned_size + guard <= self.heap_limit() - address {
+            // The old guard word becomes data
+            self.bytes_mut(address + old_size, address + old_size + guard).fill(0);
+            if self.zero_on_alloc {
+                self.bytes_mut(address + old_size, address + aligned_size).fill(0);
+            }
+            self.heap_pointer = address + aligned_size + guard;
+            self.allocated_blocks.insert(address, aligned_size);
+            if guard > 0 {
+                self.write_canary(address, aligned_size);
+            }
+            self.write_header(address, resized)?;
+            return Ok(address);
+        }
+
+        let new_address = self.allocate_tagged(new_size, type_tag)?;
+        self.record_pages(new_address, new_address + old_size);
+        self.memory.copy_within(
+            address as usize..(address + old_size) as usize,
+            new_address as usize,
+        );
+        let site = self.alloc_sites.get(&address).copied();
+        self.free(address)?;
+        if let Some(pc) = site {
+            self.alloc_sites.insert(new_address, pc);
+        }
+
+        Ok(new_address)
+    }
+
+    /// Slide all allocated blocks down to `heap_base`, closing the gaps between them
+    ///
+    /// Blocks in `pinned` stay where they are; gaps left in front of them go on the
+    /// free list. Returns a map from old to new address for every block that moved.
+    /// Only the words at `pointer_slots` (given by their address before compaction)
+    /// are treated as pointers and rewritten; any other word keeps its value, so
+    /// blocks that may be referenced from elsewhere must be pinned.
+    pub fn compact(&mut self, pinned: &HashSet<u32>, pointer_slots: &[u32]) -> HashMap<u32, u32> {
+        let mut blocks: Vec<(u32, u32)> = self.allocated_blocks.drain().collect();
+        blocks.sort_unstable();
+        self.free_blocks.clear();
+
+        let mut relocations = HashMap::new();
+        let mut moved_blocks = Vec::new(); // (old start, old end, new start), headers included
+        let mut next = self.heap_base;
+        for (address, size) in blocks {
+            // Move whole blocks, header included
+            let block = address - OBJECT_HEADER_SIZE;
+            let block_end = address + size + self.guard_size(address);
+
+            if pinned.contains(&address) {
+                if block > next {
+                    self.bytes_mut(next, block).fill(0);
+                    self.free_blocks.insert(next, block - next);
                 }
+                self.allocated_blocks.insert(address, size);
+                next = block_end;
+                continue;
             }
+
+            let new_address = next + OBJECT_HEADER_SIZE;
+            if block != next {
+                self.record_pages(next, next + (block_end - block));
+                self.memory.copy_within(block as usize..block_end as usize, next as usize);
+                relocations.insert(address, new_address);
+                moved_blocks.push((block, block_end, next));
+            }
+            self.allocated_blocks.insert(new_address, size);
+            next = new_address + (block_end - address);
+        }
+
+        let heap_pointer = self.heap_pointer;
+        self.bytes_mut(next, heap_pointer).fill(0);
+        self.heap_pointer = next;
+
+        let moved_sites: Vec<(u32, u32)> = relocations
+            .iter()
+            .filter_map(|(old, &new)| self.alloc_sites.remove(old).map(|pc| (new, pc)))
+            .collect();
+        self.alloc_sites.extend(moved_sites);
+        let moved_guards: Vec<u32> = relocations
+            .iter()
+            .filter(|&(old, _)| self.guarded_blocks.remove(old))
+            .map(|(_, &new)| new)
+            .collect();
+        self.guarded_blocks.extend(moved_guards);
+
+        if !relocations.is_empty() {
+            for &slot in pointer_slots {
+                let slot = relocated_slot(slot, &moved_blocks);
+                if let Ok(value) = self.read_word(slot)
+                    && let Some(&new_address) = relocations.get(&value)
+                {
+                    let _ = self.write_word(slot, new_address);
+                }
+            }
+        }
+
+        relocations
+    }
+
+    /// Free memory on the heap
+    ///
+    /// A block whose guard word was overwritten is left allocated and reported
+    /// as `HeapCorruption`.
+    pub fn free(&mut self, address: u32) -> VMResult<()> {
+        if let Some(&size) = self.allocated_blocks.get(&address) {
+            self.check_canary(address, size)?;
+            let guard = self.guard_size(address);
+            self.allocated_blocks.remove(&address);
+            self.guarded_blocks.remove(&address);
+            self.alloc_sites.remove(&address);
+            // Zero out the freed memory, its header and guard word for security
+            let block = address - OBJECT_HEADER_SIZE;
+            self.bytes_mut(block, address + size + guard).fill(0);
+            self.release_block(block, size + OBJECT_HEADER_SIZE + guard);
             Ok(())
         } else {
             Err(VMError::FreeFailed(address))
         }
     }
 
+    /// Return a block to the allocator, merging it with neighbouring free space
+    ///
+    /// Free space that ends at the heap pointer is given back to the bump region.
+    fn release_block(&mut self, address: u32, size: u32) {
+        let mut start = address;
+        let mut size = size;
+
+        if let Some((&prev, &prev_size)) = self.free_blocks.range(..start).next_back()
+            && prev + prev_size == start
+        {
+            self.free_blocks.remove(&prev);
+            start = prev;
+            size += prev_size;
+        }
+        if let Some(next_size) = self.free_blocks.remove(&(start + size)) {
+            size += next_size;
+        }
+
+        if start + size == self.heap_pointer {
+            self.heap_pointer = start;
+        } else {
+            self.free_blocks.insert(start, size);
+        }
+    }
+
     /// Check if an address is valid and allocated
     pub fn is_valid_address(&self, address: u32) -> bool {
         if address >= self.memory_size {
@@ -178,7 +962,7 @@ impl Memory {
 
     /// Push a value onto the stack
     pub fn stack_push(&mut self, value: u32) -> VMResult<()> {
-        if self.stack_pointer < self.heap_pointer + 4 {
+        if self.stack_pointer < self.stack_limit() + 4 {
             return Err(VMError::StackOverflow);
         }
         
@@ -202,9 +986,14 @@ impl Memory {
         self.stack_pointer
     }
 
+    /// Current stack contents as `(stack_base, stack_pointer)`; live words are in `[sp, base)`
+    pub fn stack_region(&self) -> (u32, u32) {
+        (self.stack_base, self.stack_pointer)
+    }
+
     /// Set stack pointer (use with caution)
     pub fn set_stack_pointer(&mut self, sp: u32) -> VMResult<()> {
-        if sp > self.stack_base || sp < self.heap_pointer {
+        if sp > self.stack_base || sp < self.stack_limit() {
             return Err(VMError::InvalidMemoryAddress(sp));
         }
         self.stack_pointer = sp;
@@ -213,12 +1002,15 @@ impl Memory {
 
     /// Get memory statistics
     pub fn get_stats(&self) -> MemoryStats {
+        let frontier = self.heap_limit() - self.heap_pointer;
         MemoryStats {
             total_memory: self.memory_size,
-            heap_used: self.heap_pointer - self.heap_base,
+            heap_used: self.allocated_blocks.values().sum(),
             stack_used: self.stack_base - self.stack_pointer,
             allocated_blocks: self.allocated_blocks.len(),
             heap_fragmentation: self.calculate_fragmentation(),
+            largest_free_block: self.free_blocks.values().copied().fold(frontier, u32::max),
+            total_free: self.free_blocks.values().sum::<u32>() + frontier,
         }
     }
 
@@ -228,7 +1020,11 @@ impl Memory {
             return 0.0;
         }
         
-        let total_allocated: u32 = self.allocated_blocks.values().sum();
+        let total_allocated: u32 = self
+            .allocated_blocks
+            .values()
+            .map(|size| size + OBJECT_HEADER_SIZE)
+            .sum();
         let heap_used = self.heap_pointer - self.heap_base;
         
         if heap_used == 0 {
@@ -239,33 +1035,48 @@ impl Memory {
     }
 
     /// Reset memory state
+    ///
+    /// Open snapshots are dropped; their handles can no longer be restored.
     pub fn reset(&mut self) {
         self.memory.fill(0);
+        self.snapshots.clear();
         self.stack_pointer = self.stack_base;
         self.heap_pointer = self.heap_base;
         self.allocated_blocks.clear();
+        self.free_blocks.clear();
+        self.alloc_sites.clear();
+        self.guarded_blocks.clear();
+        self.access_log.get_mut().clear();
+    }
+
+    /// Copy `length` bytes starting at `start` for inspection
+    ///
+    /// Unlike `read_bytes`, this is not recorded in the access log.
+    pub fn read_range(&self, start: u32, length: u32) -> VMResult<Vec<u8>> {
+        self.check_range(start, length)?;
+        Ok(self.memory[start as usize..(start + length) as usize].to_vec())
     }
 
     /// Dump memory contents for debugging
+    ///
+    /// Formats the bytes from `read_range` as hex and ASCII, 16 per line; the
+    /// range is cut short at the end of memory.
     pub fn dump_range(&self, start: u32, length: u32) -> String {
         let mut output = String::new();
         output.push_str(&format!("Memory dump from 0x{:08X} to 0x{:08X}:\n", 
                                 start, start + length));
-        
-        for i in (0..length).step_by(16) {
-            let addr = start + i;
-            if addr >= self.memory_size {
-                break;
-            }
-            
-            output.push_str(&format!("{:08X}: ", addr));
+
+        let available = length.min(self.memory_size.saturating_sub(start));
+        let bytes = self.read_range(start, available).unwrap_or_default();
+
+        for (row, chunk) in bytes.chunks(16).enumerate() {
+            output.push_str(&format!("{:08X}: ", start + row as u32 * 16));
             
             // Hex bytes
             for j in 0..16 {
-                if addr + j < self.memory_size && i + j < length {
-                    output.push_str(&format!("{:02X} ", self.memory[(addr + j) as usize]));
-                } else {
-                    output.push_str("   ");
+                match chunk.get(j) {
+                    Some(byte) => output.push_str(&format!("{:02X} ", byte)),
+                    None => output.push_str("   "),
                 }
             }
             
@@ -273,15 +1084,10 @@ impl Memory {
             
             // ASCII representation
             for j in 0..16 {
-                if addr + j < self.memory_size && i + j < length {
-                    let byte = self.memory[(addr + j) as usize];
-                    if byte >= 32 && byte <= 126 {
-                        output.push(byte as char);
-                    } else {
-                        output.push('.');
-                    }
-                } else {
-                    output.push(' ');
+                match chunk.get(j) {
+                    Some(&byte) if (32..=126).contains(&byte) => output.push(byte as char),
+                    Some(_) => output.push('.'),
+                    None => output.push(' '),
                 }
             }
             
@@ -300,6 +1106,13 @@ pub struct MemoryStats {
     pub stack_used: u32,
     pub allocated_blocks: usize,
     pub heap_fragmentation: f32,
+    /// Largest contiguous free heap span in bytes, header space included
+    ///
+    /// An allocation of `n` bytes succeeds only if `n` rounded up to a word plus
+    /// `OBJECT_HEADER_SIZE` fits.
+    pub largest_free_block: u32,
+    /// Free heap bytes across the free list and the unallocated top of the heap
+    pub total_free: u32,
 }
 
 impl std::fmt::Display for MemoryStats {
@@ -313,10 +1126,21 @@ impl std::fmt::Display for MemoryStats {
                 self.stack_used, self.stack_used as f32 / 1024.0)?;
         writeln!(f, "  Allocated Blocks: {}", self.allocated_blocks)?;
         writeln!(f, "  Heap Fragmentation: {:.1}%", self.heap_fragmentation * 100.0)?;
+        writeln!(f, "  Heap Free: {} bytes ({:.1} KB)", 
+                self.total_free, self.total_free as f32 / 1024.0)?;
+        writeln!(f, "  Largest Free Block: {} bytes", self.largest_free_block)?;
         Ok(())
     }
 }
 
+/// Where the word at `slot` ended up, given the `(old start, old end, new start)` of moved blocks
+fn relocated_slot(slot: u32, moved_blocks: &[(u32, u32, u32)]) -> u32 {
+    moved_blocks
+        .iter()
+        .find(|&&(start, end, _)| (start..end).contains(&slot))
+        .map_or(slot, |&(start, _, new_start)| new_start + (slot - start))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -328,6 +1152,77 @@ mod tests {
         assert_eq!(memory.heap_base, 0x10000);
     }
 
+    #[test]
+    fn test_custom_layout() {
+        let layout = MemoryLayout { code_size: 64, heap_size: 32, stack_size: 16 };
+        let mut memory = Memory::with_layout(128, layout).unwrap();
+        assert_eq!(memory.layout(), layout);
+        assert_eq!(memory.heap_region(), (64, 96));
+
+        // Heap allocations stay inside the heap region
+        assert_eq!(memory.allocate(8).unwrap(), 64 + OBJECT_HEADER_SIZE);
+        assert_eq!(memory.allocate(8).unwrap(), 80 + OBJECT_HEADER_SIZE);
+        assert!(memory.allocate(4).is_err());
+
+        // The stack holds exactly stack_size bytes at the top of memory
+        for i in 0..4 {
+            assert!(memory.stack_push(i).is_ok());
+        }
+        assert_eq!(memory.get_stack_pointer(), 112);
+        assert!(matches!(memory.stack_push(4), Err(VMError::StackOverflow)));
+        assert_eq!(memory.stack_pop().unwrap(), 3);
+
+        // Programs must fit in the code section
+        assert!(memory.load_program(&[0; 16]).is_ok());
+        assert!(memory.load_program(&[0; 17]).is_err());
+    }
+
+    #[test]
+    fn test_grow() {
+        let layout = MemoryLayout { code_size: 64, heap_size: 32, stack_size: 16 };
+        let mut memory = Memory::with_layout(128, layout).unwrap();
+
+        let block = memory.allocate(20).unwrap();
+        memory.write_word(block, 0xCAFEBABE).unwrap();
+        assert!(matches!(memory.allocate(4), Err(VMError::OutOfMemory)));
+
+        memory.stack_push(7).unwrap();
+        let frame = memory.get_stack_pointer();
+        memory.stack_push(frame).unwrap(); // saved pointer into the stack
+
+        memory.grow(64).unwrap();
+        assert_eq!(memory.get_stats().total_memory, 192);
+        assert_eq!(memory.heap_region(), (64, 160));
+        assert_eq!(memory.stack_region(), (192, 184));
+
+        // Existing heap data is untouched and the heap has room again
+        assert_eq!(memory.read_word(block).unwrap(), 0xCAFEBABE);
+        assert_eq!(memory.allocate(4).unwrap(), 92 + OBJECT_HEADER_SIZE);
+        assert_eq!(memory.allocate(40).unwrap(), 104 + OBJECT_HEADER_SIZE);
+
+        // The stack moved up with its words unchanged, even one that looks like a stack address
+        assert_eq!(memory.stack_pop().unwrap(), frame);
+        assert_eq!(memory.stack_pop().unwrap(), 7);
+        for i in 0..4 {
+            memory.stack_push(i).unwrap();
+        }
+        assert!(matches!(memory.stack_push(4), Err(VMError::StackOverflow)));
+
+        assert!(memory.grow(u32::MAX).is_err());
+    }
+
+    #[test]
+    fn test_invalid_layout() {
+        let layout = MemoryLayout { code_size: 64, heap_size: 64, stack_size: 16 };
+        assert!(matches!(
+            Memory::with_layout(128, layout),
+            Err(VMError::InvalidMemoryLayout(_))
+        ));
+
+        let overflowing = MemoryLayout { code_size: u32::MAX, heap_size: 1, stack_size: 0 };
+        assert!(Memory::with_layout(128, overflowing).is_err());
+    }
+
     #[test]
     fn test_byte_operations() {
         let mut memory = Memory::new(1024);
@@ -353,6 +1248,150 @@ mod tests {
         assert!(memory.write_word(1021, test_value).is_err());
     }
 
+    #[test]
+    fn test_halfword_operations() {
+        let mut memory = Memory::new(1024);
+
+        assert!(memory.write_word(100, 0xFFFFFFFF).is_ok());
+        assert!(memory.write_halfword(100, 0x1234).is_ok());
+        assert_eq!(memory.read_halfword(100).unwrap(), 0x1234);
+        assert_eq!(memory.read_word(100).unwrap(), 0xFFFF1234);
+
+        // Test boundary
+        assert!(memory.write_halfword(1023, 1).is_err());
+        assert!(memory.read_halfword(1023).is_err());
+    }
+
+    #[test]
+    fn test_bulk_byte_operations() {
+        let mut memory = Memory::new(1024);
+
+        assert!(memory.write_bytes(100, b"hello").is_ok());
+        assert_eq!(memory.read_bytes(100, 5).unwrap(), b"hello");
+        assert_eq!(memory.read_bytes(100, 0).unwrap(), Vec::<u8>::new());
+
+        // Out of bounds ranges fail without writing anything
+        assert!(memory.write_bytes(1020, b"hello").is_err());
+        assert_eq!(memory.read_bytes(1020, 4).unwrap(), vec![0; 4]);
+        assert!(memory.read_bytes(1020, 5).is_err());
+        assert!(memory.read_bytes(u32::MAX, 2).is_err());
+    }
+
+    #[test]
+    fn test_read_range() {
+        let mut memory = Memory::new(1024);
+        memory.write_bytes(0x40, b"Widow!").unwrap();
+        memory.write_word(0x48, 0x04030201).unwrap();
+
+        assert_eq!(memory.read_range(0x40, 6).unwrap(), b"Widow!");
+        assert_eq!(memory.read_range(0x46, 6).unwrap(), vec![0, 0, 1, 2, 3, 4]);
+        assert_eq!(memory.read_range(1020, 4).unwrap(), vec![0; 4]);
+        assert_eq!(memory.read_range(1020, 8), Err(VMError::InvalidMemoryAddress(1020)));
+
+        // The dump formats the same bytes
+        let dump = memory.dump_range(0x40, 16);
+        assert!(dump.contains("00000040: 57 69 64 6F 77 21 00 00 01 02 03 04 00 00 00 00  |Widow!..........|"));
+        let dump = memory.dump_range(1016, 16);
+        assert_eq!(dump.lines().count(), 2);
+        assert!(dump.ends_with("|........        |\n"));
+    }
+
+    #[test]
+    fn test_snapshot_restore() {
+        let mut memory = Memory::new(1024 * 1024);
+        memory.write_word(0x100, 0x11111111).unwrap();
+        memory.write_word(0x2000, 0x22222222).unwrap();
+        let kept = memory.allocate(16).unwrap();
+        memory.write_word(kept, 0x33333333).unwrap();
+        let heap_before = memory.get_stats();
+
+        let snap = memory.snapshot();
+        memory.write_word(0x100, 0xAAAAAAAA).unwrap();
+        memory.write_byte(0x103, 0xBB).unwrap();
+        memory.mem_set(0x1FFE, 0xCC, 8).unwrap(); // spans two pages
+        memory.write_word(kept, 0xDDDDDDDD).unwrap();
+        memory.free(kept).unwrap();
+        memory.allocate(64).unwrap();
+        memory.stack_push(7).unwrap();
+        // Only the pages written so far have been copied: one low page, two
+        // around 0x2000, one heap page and the top stack page
+        assert_eq!(memory.snapshots[0].pages.len(), 5);
+
+        memory.restore(snap).unwrap();
+        assert_eq!(memory.read_word(0x100).unwrap(), 0x11111111);
+        assert_eq!(memory.read_word(0x2000).unwrap(), 0x22222222);
+        assert_eq!(memory.read_range(0x1FFC, 4).unwrap(), vec![0; 4]);
+        assert_eq!(memory.read_word(kept).unwrap(), 0x33333333);
+        assert_eq!(memory.live_allocations(), vec![(kept, 16, NO_ALLOC_SITE)]);
+        assert_eq!(memory.get_stats().heap_used, heap_before.heap_used);
+        assert_eq!(memory.stack_region(), (1024 * 1024, 1024 * 1024));
+        assert_eq!(memory.open_snapshots(), 0);
+
+        // Untouched memory and handles that were already used
+        let snap = memory.snapshot();
+        assert_eq!(memory.read_word(0x4000).unwrap(), 0);
+        memory.release(snap).unwrap();
+        let stale = MemorySnapshot { id: 0 };
+        assert_eq!(memory.restore(stale), Err(VMError::UnknownSnapshot));
+    }
+
+    #[test]
+    fn test_nested_snapshots() {
+        let mut memory = Memory::new(1024 * 1024);
+        memory.write_word(0x100, 1).unwrap();
+
+        let outer = memory.snapshot();
+        memory.write_word(0x100, 2).unwrap();
+        let inner = memory.snapshot();
+        memory.write_word(0x100, 3).unwrap();
+        memory.write_word(0x5000, 4).unwrap();
+
+        // Releasing the inner snapshot keeps its changes but the outer one still covers them
+        memory.release(inner).unwrap();
+        assert_eq!(memory.read_word(0x100).unwrap(), 3);
+        memory.grow(4096).unwrap();
+        memory.restore(outer).unwrap();
+
+        assert_eq!(memory.read_word(0x100).unwrap(), 1);
+        assert_eq!(memory.read_word(0x5000).unwrap(), 0);
+        assert_eq!(memory.get_stats().total_memory, 1024 * 1024);
+    }
+
+    #[test]
+    fn test_mem_copy() {
+        let mut memory = Memory::new(1024);
+        for i in 0..8 {
+            memory.write_byte(100 + i, i as u8 + 1).unwrap();
+        }
+
+        // Non-overlapping copy
+        assert!(memory.mem_copy(200, 100, 8).is_ok());
+        for i in 0..8 {
+            assert_eq!(memory.read_byte(200 + i).unwrap(), i as u8 + 1);
+        }
+
+        // Forward-overlapping copy behaves like memmove
+        assert!(memory.mem_copy(102, 100, 6).is_ok());
+        let bytes: Vec<u8> = (100..108).map(|addr| memory.read_byte(addr).unwrap()).collect();
+        assert_eq!(bytes, vec![1, 2, 1, 2, 3, 4, 5, 6]);
+
+        // Out of bounds ranges are rejected
+        assert!(memory.mem_copy(1020, 100, 8).is_err());
+        assert!(memory.mem_copy(100, 1020, 8).is_err());
+    }
+
+    #[test]
+    fn test_mem_set() {
+        let mut memory = Memory::new(1024);
+
+        assert!(memory.mem_set(100, 0xAA, 4).is_ok());
+        assert_eq!(memory.read_word(100).unwrap(), 0xAAAAAAAA);
+        assert_eq!(memory.read_byte(104).unwrap(), 0);
+
+        assert!(memory.mem_set(1000, 0xAA, 100).is_err());
+        assert!(memory.mem_set(u32::MAX, 0xAA, 2).is_err());
+    }
+
     #[test]
     fn test_stack_operations() {
         let mut memory = Memory::new(1024 * 1024);
@@ -361,6 +1400,7 @@ mod tests {
         assert!(memory.stack_push(42).is_ok());
         assert!(memory.stack_push(100).is_ok());
         
+        assert_eq!(memory.stack_region(), (1024 * 1024, 1024 * 1024 - 8));
         assert_eq!(memory.stack_pop().unwrap(), 100);
         assert_eq!(memory.stack_pop().unwrap(), 42);
     }
@@ -385,6 +1425,304 @@ mod tests {
         assert!(memory.free(addr1).is_err());
     }
 
+    #[test]
+    fn test_free_list_reuse() {
+        let mut memory = Memory::new(1024 * 1024);
+
+        let a = memory.allocate(16).unwrap();
+        let b = memory.allocate(16).unwrap();
+        let c = memory.allocate(16).unwrap();
+        let _d = memory.allocate(16).unwrap();
+
+        // Freed blocks are reused first-fit, splitting larger holes
+        memory.free(a).unwrap();
+        memory.free(b).unwrap(); // coalesces with a
+        assert_eq!(memory.get_stats().heap_used, 32);
+        assert_eq!(memory.allocate(8).unwrap(), a);
+        assert_eq!(memory.allocate(24).unwrap(), a + 8 + OBJECT_HEADER_SIZE);
+
+        // Freeing the top block returns space to the bump region
+        let top = memory.allocate(16).unwrap();
+        memory.free(top).unwrap();
+        assert_eq!(memory.allocate(16).unwrap(), top);
+
+        memory.free(c).unwrap();
+        assert_eq!(memory.allocate(12).unwrap(), c);
+    }
+
+    #[test]
+    fn test_free_space_stats() {
+        let layout = MemoryLayout { code_size: 64, heap_size: 128, stack_size: 16 };
+        let mut memory = Memory::with_layout(256, layout).unwrap();
+
+        let stats = memory.get_stats();
+        assert_eq!(stats.total_free, 128);
+        assert_eq!(stats.largest_free_block, 128);
+
+        // Four 32-byte blocks fill the heap; freeing every other one leaves two holes
+        let blocks: Vec<u32> = (0..4).map(|_| memory.allocate(24).unwrap()).collect();
+        assert_eq!(memory.get_stats().total_free, 0);
+        memory.free(blocks[0]).unwrap();
+        memory.free(blocks[2]).unwrap();
+
+        let stats = memory.get_stats();
+        assert_eq!(stats.total_free, 64);
+        assert_eq!(stats.largest_free_block, 32);
+        assert!(stats.largest_free_block < stats.total_free);
+        assert!(memory.allocate(40).is_err());
+        assert!(memory.allocate(24).is_ok());
+
+        assert!(stats.to_string().contains("Largest Free Block: 32 bytes"));
+    }
+
+    #[test]
+    fn test_alloc_strategies() {
+        let place = |strategy| {
+            let mut memory = Memory::new(1024 * 1024);
+            memory.set_alloc_strategy(strategy);
+
+            // Holes of 40, 24 and 72 bytes (headers included), kept apart by live blocks
+            let mut holes = Vec::new();
+            for size in [32, 16, 64] {
+                holes.push(memory.allocate(size).unwrap());
+                memory.allocate(4).unwrap();
+            }
+            for &hole in &holes {
+                memory.free(hole).unwrap();
+            }
+
+            let address = memory.allocate(12).unwrap();
+            holes.iter().position(|&hole| hole == address).unwrap()
+        };
+
+        assert_eq!(place(AllocStrategy::FirstFit), 0);
+        assert_eq!(place(AllocStrategy::BestFit), 1);
+        assert_eq!(place(AllocStrategy::WorstFit), 2);
+    }
+
+    #[test]
+    fn test_live_allocations_report_sites() {
+        let mut memory = Memory::new(1024 * 1024);
+        let a = memory.allocate(8).unwrap();
+        let b = memory.allocate(16).unwrap();
+        let host = memory.allocate(4).unwrap();
+        memory.record_alloc_site(a, 0x10).unwrap();
+        memory.record_alloc_site(b, 0x20).unwrap();
+        assert_eq!(memory.record_alloc_site(0x4, 0x30), Err(VMError::InvalidMemoryAddress(0x4)));
+
+        assert_eq!(
+            memory.live_allocations(),
+            vec![(a, 8, 0x10), (b, 16, 0x20), (host, 4, NO_ALLOC_SITE)]
+        );
+
+        // Sites follow blocks that move and disappear with freed ones
+        memory.free(a).unwrap();
+        let moved = memory.reallocate(b, 64).unwrap();
+        assert_eq!(memory.live_allocations(), vec![(host, 4, NO_ALLOC_SITE), (moved, 64, 0x20)]);
+    }
+
+    #[test]
+    fn test_zero_on_alloc() {
+        let mut memory = Memory::new(1024 * 1024);
+        memory.set_zero_on_alloc(true);
+
+        // Free clears a block, so dirty it again after it went back on the free list
+        let block = memory.allocate(16).unwrap();
+        memory.free(block).unwrap();
+        memory.write_word(block, 0xDEAD_BEEF).unwrap();
+        memory.write_word(block + 12, 0xDEAD_BEEF).unwrap();
+        let reused = memory.allocate(16).unwrap();
+        assert_eq!(reused, block);
+        assert_eq!(memory.read_bytes(reused, 16).unwrap(), vec![0; 16]);
+
+        // Stale bytes written past the heap pointer are cleared only with the option on
+        let next = reused + 16 + OBJECT_HEADER_SIZE;
+        memory.write_word(next, 0xDEAD_BEEF).unwrap();
+        memory.write_word(next + 8 + OBJECT_HEADER_SIZE, 0xDEAD_BEEF).unwrap();
+        assert_eq!(memory.allocate(8).unwrap(), next);
+        assert_eq!(memory.read_word(next).unwrap(), 0);
+
+        memory.set_zero_on_alloc(false);
+        let dirty = memory.allocate(8).unwrap();
+        assert_eq!(memory.read_word(dirty).unwrap(), 0xDEAD_BEEF);
+    }
+
+    #[test]
+    fn test_access_log() {
+        let mut memory = Memory::new(1024 * 1024);
+        memory.write_word(0x100, 7).unwrap(); // not logged yet
+
+        memory.set_access_logging(true);
+        memory.write_word(0x200, 1).unwrap();
+        memory.read_byte(0x200).unwrap();
+        memory.write_halfword(0x204, 2).unwrap();
+        memory.read_word(0x100).unwrap();
+        assert!(memory.read_word(u32::MAX - 3).is_err()); // failed accesses are not logged
+        memory.mem_copy(0x300, 0x200, 8).unwrap();
+
+        assert_eq!(memory.access_log(), vec![
+            (AccessKind::Write, 0x200, 4),
+            (AccessKind::Read, 0x200, 1),
+            (AccessKind::Write, 0x204, 2),
+            (AccessKind::Read, 0x100, 4),
+            (AccessKind::Read, 0x200, 8),
+            (AccessKind::Write, 0x300, 8),
+        ]);
+
+        // The log is a ring buffer keeping only the newest entries
+        memory.clear_access_log();
+        for i in 0..ACCESS_LOG_CAPACITY as u32 + 2 {
+            memory.read_byte(i).unwrap();
+        }
+        let log = memory.access_log();
+        assert_eq!(log.len(), ACCESS_LOG_CAPACITY);
+        assert_eq!(log[0], (AccessKind::Read, 2, 1));
+    }
+
+    #[test]
+    fn test_heap_canaries() {
+        let mut memory = Memory::new(1024 * 1024);
+        memory.set_heap_canaries(true);
+
+        let first = memory.allocate(8).unwrap();
+        let second = memory.allocate(8).unwrap();
+        // Each block is followed by its guard word
+        assert_eq!(second, first + 8 + CANARY_SIZE + OBJECT_HEADER_SIZE);
+        assert_eq!(memory.read_word(first + 8).unwrap(), HEAP_CANARY);
+        assert_eq!(memory.check_canaries(), Ok(()));
+
+        // Writing the last word of the block is fine
+        memory.write_word(first + 4, 1).unwrap();
+        memory.free(first).unwrap();
+
+        // One word past the end clobbers the guard
+        memory.write_word(second + 8, 0xDEADBEEF).unwrap();
+        assert_eq!(memory.check_canaries(), Err(VMError::HeapCorruption(second)));
+        assert_eq!(memory.free(second), Err(VMError::HeapCorruption(second)));
+        assert_eq!(memory.get_stats().allocated_blocks, 1);
+
+        // Guards survive resizing
+        memory.write_word(second + 8, HEAP_CANARY).unwrap();
+        let grown = memory.reallocate(second, 16).unwrap();
+        assert_eq!(grown, second);
+        assert_eq!(memory.read_word(second + 16).unwrap(), HEAP_CANARY);
+        let shrunk = memory.reallocate(second, 4).unwrap();
+        assert_eq!(memory.read_word(shrunk + 4).unwrap(), HEAP_CANARY);
+        memory.free(shrunk).unwrap();
+
+        let (heap_start, _) = memory.heap_region();
+        assert_eq!(memory.get_stats().heap_used, 0);
+        assert_eq!(memory.allocate(4).unwrap(), heap_start + OBJECT_HEADER_SIZE);
+    }
+
+    #[test]
+    fn test_strict_bounds() {
+        let mut memory = Memory::new(1024 * 1024);
+        let block = memory.allocate(8).unwrap();
+        let (_, heap_end) = memory.heap_region();
+
+        // Off by default
+        assert_eq!(memory.check_access(block + 8, 4), Ok(()));
+
+        memory.set_strict_bounds(true);
+        assert_eq!(memory.check_access(block, 4), Ok(()));
+        assert_eq!(memory.check_access(block + 4, 4), Ok(()));
+        assert_eq!(memory.check_access(block + 6, 4), Err(VMError::MemoryAccessViolation(block + 6)));
+        assert_eq!(memory.check_access(block + 8, 4), Err(VMError::MemoryAccessViolation(block + 8)));
+        assert_eq!(memory.check_access(block - 4, 4), Err(VMError::MemoryAccessViolation(block - 4)));
+
+        // Code and stack are exempt
+        assert_eq!(memory.check_access(0, 4), Ok(()));
+        assert_eq!(memory.check_access(heap_end, 4), Ok(()));
+    }
+
+    #[test]
+    fn test_object_header() {
+        let mut memory = Memory::new(1024 * 1024);
+        let (heap_base, _) = memory.heap_region();
+
+        let a = memory.allocate_tagged(10, 7).unwrap();
+        assert_eq!(a, heap_base + OBJECT_HEADER_SIZE);
+        assert_eq!(memory.object_header(a).unwrap(), ObjectHeader { size: 12, type_tag: 7 });
+
+        // User data starts right after the header and doesn't disturb it
+        memory.write_word(a, 0xDEADBEEF).unwrap();
+        let b = memory.allocate(4).unwrap();
+        assert_eq!(b, a + 12 + OBJECT_HEADER_SIZE);
+        assert_eq!(memory.read_word(b - OBJECT_HEADER_SIZE).unwrap(), 4);
+        assert_eq!(memory.object_header(a).unwrap().size, 12);
+
+        memory.set_type_tag(b, 3).unwrap();
+        assert_eq!(memory.object_header(b).unwrap(), ObjectHeader { size: 4, type_tag: 3 });
+
+        // Headers follow reallocation and are cleared on free
+        let moved = memory.reallocate(a, 64).unwrap();
+        assert_eq!(memory.object_header(moved).unwrap(), ObjectHeader { size: 64, type_tag: 7 });
+        assert!(memory.object_header(a).is_err());
+        assert_eq!(memory.read_word(a - OBJECT_HEADER_SIZE).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_reallocate() {
+        let mut memory = Memory::new(1024 * 1024);
+
+        let block = memory.allocate(16).unwrap();
+        let _next = memory.allocate(8).unwrap(); // Prevents growing in place
+        for i in 0..4 {
+            memory.write_word(block + i * 4, 0x1000 + i).unwrap();
+        }
+
+        let moved = memory.reallocate(block, 64).unwrap();
+        assert_ne!(moved, block);
+        for i in 0..4 {
+            assert_eq!(memory.read_word(moved + i * 4).unwrap(), 0x1000 + i);
+        }
+        assert!(!memory.is_valid_address(block));
+        assert!(memory.is_valid_address(moved + 60));
+
+        // The last block on the heap grows in place
+        let grown = memory.reallocate(moved, 128).unwrap();
+        assert_eq!(grown, moved);
+        assert_eq!(memory.read_word(grown + 12).unwrap(), 0x1003);
+
+        // Shrinking keeps the address and the leading contents
+        let shrunk = memory.reallocate(grown, 8).unwrap();
+        assert_eq!(shrunk, grown);
+        assert_eq!(memory.read_word(shrunk + 4).unwrap(), 0x1001);
+        assert!(!memory.is_valid_address(shrunk + 8));
+
+        assert!(memory.reallocate(block, 32).is_err());
+    }
+
+    #[test]
+    fn test_compact() {
+        let mut memory = Memory::new(1024 * 1024);
+
+        let a = memory.allocate(16).unwrap();
+        let b = memory.allocate(16).unwrap();
+        let c = memory.allocate(16).unwrap();
+        memory.write_word(c, 0xC0FFEE).unwrap();
+        memory.write_word(c + 4, c).unwrap(); // c -> c, moving along with it
+        memory.write_word(a, c).unwrap(); // a -> c
+        memory.write_word(a + 4, c).unwrap(); // an integer that happens to equal c
+        memory.stack_push(c).unwrap();
+        memory.free(b).unwrap();
+
+        let relocations = memory.compact(&HashSet::new(), &[a, c + 4]);
+        assert_eq!(relocations.len(), 1);
+        assert_eq!(relocations[&c], b);
+
+        assert_eq!(memory.read_word(b).unwrap(), 0xC0FFEE);
+        assert_eq!(memory.read_word(b + 4).unwrap(), b);
+        assert_eq!(memory.read_word(a).unwrap(), b);
+
+        // Words that were not named as pointer slots keep their value
+        assert_eq!(memory.read_word(a + 4).unwrap(), c);
+        assert_eq!(memory.stack_pop().unwrap(), c);
+        assert_eq!(memory.get_stats().heap_used, 32);
+        assert!(!memory.is_valid_address(c));
+    }
+
     #[test]
     fn test_program_loading() {
         let mut memory = Memory::new(1024 * 1024);
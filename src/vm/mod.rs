@@ -4,6 +4,14 @@ pub mod error;
 pub mod registers;
 pub mod gc;
 
-pub use vm::VM;
-pub use error::VMError;
-pub use gc::{GarbageCollector, GCConfig, GCStats};
\ No newline at end of file
+pub use vm::{
+    DEFAULT_MAX_CALL_DEPTH, MAX_STRING_LENGTH, SYSCALL_EXIT, SYSCALL_RANDOM, SYSCALL_TIME,
+    SYSCALL_WRITE_STRING, StepOutcome, TraceHook, VMSnapshot, VM,
+};
+pub use error::{IoError, RuntimeError, RuntimeResult, ValidationError, VMError};
+pub use gc::{GarbageCollector, GCConfig, GCStats, GcEvent, GcEventListener, GcStrategy};
+pub use memory::{
+    AccessKind, AllocStrategy, MemoryLayout, MemorySnapshot, ObjectHeader, ACCESS_LOG_CAPACITY, HEAP_CANARY, NO_ALLOC_SITE,
+    OBJECT_HEADER_SIZE,
+};
+pub use registers::RegisterWriteObserver;
\ No newline at end of file
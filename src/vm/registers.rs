@@ -1,9 +1,66 @@
 use crate::vm::error::{VMError, VMResult};
 
-/// Register file containing 32 general-purpose registers
-#[derive(Debug, Clone)]
+/// Condition flags set by the CMP instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Flags {
+    /// Result was zero (operands equal)
+    pub zero: bool,
+    /// Result was negative
+    pub negative: bool,
+    /// Unsigned borrow occurred (rs < rt as unsigned)
+    pub carry: bool,
+    /// Signed overflow occurred
+    pub overflow: bool,
+}
+
+impl Flags {
+    /// Compute flags from the subtraction `lhs - rhs`
+    pub fn from_compare(lhs: i32, rhs: i32) -> Self {
+        let (result, overflow) = lhs.overflowing_sub(rhs);
+        Self {
+            zero: result == 0,
+            negative: result < 0,
+            carry: (lhs as u32) < (rhs as u32),
+            overflow,
+        }
+    }
+}
+
+/// Callback invoked with the register number, old value and new value on every write
+pub type RegisterWriteObserver = Box<dyn FnMut(u8, i32, i32)>;
+
+/// Register file containing 32 general-purpose registers and 32 float registers
 pub struct RegisterFile {
     registers: [i32; 32],
+    /// Separate bank used by the floating-point instructions
+    float_registers: [f32; 32],
+    /// When set, R0 always reads as 0 and writes to it are discarded
+    zero_register: bool,
+    /// Optional callback notified of every register write
+    on_write: Option<RegisterWriteObserver>,
+}
+
+impl std::fmt::Debug for RegisterFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("RegisterFile")
+            .field("registers", &self.registers)
+            .field("float_registers", &self.float_registers)
+            .field("zero_register", &self.zero_register)
+            .field("on_write", &self.on_write.is_some())
+            .finish()
+    }
+}
+
+/// Clones carry the register values but not the write observer
+impl Clone for RegisterFile {
+    fn clone(&self) -> Self {
+        Self {
+            registers: self.registers,
+            float_registers: self.float_registers,
+            zero_register: self.zero_register,
+            on_write: None,
+        }
+    }
 }
 
 impl RegisterFile {
@@ -11,9 +68,60 @@ impl RegisterFile {
     pub fn new() -> Self {
         Self {
             registers: [0; 32],
+            float_registers: [0.0; 32],
+            zero_register: false,
+            on_write: None,
         }
     }
 
+    /// Create a register file, optionally hardwiring R0 to zero
+    ///
+    /// `InstructionBuilder` fills unused operands with R0, but only in fields
+    /// the instruction never reads (e.g. `rt` of `MOV` and `NOT`), so those
+    /// instructions behave the same either way.
+    pub fn with_zero_register(enabled: bool) -> Self {
+        Self {
+            registers: [0; 32],
+            float_registers: [0.0; 32],
+            zero_register: enabled,
+            on_write: None,
+        }
+    }
+
+    /// Copy register values and settings from `other`, keeping this file's observer
+    ///
+    /// The observer is not notified of the copied values.
+    pub fn copy_from(&mut self, other: &RegisterFile) {
+        self.registers = other.registers;
+        self.float_registers = other.float_registers;
+        self.zero_register = other.zero_register;
+    }
+
+    /// Install a callback observing every write as `(register, old, new)`
+    ///
+    /// Writes dropped by the hardwired zero register are not reported.
+    pub fn set_on_write(&mut self, observer: RegisterWriteObserver) {
+        self.on_write = Some(observer);
+    }
+
+    /// Remove the write observer, if any
+    pub fn clear_on_write(&mut self) {
+        self.on_write = None;
+    }
+
+    /// Enable or disable the hardwired zero register, clearing R0 when enabled
+    pub fn set_zero_register(&mut self, enabled: bool) {
+        self.zero_register = enabled;
+        if enabled {
+            self.registers[0] = 0;
+        }
+    }
+
+    /// Whether R0 is hardwired to zero
+    pub fn has_zero_register(&self) -> bool {
+        self.zero_register
+    }
+
     /// Read value from a register
     pub fn read(&self, reg: u8) -> VMResult<i32> {
         if reg >= 32 {
@@ -28,13 +136,54 @@ impl RegisterFile {
             return Err(VMError::InvalidRegister(reg));
         }
         
-        // Register 0 is typically read-only zero register in many architectures
-        // Uncomment the following lines if you want R0 to always be zero:
-        // if reg == 0 {
-        //     return Ok(()); // Ignore writes to R0
-        // }
-        
-        self.registers[reg as usize] = value;
+        // With a hardwired zero register, writes to R0 are silently dropped
+        if reg == 0 && self.zero_register {
+            return Ok(());
+        }
+
+        let old = std::mem::replace(&mut self.registers[reg as usize], value);
+        if let Some(observer) = self.on_write.as_mut() {
+            observer(reg, old, value);
+        }
+        Ok(())
+    }
+
+    /// Read a register as an unsigned 32-bit value (e.g. an address)
+    pub fn read_u32(&self, reg: u8) -> VMResult<u32> {
+        self.read(reg).map(|value| value as u32)
+    }
+
+    /// Write an unsigned 32-bit value, stored with the same bit pattern
+    pub fn write_u32(&mut self, reg: u8, value: u32) -> VMResult<()> {
+        self.write(reg, value as i32)
+    }
+
+    /// Read a register as a boolean (nonzero is true)
+    pub fn read_bool(&self, reg: u8) -> VMResult<bool> {
+        self.read(reg).map(|value| value != 0)
+    }
+
+    /// Write a boolean as 1 or 0
+    pub fn write_bool(&mut self, reg: u8, value: bool) -> VMResult<()> {
+        self.write(reg, value as i32)
+    }
+
+    /// Read a float register
+    pub fn read_float(&self, reg: u8) -> VMResult<f32> {
+        if reg >= 32 {
+            return Err(VMError::InvalidRegister(reg));
+        }
+        Ok(self.float_registers[reg as usize])
+    }
+
+    /// Write a float register
+    ///
+    /// The float bank has no zero register and writes are not reported to the observer.
+    pub fn write_float(&mut self, reg: u8, value: f32) -> VMResult<()> {
+        if reg >= 32 {
+            return Err(VMError::InvalidRegister(reg));
+        }
+        self.float_registers[reg as usize] = value;
         Ok(())
     }
 
@@ -46,6 +195,7 @@ impl RegisterFile {
     /// Reset all registers to zero
     pub fn reset(&mut self) {
         self.registers = [0; 32];
+        self.float_registers = [0.0; 32];
     }
 
     /// Dump register state for debugging
@@ -75,7 +225,7 @@ impl RegisterFile {
         }
         
         for (i, &value) in values.iter().enumerate() {
-            self.registers[i] = value;
+            self.write(i as u8, value)?;
         }
         
         Ok(())
@@ -83,7 +233,9 @@ impl RegisterFile {
 
     /// Check if two register files are equal (useful for testing)
     pub fn equals(&self, other: &RegisterFile) -> bool {
+        // Float registers are compared bitwise so NaNs match themselves
         self.registers == other.registers
+            && self.float_registers.map(f32::to_bits) == other.float_registers.map(f32::to_bits)
     }
 }
 
@@ -147,6 +299,98 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_zero_register_drops_writes() {
+        let mut regs = RegisterFile::with_zero_register(true);
+        regs.write(0, 42).unwrap();
+        regs.write(1, 7).unwrap();
+        assert_eq!(regs.read(0).unwrap(), 0);
+        assert_eq!(regs.read(1).unwrap(), 7);
+
+        regs.set_from_slice(&[5, 6]).unwrap();
+        assert_eq!(regs.read(0).unwrap(), 0);
+        assert_eq!(regs.read(1).unwrap(), 6);
+
+        // Resetting keeps the register hardwired
+        regs.reset();
+        regs.write(0, 1).unwrap();
+        assert_eq!(regs.read(0).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_zero_register_disabled_by_default() {
+        let mut regs = RegisterFile::new();
+        assert!(!regs.has_zero_register());
+        regs.write(0, 42).unwrap();
+        assert_eq!(regs.read(0).unwrap(), 42);
+
+        // Enabling later clears whatever R0 held
+        regs.set_zero_register(true);
+        assert_eq!(regs.read(0).unwrap(), 0);
+        regs.set_zero_register(false);
+        regs.write(0, 3).unwrap();
+        assert_eq!(regs.read(0).unwrap(), 3);
+    }
+
+    #[test]
+    fn test_typed_accessors() {
+        let mut regs = RegisterFile::new();
+
+        regs.write_u32(3, 0xFFFFFFFF).unwrap();
+        assert_eq!(regs.read(3).unwrap(), -1);
+        assert_eq!(regs.read_u32(3).unwrap(), 0xFFFFFFFF);
+        assert!(regs.read_bool(3).unwrap());
+
+        regs.write_bool(4, true).unwrap();
+        assert_eq!(regs.read(4).unwrap(), 1);
+        regs.write_bool(4, false).unwrap();
+        assert!(!regs.read_bool(4).unwrap());
+
+        assert!(matches!(regs.read_u32(32), Err(VMError::InvalidRegister(32))));
+        assert!(matches!(regs.write_bool(40, true), Err(VMError::InvalidRegister(40))));
+    }
+
+    #[test]
+    fn test_float_registers() {
+        let mut regs = RegisterFile::with_zero_register(true);
+        regs.write_float(0, 1.5).unwrap();
+        regs.write_float(31, -2.25).unwrap();
+        assert_eq!(regs.read_float(0).unwrap(), 1.5);
+        assert_eq!(regs.read_float(31).unwrap(), -2.25);
+        assert_eq!(regs.read(0).unwrap(), 0); // the banks are independent
+        assert_eq!(regs.read_float(32), Err(VMError::InvalidRegister(32)));
+
+        let copy = regs.clone();
+        assert!(copy.equals(&regs));
+        regs.reset();
+        assert_eq!(regs.read_float(31).unwrap(), 0.0);
+        assert!(!copy.equals(&regs));
+    }
+
+    #[test]
+    fn test_write_observer() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let sink = Rc::clone(&log);
+
+        let mut regs = RegisterFile::with_zero_register(true);
+        regs.set_on_write(Box::new(move |reg, old, new| sink.borrow_mut().push((reg, old, new))));
+        regs.write(2, 10).unwrap();
+        regs.write(2, 11).unwrap();
+        regs.write(0, 5).unwrap(); // dropped, not reported
+
+        // Clones don't share the observer
+        let mut copy = regs.clone();
+        copy.write(3, 1).unwrap();
+
+        regs.clear_on_write();
+        regs.write(4, 1).unwrap();
+
+        assert_eq!(*log.borrow(), vec![(2, 0, 10), (2, 10, 11)]);
+    }
+
     #[test]
     fn test_set_from_slice() {
         let mut regs = RegisterFile::new();
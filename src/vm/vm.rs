@@ -5,18 +5,111 @@ use crate::compiler::{
     decode::decode,
 };
 use crate::vm::{
-    error::{VMError, VMResult},
+    error::{RuntimeError, RuntimeResult, ValidationError, VMError, VMResult},
     memory::Memory,
-    registers::RegisterFile,
+    registers::{Flags, RegisterFile},
     gc::{GarbageCollector, GCConfig},
 };
-use std::io::{self, Write};
+use std::collections::{HashMap, HashSet};
+use std::io::{self, BufRead, Write};
+use std::time::Instant;
+
+/// Callback invoked with the PC and decoded form of each executed instruction
+pub type TraceHook = Box<dyn FnMut(u32, &InstructionType)>;
+
+/// Default limit on nested CALLs before `VMError::CallStackOverflow`
+pub const DEFAULT_MAX_CALL_DEPTH: u32 = 10_000;
+
+/// SYSCALL number (read from `rs`) that stops execution
+pub const SYSCALL_EXIT: i32 = 1;
+/// SYSCALL number that writes a pseudo-random `i32` into `rd`
+pub const SYSCALL_RANDOM: i32 = 2;
+/// SYSCALL number that writes the NUL-terminated string whose address is in `rd`
+pub const SYSCALL_WRITE_STRING: i32 = 3;
+
+/// SYSCALL number that writes milliseconds since the VM was created into `rd`
+pub const SYSCALL_TIME: i32 = 4;
+
+/// Longest string `SYSCALL_WRITE_STRING` scans before giving up on a missing terminator
+pub const MAX_STRING_LENGTH: u32 = 64 * 1024;
+
+/// Seed used until `VM::seed_rng` is called
+const DEFAULT_RNG_SEED: u64 = 0x2545_F491_4F6C_DD1D;
+
+/// Small xorshift64* generator, so programs get reproducible randomness without extra dependencies
+#[derive(Debug, Clone, Copy)]
+struct XorShift64 {
+    state: u64,
+}
+
+impl XorShift64 {
+    fn new(seed: u64) -> Self {
+        // An all-zero state would only ever produce zeros
+        Self { state: if seed == 0 { DEFAULT_RNG_SEED } else { seed } }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+}
+
+/// Register every instruction that moves the stack pointer mirrors it into
+const STACK_POINTER_REGISTER: u8 = 29;
+/// Frame pointer register saved and restored by `ENTER` and `LEAVE`
+const FRAME_POINTER_REGISTER: u8 = 30;
+
+/// Why `VM::run_steps` returned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepOutcome {
+    /// A `HALT` (or exit syscall) stopped the program
+    Halted,
+    /// This many instructions ran and the program can continue
+    Ran(u64),
+    /// Execution paused before the breakpoint at this address
+    Breakpoint(u32),
+}
+
+/// Saved execution state produced by `VM::snapshot`
+///
+/// Hooks, breakpoints, observers and I/O streams belong to the host rather
+/// than the program, so they are not captured.
+#[derive(Debug, Clone)]
+pub struct VMSnapshot {
+    registers: RegisterFile,
+    flags: Flags,
+    memory: Memory,
+    gc: GarbageCollector,
+    pc: u32,
+    running: bool,
+    halted: bool,
+    instruction_count: u64,
+    code_len: u32,
+    allocations_since_gc: u64,
+    call_stack: Vec<u32>,
+    rng: XorShift64,
+}
+
+impl VMSnapshot {
+    /// Program counter at the time of the snapshot
+    pub fn pc(&self) -> u32 {
+        self.pc
+    }
+
+    /// Instructions executed at the time of the snapshot
+    pub fn instruction_count(&self) -> u64 {
+        self.instruction_count
+    }
+}
 
 /// The main virtual machine for executing bytecode
-#[derive(Debug)]
 pub struct VM {
     /// Register file (32 general-purpose registers)
     registers: RegisterFile,
+    /// Condition flags (set by CMP)
+    flags: Flags,
     /// Memory subsystem
     memory: Memory,
     /// Garbage collector
@@ -25,10 +118,67 @@ pub struct VM {
     pc: u32,
     /// Execution state
     running: bool,
+    /// Set once the program executes `HALT` or the exit syscall
+    halted: bool,
     /// Instruction count for debugging/profiling
     instruction_count: u64,
+    /// Size in bytes of the loaded program; fetching at or past it is an error
+    code_len: u32,
     /// Automatic GC enabled
     auto_gc: bool,
+    /// Allocations since the last full collection, for the allocation-count trigger
+    allocations_since_gc: u64,
+    /// Shadow stack of return addresses pushed by CALL and not yet consumed by RET
+    call_stack: Vec<u32>,
+    /// Deepest nesting of CALLs allowed before reporting a call stack overflow
+    max_call_depth: u32,
+    /// Generator behind the random syscall
+    rng: XorShift64,
+    /// When the VM was created, for the time syscall
+    started_at: Instant,
+    /// Fixed value reported by the time syscall instead of the real clock
+    clock_override: Option<u64>,
+    /// Whether executed opcodes are counted in `opcode_profile`
+    profiling: bool,
+    /// Execution count per opcode mnemonic, while profiling is enabled
+    opcode_profile: HashMap<&'static str, u64>,
+    /// Optional per-instruction trace callback
+    trace_hook: Option<TraceHook>,
+    /// Code addresses that pause `run` before executing
+    breakpoints: HashSet<u32>,
+    /// Breakpoint that paused the last run, if any
+    hit_breakpoint: Option<u32>,
+    /// Memory addresses whose modification pauses `run`
+    watchpoints: HashSet<u32>,
+    /// Watchpoint written by the instruction that paused the last run, if any
+    hit_watchpoint: Option<u32>,
+    /// Output sink for PRINT
+    out: Box<dyn Write>,
+    /// Input source for READ
+    inp: Box<dyn BufRead>,
+}
+
+impl std::fmt::Debug for VM {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("VM")
+            .field("registers", &self.registers)
+            .field("flags", &self.flags)
+            .field("memory", &self.memory)
+            .field("gc", &self.gc)
+            .field("pc", &self.pc)
+            .field("running", &self.running)
+            .field("instruction_count", &self.instruction_count)
+            .field("auto_gc", &self.auto_gc)
+            .field("call_stack", &self.call_stack)
+            .field("max_call_depth", &self.max_call_depth)
+            .field("profiling", &self.profiling)
+            .field("trace_hook", &self.trace_hook.is_some())
+            .field("breakpoints", &self.breakpoints)
+            .field("hit_breakpoint", &self.hit_breakpoint)
+            .field("watchpoints", &self.watchpoints)
+            .field("hit_watchpoint", &self.hit_watchpoint)
+            .finish_non_exhaustive()
+    }
 }
 
 impl VM {
@@ -36,12 +186,30 @@ impl VM {
     pub fn new(memory_size: u32) -> Self {
         Self {
             registers: RegisterFile::new(),
+            flags: Flags::default(),
             memory: Memory::new(memory_size),
             gc: GarbageCollector::new_default(),
             pc: 0,
             running: false,
+            halted: false,
             instruction_count: 0,
+            code_len: 0,
             auto_gc: true,
+            allocations_since_gc: 0,
+            call_stack: Vec::new(),
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+            rng: XorShift64::new(DEFAULT_RNG_SEED),
+            started_at: Instant::now(),
+            clock_override: None,
+            profiling: false,
+            opcode_profile: HashMap::new(),
+            trace_hook: None,
+            breakpoints: HashSet::new(),
+            hit_breakpoint: None,
+            watchpoints: HashSet::new(),
+            hit_watchpoint: None,
+            out: Box::new(io::stdout()),
+            inp: Box::new(io::BufReader::new(io::stdin())),
         }
     }
 
@@ -49,12 +217,30 @@ impl VM {
     pub fn new_with_gc(memory_size: u32, gc_config: GCConfig) -> Self {
         Self {
             registers: RegisterFile::new(),
+            flags: Flags::default(),
             memory: Memory::new(memory_size),
             gc: GarbageCollector::new(gc_config),
             pc: 0,
             running: false,
+            halted: false,
             instruction_count: 0,
+            code_len: 0,
             auto_gc: true,
+            allocations_since_gc: 0,
+            call_stack: Vec::new(),
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+            rng: XorShift64::new(DEFAULT_RNG_SEED),
+            started_at: Instant::now(),
+            clock_override: None,
+            profiling: false,
+            opcode_profile: HashMap::new(),
+            trace_hook: None,
+            breakpoints: HashSet::new(),
+            hit_breakpoint: None,
+            watchpoints: HashSet::new(),
+            hit_watchpoint: None,
+            out: Box::new(io::stdout()),
+            inp: Box::new(io::BufReader::new(io::stdin())),
         }
     }
 
@@ -63,37 +249,273 @@ impl VM {
         Self::new(16 * 1024 * 1024) // 16MB
     }
 
+    /// Create a default VM that prints to `out` and reads from `inp` instead of stdio
+    pub fn with_io(out: Box<dyn Write>, inp: Box<dyn BufRead>) -> Self {
+        let mut vm = Self::new_default();
+        vm.out = out;
+        vm.inp = inp;
+        vm
+    }
+
     /// Load a program (bytecode) into memory
     pub fn load_program(&mut self, bytecode: &[u32]) -> VMResult<()> {
         self.memory.load_program(bytecode)?;
+        self.code_len = (bytecode.len() * 4) as u32;
+        self.flags = Flags::default();
         self.pc = 0;
+        self.call_stack.clear();
         self.running = false;
+        self.halted = false;
         self.instruction_count = 0;
+        self.hit_breakpoint = None;
         Ok(())
     }
 
-    /// Run the program until halt or error
-    pub fn run(&mut self) -> VMResult<()> {
+    /// Mirror the stack pointer into SP (R29)
+    fn sync_stack_pointer(&mut self) -> VMResult<()> {
+        self.registers.write_u32(STACK_POINTER_REGISTER, self.memory.get_stack_pointer())
+    }
+
+    /// Load a program from raw little-endian bytes
+    pub fn load_program_bytes(&mut self, bytes: &[u8]) -> VMResult<()> {
+        if !bytes.len().is_multiple_of(4) {
+            return Err(VMError::MalformedProgram(format!(
+                "length {} is not a multiple of 4",
+                bytes.len()
+            )));
+        }
+
+        let bytecode: Vec<u32> = bytes
+            .chunks_exact(4)
+            .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect();
+
+        self.load_program(&bytecode)
+    }
+
+    /// Statically check bytecode before loading it
+    ///
+    /// Every word must decode, and every `JMP`, `CALL` and branch target must be
+    /// word-aligned and inside the program. Register-indirect jumps can't be
+    /// checked ahead of time. All problems are reported, in address order.
+    pub fn validate_program(bytecode: &[u32]) -> Result<(), Vec<ValidationError>> {
+        let code_len = bytecode.len() as i64 * 4;
+        let mut errors = Vec::new();
+
+        for (index, &word) in bytecode.iter().enumerate() {
+            let address = index as u32 * 4;
+            let target = match decode(word) {
+                Err(_) => {
+                    errors.push(ValidationError::InvalidInstruction { address, word });
+                    continue;
+                }
+                Ok(InstructionType::JType { opcode: JTypeOp::JMP | JTypeOp::CALL, addr }) => addr as i64,
+                Ok(InstructionType::BType { offset, .. }) => address as i64 + 4 + offset as i16 as i64,
+                Ok(_) => continue,
+            };
+
+            if !(0..code_len).contains(&target) {
+                errors.push(ValidationError::TargetOutOfRange { address, target });
+            } else if target % 4 != 0 {
+                errors.push(ValidationError::MisalignedTarget { address, target });
+            }
+        }
+
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
+    /// Run the program until halt, error, or a breakpoint
+    ///
+    /// When a breakpoint is hit, execution pauses before the instruction at that
+    /// address and the VM stays running; calling `run` again resumes past it.
+    pub fn run(&mut self) -> RuntimeResult<()> {
+        self.run_until(None, None).map(|_| ())
+    }
+
+    /// Run the program until halt, error, breakpoint, or `max_instructions` have been executed
+    pub fn run_with_limit(&mut self, max_instructions: u64) -> RuntimeResult<()> {
+        self.run_until(None, Some(max_instructions)).map(|_| ())
+    }
+
+    /// Execute at most `n` instructions, then yield back to the caller
+    ///
+    /// Like `run`, this starts the VM and pauses on breakpoints and watchpoints;
+    /// a watchpoint hit ends the batch early with `Ran`. Once the program has
+    /// halted, further calls return `Halted` without executing anything until a
+    /// program is loaded, the VM is reset or the PC is moved with `set_pc`.
+    pub fn run_steps(&mut self, n: u64) -> RuntimeResult<StepOutcome> {
+        if self.halted {
+            return Ok(StepOutcome::Halted);
+        }
+        self.run_until(Some(n), None)
+    }
+
+    fn run_until(&mut self, max_steps: Option<u64>, max_instructions: Option<u64>) -> RuntimeResult<StepOutcome> {
         self.running = true;
-        
+
+        // Resuming from a breakpoint executes the instruction it paused on
+        let mut resuming = self.hit_breakpoint.take() == Some(self.pc);
+        self.hit_watchpoint = None;
+
+        let mut steps = 0;
         while self.running {
+            if max_steps.is_some_and(|max| steps >= max) {
+                return Ok(StepOutcome::Ran(steps));
+            }
+
+            if !resuming && self.breakpoints.contains(&self.pc) {
+                self.hit_breakpoint = Some(self.pc);
+                return Ok(StepOutcome::Breakpoint(self.pc));
+            }
+            resuming = false;
+
+            if let Some(limit) = max_instructions
+                && self.instruction_count >= limit
+            {
+                return Err(self.runtime_error(self.pc, VMError::InstructionLimitExceeded(limit)));
+            }
             self.step()?;
+            steps += 1;
+
+            // Watchpoints pause after the write has happened
+            if self.hit_watchpoint.is_some() {
+                return Ok(StepOutcome::Ran(steps));
+            }
         }
-        
-        Ok(())
+
+        Ok(StepOutcome::Halted)
+    }
+
+    /// Capture the full execution state so it can be restored later
+    pub fn snapshot(&self) -> VMSnapshot {
+        VMSnapshot {
+            registers: self.registers.clone(),
+            flags: self.flags,
+            memory: self.memory.clone(),
+            gc: self.gc.clone(),
+            pc: self.pc,
+            running: self.running,
+            halted: self.halted,
+            instruction_count: self.instruction_count,
+            code_len: self.code_len,
+            allocations_since_gc: self.allocations_since_gc,
+            call_stack: self.call_stack.clone(),
+            rng: self.rng,
+        }
+    }
+
+    /// Return to a previously captured state
+    ///
+    /// Installed hooks, observers, breakpoints and I/O streams are kept.
+    pub fn restore(&mut self, snap: &VMSnapshot) {
+        self.registers.copy_from(&snap.registers);
+        self.flags = snap.flags;
+        self.memory = snap.memory.clone();
+        self.gc.copy_from(&snap.gc);
+        self.pc = snap.pc;
+        self.running = snap.running;
+        self.halted = snap.halted;
+        self.instruction_count = snap.instruction_count;
+        self.code_len = snap.code_len;
+        self.allocations_since_gc = snap.allocations_since_gc;
+        self.call_stack.clone_from(&snap.call_stack);
+        self.rng = snap.rng;
+        self.hit_breakpoint = None;
+        self.hit_watchpoint = None;
+    }
+
+    /// Pause `run` whenever the PC reaches `addr`
+    pub fn add_breakpoint(&mut self, addr: u32) {
+        self.breakpoints.insert(addr);
+    }
+
+    /// Remove a breakpoint, returning whether it was set
+    pub fn remove_breakpoint(&mut self, addr: u32) -> bool {
+        self.breakpoints.remove(&addr)
+    }
+
+    /// Address of the breakpoint that paused the last run, if any
+    pub fn hit_breakpoint(&self) -> Option<u32> {
+        self.hit_breakpoint
+    }
+
+    /// Pause `run` right after any store that writes the byte at `addr`
+    pub fn add_watchpoint(&mut self, addr: u32) {
+        self.watchpoints.insert(addr);
+    }
+
+    /// Remove a watchpoint, returning whether it was set
+    pub fn remove_watchpoint(&mut self, addr: u32) -> bool {
+        self.watchpoints.remove(&addr)
+    }
+
+    /// Watched address written by the store that paused the last run, if any
+    pub fn hit_watchpoint(&self) -> Option<u32> {
+        self.hit_watchpoint
+    }
+
+    /// Report the word containing a byte or halfword store to the GC write barrier
+    fn record_partial_store(&mut self, address: u32) {
+        let word = address & !3;
+        if let Ok(value) = self.memory.read_word(word) {
+            self.gc.record_store(word, value);
+        }
+    }
+
+    /// Run the write barrier over every word a `len`-byte bulk store at `address` touched
+    fn record_bulk_store(&mut self, address: u32, len: u32) {
+        let end = address.saturating_add(len);
+        for word in ((address & !3)..end).step_by(4) {
+            self.record_partial_store(word);
+        }
+    }
+
+    /// Record a hit if a `len`-byte store at `address` touches a watched byte
+    fn check_watchpoints(&mut self, address: u32, len: u32) {
+        if self.watchpoints.is_empty() {
+            return;
+        }
+        if let Some(offset) = (0..len).find(|&i| self.watchpoints.contains(&address.wrapping_add(i))) {
+            self.hit_watchpoint = Some(address.wrapping_add(offset));
+        }
+    }
+
+    /// Install a callback that observes every instruction as it executes
+    pub fn set_trace_hook(&mut self, hook: TraceHook) {
+        self.trace_hook = Some(hook);
+    }
+
+    /// Remove the trace callback, if any
+    pub fn clear_trace_hook(&mut self) {
+        self.trace_hook = None;
     }
 
     /// Execute a single instruction
-    pub fn step(&mut self) -> VMResult<()> {
+    ///
+    /// Errors carry the address of the instruction that was being executed.
+    pub fn step(&mut self) -> RuntimeResult<()> {
+        let pc = self.pc;
+        self.execute_next().map_err(|kind| self.runtime_error(pc, kind))
+    }
+
+    fn runtime_error(&self, pc: u32, kind: VMError) -> RuntimeError {
+        RuntimeError { pc, instruction_count: self.instruction_count, kind }
+    }
+
+    /// Fetch, decode, and execute the instruction at the PC
+    fn execute_next(&mut self) -> VMResult<()> {
         if !self.running {
             return Err(VMError::ProgramHalted);
         }
 
-        // Check if we should trigger garbage collection
-        if self.auto_gc && self.gc.should_collect(&self.memory) {
-            self.gc.collect(&mut self.memory, &self.registers)?;
+        // Running off the end means the program is missing a HALT
+        if self.pc >= self.code_len {
+            return Err(VMError::ExecutedPastProgram(self.pc));
         }
 
+        // Check if we should trigger garbage collection
+        self.maybe_collect()?;
+
         // Fetch instruction
         let instruction_bits = self.memory.read_word(self.pc)?;
         let current_pc = self.pc; // Save current PC for branch calculations
@@ -101,6 +523,13 @@ impl VM {
         // Decode instruction
         let instruction = decode(instruction_bits)
             .map_err(|_| VMError::InvalidInstruction(instruction_bits))?;
+
+        if let Some(hook) = self.trace_hook.as_mut() {
+            hook(current_pc, &instruction);
+        }
+        if self.profiling {
+            *self.opcode_profile.entry(instruction.mnemonic()).or_insert(0) += 1;
+        }
         
         // Increment PC (most instructions advance by 4 bytes)
         self.pc += 4;
@@ -128,7 +557,7 @@ impl VM {
                 self.execute_jtype(opcode, addr)
             }
             InstructionType::MType { opcode, rd, rs, rt } => {
-                self.execute_mtype(opcode, rd, rs, rt)
+                self.execute_mtype(opcode, rd, rs, rt, current_pc)
             }
             InstructionType::SType { opcode, rd, rs } => {
                 self.execute_stype(opcode, rd, rs)
@@ -154,17 +583,89 @@ impl VM {
                 }
                 rs_val / rt_val
             }
+            RTypeOp::DIVMOD => {
+                if rt_val == 0 {
+                    return Err(VMError::DivisionByZero);
+                }
+                // The remainder goes to the register after rd, so rd must not be R31.
+                // It is written first so a bad rd leaves both registers untouched.
+                self.registers.write(rd.get_value() + 1, rs_val.wrapping_rem(rt_val))?;
+                rs_val.wrapping_div(rt_val)
+            }
+            RTypeOp::FADD | RTypeOp::FSUB | RTypeOp::FMUL | RTypeOp::FDIV | RTypeOp::ITOF | RTypeOp::FTOI => {
+                return self.execute_float(opcode, rd, rs, rt);
+            }
             RTypeOp::MOV => rs_val,
+            RTypeOp::NEG => rs_val.wrapping_neg(),
+            // |i32::MIN| does not fit, so it wraps back to i32::MIN
+            RTypeOp::ABS => rs_val.wrapping_abs(),
+            RTypeOp::MULH => ((rs_val as i64 * rt_val as i64) >> 32) as i32,
+            RTypeOp::MULHU => ((rs_val as u32 as u64 * rt_val as u32 as u64) >> 32) as i32,
             RTypeOp::AND => rs_val & rt_val,
             RTypeOp::OR => rs_val | rt_val,
             RTypeOp::XOR => rs_val ^ rt_val,
             RTypeOp::NOT => !rs_val,
+            // Shift amount comes from the low 5 bits of rt; right shifts are logical
+            RTypeOp::SLL => rs_val.wrapping_shl(rt_val as u32 & 0x1F),
+            RTypeOp::SRL => (rs_val as u32).wrapping_shr(rt_val as u32 & 0x1F) as i32,
+            RTypeOp::CMP => {
+                // CMP only updates the flags; rd is left untouched
+                self.flags = Flags::from_compare(rs_val, rt_val);
+                return Ok(());
+            }
+            RTypeOp::SLT => (rs_val < rt_val) as i32,
+            RTypeOp::SLTU => ((rs_val as u32) < (rt_val as u32)) as i32,
+            RTypeOp::MIN => rs_val.min(rt_val),
+            RTypeOp::MAX => rs_val.max(rt_val),
+            RTypeOp::MINU => (rs_val as u32).min(rt_val as u32) as i32,
+            RTypeOp::MAXU => (rs_val as u32).max(rt_val as u32) as i32,
+            // Rotates take their count from the low 5 bits of rt, like the shifts
+            RTypeOp::ROL => (rs_val as u32).rotate_left(rt_val as u32 & 0x1F) as i32,
+            RTypeOp::ROR => (rs_val as u32).rotate_right(rt_val as u32 & 0x1F) as i32,
+            RTypeOp::CLZ => (rs_val as u32).leading_zeros() as i32,
+            RTypeOp::POPCNT => (rs_val as u32).count_ones() as i32,
+            RTypeOp::SEB => rs_val as i8 as i32,
+            RTypeOp::SEH => rs_val as i16 as i32,
+            RTypeOp::ZEB => rs_val as u8 as i32,
+            RTypeOp::ZEH => rs_val as u16 as i32,
         };
         
         self.registers.write(rd.get_value(), result)?;
         Ok(())
     }
 
+    /// Execute the floating-point subset of the R-Type instructions
+    ///
+    /// Arithmetic follows IEEE 754, so dividing by zero yields an infinity or NaN
+    /// rather than an error. `FTOI` truncates, saturating out-of-range values and
+    /// mapping NaN to 0.
+    fn execute_float(&mut self, opcode: RTypeOp, rd: Register, rs: Register, rt: Register) -> VMResult<()> {
+        let (rd, rs, rt) = (rd.get_value(), rs.get_value(), rt.get_value());
+
+        match opcode {
+            RTypeOp::ITOF => {
+                let value = self.registers.read(rs)?;
+                self.registers.write_float(rd, value as f32)
+            }
+            RTypeOp::FTOI => {
+                let value = self.registers.read_float(rs)?;
+                self.registers.write(rd, value as i32)
+            }
+            _ => {
+                let lhs = self.registers.read_float(rs)?;
+                let rhs = self.registers.read_float(rt)?;
+                let result = match opcode {
+                    RTypeOp::FADD => lhs + rhs,
+                    RTypeOp::FSUB => lhs - rhs,
+                    RTypeOp::FMUL => lhs * rhs,
+                    RTypeOp::FDIV => lhs / rhs,
+                    _ => unreachable!("not a float arithmetic opcode: {}", opcode),
+                };
+                self.registers.write_float(rd, result)
+            }
+        }
+    }
+
     /// Execute I-Type instructions
     fn execute_itype(&mut self, opcode: ITypeOp, rd: Register, rs: Register, imm: u16) -> VMResult<()> {
         match opcode {
@@ -181,18 +682,62 @@ impl VM {
                 self.registers.write(rd.get_value(), result)?;
             }
             ITypeOp::LOAD => {
-                // Load: rd = memory[rs + offset]
-                let rs_val = self.registers.read(rs.get_value())?;
-                let address = (rs_val as u32).wrapping_add(imm as u32);
+                // Load: rd = memory[rs + offset] (offset sign-extended)
+                let address = self.registers.read_u32(rs.get_value())?.wrapping_add(imm as i16 as u32);
+                self.memory.check_access(address, 4)?;
                 let value = self.memory.read_word(address)?;
                 self.registers.write(rd.get_value(), value as i32)?;
             }
             ITypeOp::STORE => {
-                // Store: memory[rs + offset] = rd
-                let rs_val = self.registers.read(rs.get_value())?;
+                // Store: memory[rs + offset] = rd (offset sign-extended)
+                let address = self.registers.read_u32(rs.get_value())?.wrapping_add(imm as i16 as u32);
+                self.memory.check_access(address, 4)?;
                 let rd_val = self.registers.read(rd.get_value())?;
-                let address = (rs_val as u32).wrapping_add(imm as u32);
                 self.memory.write_word(address, rd_val as u32)?;
+                self.gc.record_store(address, rd_val as u32);
+                self.check_watchpoints(address, 4);
+            }
+            ITypeOp::LB => {
+                // Load byte: rd = sign_extend(memory[rs + offset])
+                let address = self.registers.read_u32(rs.get_value())?.wrapping_add(imm as i16 as u32);
+                self.memory.check_access(address, 1)?;
+                let value = self.memory.read_byte(address)?;
+                self.registers.write(rd.get_value(), value as i8 as i32)?;
+            }
+            ITypeOp::SB => {
+                // Store byte: memory[rs + offset] = low byte of rd
+                let address = self.registers.read_u32(rs.get_value())?.wrapping_add(imm as i16 as u32);
+                self.memory.check_access(address, 1)?;
+                let rd_val = self.registers.read(rd.get_value())?;
+                self.memory.write_byte(address, rd_val as u8)?;
+                self.record_partial_store(address);
+                self.check_watchpoints(address, 1);
+            }
+            ITypeOp::LH => {
+                // Load halfword: rd = sign_extend(memory[rs + offset])
+                let address = self.registers.read_u32(rs.get_value())?.wrapping_add(imm as i16 as u32);
+                self.memory.check_access(address, 2)?;
+                let value = self.memory.read_halfword(address)?;
+                self.registers.write(rd.get_value(), value as i16 as i32)?;
+            }
+            ITypeOp::SH => {
+                // Store halfword: memory[rs + offset] = low halfword of rd
+                let address = self.registers.read_u32(rs.get_value())?.wrapping_add(imm as i16 as u32);
+                self.memory.check_access(address, 2)?;
+                let rd_val = self.registers.read(rd.get_value())?;
+                self.memory.write_halfword(address, rd_val as u16)?;
+                self.record_partial_store(address);
+                self.check_watchpoints(address, 2);
+            }
+            ITypeOp::ENTER => {
+                // Push fp, point fp at the saved value, then reserve imm bytes of locals
+                let fp = self.registers.read_u32(FRAME_POINTER_REGISTER)?;
+                self.memory.stack_push(fp)?;
+                let frame = self.memory.get_stack_pointer();
+                let sp = frame.checked_sub(imm as u32).ok_or(VMError::StackOverflow)?;
+                self.memory.set_stack_pointer(sp).map_err(|_| VMError::StackOverflow)?;
+                self.registers.write_u32(FRAME_POINTER_REGISTER, frame)?;
+                self.sync_stack_pointer()?;
             }
         }
         Ok(())
@@ -210,6 +755,12 @@ impl VM {
             BTypeOp::BGE => rs_val >= rt_val,
             BTypeOp::BZ => rs_val == 0,
             BTypeOp::BNZ => rs_val != 0,
+            BTypeOp::BZF => self.flags.zero,
+            BTypeOp::BNZF => !self.flags.zero,
+            BTypeOp::BLTF => self.flags.negative != self.flags.overflow,
+            BTypeOp::BGEF => self.flags.negative == self.flags.overflow,
+            BTypeOp::BLTU => (rs_val as u32) < (rt_val as u32),
+            BTypeOp::BGEU => (rs_val as u32) >= (rt_val as u32),
         };
         
         if should_branch {
@@ -236,11 +787,11 @@ impl VM {
     }
 
     /// Execute J-Type instructions
-    fn execute_jtype(&mut self, opcode: JTypeOp, addr: u16) -> VMResult<()> {
+    fn execute_jtype(&mut self, opcode: JTypeOp, addr: u32) -> VMResult<()> {
         match opcode {
             JTypeOp::JMP => {
                 // Jump to address
-                let target = addr as u32;
+                let target = addr;
                 if target >= self.memory.get_stats().total_memory {
                     return Err(VMError::InvalidJumpAddress(target));
                 }
@@ -248,66 +799,154 @@ impl VM {
             }
             JTypeOp::CALL => {
                 // Call function: push return address and jump
-                let return_addr = self.pc;
-                self.memory.stack_push(return_addr)?;
-                
-                let target = addr as u32;
+                let target = addr;
                 if target >= self.memory.get_stats().total_memory {
                     return Err(VMError::InvalidJumpAddress(target));
                 }
-                self.pc = target;
+                self.enter_call(target)?;
             }
             JTypeOp::RET => {
                 // Return from function: pop return address
                 let return_addr = self.memory.stack_pop()?;
+                self.sync_stack_pointer()?;
                 self.pc = return_addr;
+                self.call_stack.pop();
             }
         }
         Ok(())
     }
 
+    /// Push the return address and transfer control to an already validated `target`
+    fn enter_call(&mut self, target: u32) -> VMResult<()> {
+        if self.call_depth() >= self.max_call_depth {
+            return Err(VMError::CallStackOverflow(self.max_call_depth));
+        }
+        let return_addr = self.pc;
+        self.memory.stack_push(return_addr)?;
+        self.sync_stack_pointer()?;
+        self.pc = target;
+        self.call_stack.push(return_addr);
+        Ok(())
+    }
+
+    /// Read an indirect jump target from a register, requiring it to lie in the code segment
+    fn indirect_target(&self, reg: Register) -> VMResult<u32> {
+        let target = self.registers.read_u32(reg.get_value())?;
+        if target >= self.memory.layout().code_size {
+            return Err(VMError::InvalidJumpAddress(target));
+        }
+        Ok(target)
+    }
+
     /// Execute M-Type instructions
-    fn execute_mtype(&mut self, opcode: MTypeOp, rd: Register, rs: Register, rt: Register) -> VMResult<()> {
+    fn execute_mtype(&mut self, opcode: MTypeOp, rd: Register, rs: Register, rt: Register, current_pc: u32) -> VMResult<()> {
         match opcode {
             MTypeOp::ALLOC => {
                 // Allocate memory: rd = allocate(rs bytes)
-                let size = self.registers.read(rs.get_value())? as u32;
+                let size = self.registers.read_u32(rs.get_value())?;
                 let address = self.memory.allocate(size)?;
+                self.memory.record_alloc_site(address, current_pc)?;
                 
                 // Register object with garbage collector
                 self.gc.register_object(address, size);
                 
-                self.registers.write(rd.get_value(), address as i32)?;
+                self.registers.write_u32(rd.get_value(), address)?;
 
                 // Check if automatic GC should run
-                if self.auto_gc && self.gc.should_collect(&self.memory) {
-                    self.gc.collect(&mut self.memory, &self.registers)?;
+                self.allocations_since_gc += 1;
+                let allocation_limit_reached = self
+                    .gc
+                    .get_config()
+                    .collect_every_n_allocations
+                    .is_some_and(|n| self.allocations_since_gc >= n);
+                if self.auto_gc && allocation_limit_reached {
+                    self.collect_garbage(false)?;
+                } else {
+                    self.maybe_collect()?;
                 }
             }
             MTypeOp::FREE => {
                 // Free memory: free(rs)
-                let address = self.registers.read(rs.get_value())? as u32;
-                
-                // Unregister from garbage collector
-                self.gc.unregister_object(address);
-                
+                let address = self.registers.read_u32(rs.get_value())?;
                 self.memory.free(address)?;
+
+                // Only stop tracking blocks that were actually released
+                self.gc.unregister_object(address);
             }
             MTypeOp::ALOAD => {
                 // Array load: rd = array[rs + rt]
-                let base = self.registers.read(rs.get_value())? as u32;
-                let index = self.registers.read(rt.get_value())? as u32;
+                let base = self.registers.read_u32(rs.get_value())?;
+                let index = self.registers.read_u32(rt.get_value())?;
                 let address = base.wrapping_add(index * 4); // Assuming 4-byte elements
+                self.memory.check_access(address, 4)?;
                 let value = self.memory.read_word(address)?;
                 self.registers.write(rd.get_value(), value as i32)?;
             }
             MTypeOp::ASTORE => {
                 // Array store: array[rs + rt] = rd
-                let base = self.registers.read(rs.get_value())? as u32;
-                let index = self.registers.read(rt.get_value())? as u32;
+                let base = self.registers.read_u32(rs.get_value())?;
+                let index = self.registers.read_u32(rt.get_value())?;
                 let value = self.registers.read(rd.get_value())?;
                 let address = base.wrapping_add(index * 4); // Assuming 4-byte elements
+                self.memory.check_access(address, 4)?;
                 self.memory.write_word(address, value as u32)?;
+                self.gc.record_store(address, value as u32);
+                self.check_watchpoints(address, 4);
+            }
+            MTypeOp::PUSH => {
+                // Push register onto the stack: push(rs)
+                let value = self.registers.read(rs.get_value())?;
+                self.memory.stack_push(value as u32)?;
+                self.sync_stack_pointer()?;
+            }
+            MTypeOp::POP => {
+                // Pop the top of the stack into a register: rd = pop()
+                let value = self.memory.stack_pop()?;
+                self.sync_stack_pointer()?;
+                self.registers.write(rd.get_value(), value as i32)?;
+            }
+            MTypeOp::MEMCPY => {
+                // Copy rt bytes from rs to rd (overlap-safe)
+                let dst = self.registers.read_u32(rd.get_value())?;
+                let src = self.registers.read_u32(rs.get_value())?;
+                let len = self.registers.read_u32(rt.get_value())?;
+                self.memory.check_access(src, len)?;
+                self.memory.check_access(dst, len)?;
+                self.memory.mem_copy(dst, src, len)?;
+                self.record_bulk_store(dst, len);
+            }
+            MTypeOp::MEMSET => {
+                // Fill rt bytes at rd with the low byte of rs
+                let dst = self.registers.read_u32(rd.get_value())?;
+                let byte = self.registers.read(rs.get_value())? as u8;
+                let len = self.registers.read_u32(rt.get_value())?;
+                self.memory.check_access(dst, len)?;
+                self.memory.mem_set(dst, byte, len)?;
+                self.record_bulk_store(dst, len);
+            }
+            MTypeOp::CAS => {
+                // Compare-and-swap the word at rs: store rd if it equals rt, then rd = old value.
+                // The VM is single-threaded, so read-compare-write is already atomic.
+                let address = self.registers.read_u32(rs.get_value())?;
+                let expected = self.registers.read(rt.get_value())?;
+                let new_value = self.registers.read(rd.get_value())?;
+                self.memory.check_access(address, 4)?;
+                let old = self.memory.read_word(address)? as i32;
+                if old == expected {
+                    self.memory.write_word(address, new_value as u32)?;
+                    self.gc.record_store(address, new_value as u32);
+                    self.check_watchpoints(address, 4);
+                }
+                self.registers.write(rd.get_value(), old)?;
+            }
+            MTypeOp::JR => {
+                // Jump to the address held in rs
+                self.pc = self.indirect_target(rs)?;
+            }
+            MTypeOp::CALLR => {
+                // Call the function whose address is held in rs
+                let target = self.indirect_target(rs)?;
+                self.enter_call(target)?;
             }
         }
         Ok(())
@@ -316,30 +955,41 @@ impl VM {
     /// Execute S-Type instructions
     fn execute_stype(&mut self, opcode: STypeOp, rd: Option<Register>, rs: Option<Register>) -> VMResult<()> {
         match opcode {
-            STypeOp::PRINT => {
-                // Print value from register
+            STypeOp::PRINT | STypeOp::PRINTX => {
+                // Print value from register, in decimal or as zero-padded hex
                 if let Some(reg) = rs {
                     let value = self.registers.read(reg.get_value())?;
-                    println!("{}", value);
-                    io::stdout().flush().map_err(|e| VMError::IOError(e.to_string()))?;
+                    if opcode == STypeOp::PRINTX {
+                        writeln!(self.out, "0x{:08X}", value as u32)?;
+                    } else {
+                        writeln!(self.out, "{}", value)?;
+                    }
+                    self.out.flush()?;
                 }
             }
             STypeOp::READ => {
-                // Read integer from stdin
+                // Read integer from the input source
                 if let Some(reg) = rd {
-                    print!("Enter number: ");
-                    io::stdout().flush().map_err(|e| VMError::IOError(e.to_string()))?;
+                    write!(self.out, "Enter number: ")?;
+                    self.out.flush()?;
                     
                     let mut input = String::new();
-                    io::stdin().read_line(&mut input)
-                        .map_err(|e| VMError::IOError(e.to_string()))?;
+                    self.inp.read_line(&mut input)?;
                     
                     let value: i32 = input.trim().parse()
-                        .map_err(|e| VMError::IOError(format!("Invalid input: {}", e)))?;
+                        .map_err(|e| VMError::InvalidInput(format!("{:?}: {}", input.trim(), e)))?;
                     
                     self.registers.write(reg.get_value(), value)?;
                 }
             }
+            STypeOp::PUTC => {
+                // Write the low byte of the register as a single character
+                if let Some(reg) = rs {
+                    let value = self.registers.read(reg.get_value())?;
+                    self.out.write_all(&[value as u8])?;
+                    self.out.flush()?;
+                }
+            }
             STypeOp::SYSCALL => {
                 // System call - simplified implementation
                 let syscall_num = if let Some(reg) = rs {
@@ -349,9 +999,31 @@ impl VM {
                 };
                 
                 match syscall_num {
-                    1 => {
-                        // Exit syscall
+                    SYSCALL_EXIT => {
                         self.running = false;
+                        self.halted = true;
+                    }
+                    SYSCALL_RANDOM => {
+                        let reg = rd.ok_or_else(|| {
+                            VMError::SystemCallError("random needs a destination register".to_string())
+                        })?;
+                        let value = (self.rng.next_u64() >> 32) as i32;
+                        self.registers.write(reg.get_value(), value)?;
+                    }
+                    SYSCALL_WRITE_STRING => {
+                        let reg = rd.ok_or_else(|| {
+                            VMError::SystemCallError("write string needs an address register".to_string())
+                        })?;
+                        let start = self.registers.read_u32(reg.get_value())?;
+                        let bytes = self.read_c_string(start)?;
+                        self.out.write_all(&bytes)?;
+                        self.out.flush()?;
+                    }
+                    SYSCALL_TIME => {
+                        let reg = rd.ok_or_else(|| {
+                            VMError::SystemCallError("time needs a destination register".to_string())
+                        })?;
+                        self.registers.write(reg.get_value(), self.elapsed_millis() as i32)?;
                     }
                     _ => {
                         return Err(VMError::SystemCallError(format!("Unknown syscall: {}", syscall_num)));
@@ -362,6 +1034,27 @@ impl VM {
         Ok(())
     }
 
+    /// Milliseconds since the VM was created, or the override if one is set
+    fn elapsed_millis(&self) -> u64 {
+        self.clock_override
+            .unwrap_or_else(|| self.started_at.elapsed().as_millis() as u64)
+    }
+
+    /// Read bytes from `start` up to (not including) a NUL, bounded by `MAX_STRING_LENGTH`
+    fn read_c_string(&self, start: u32) -> VMResult<Vec<u8>> {
+        let mut bytes = Vec::new();
+        for offset in 0..MAX_STRING_LENGTH {
+            match self.memory.read_byte(start.wrapping_add(offset))? {
+                0 => return Ok(bytes),
+                byte => bytes.push(byte),
+            }
+        }
+        Err(VMError::SystemCallError(format!(
+            "string at 0x{:08X} has no terminator within {} bytes",
+            start, MAX_STRING_LENGTH
+        )))
+    }
+
     /// Execute N-Type instructions
     fn execute_ntype(&mut self, opcode: NTypeOp) -> VMResult<()> {
         match opcode {
@@ -371,19 +1064,72 @@ impl VM {
             NTypeOp::HALT => {
                 // Halt execution
                 self.running = false;
+                self.halted = true;
+            }
+            NTypeOp::LEAVE => {
+                // Discard the frame's locals and restore the caller's fp
+                let frame = self.registers.read_u32(FRAME_POINTER_REGISTER)?;
+                self.memory.set_stack_pointer(frame)?;
+                let fp = self.memory.stack_pop()?;
+                self.registers.write_u32(FRAME_POINTER_REGISTER, fp)?;
+                self.sync_stack_pointer()?;
             }
         }
         Ok(())
     }
 
     /// Reset the VM to initial state
+    ///
+    /// The garbage collector's state is cleared, but its event listener stays installed.
     pub fn reset(&mut self) {
         self.registers.reset();
+        self.flags = Flags::default();
         self.memory.reset();
-        self.gc = GarbageCollector::new(self.gc.get_config().clone());
+        let fresh = GarbageCollector::new(self.gc.get_config().clone());
+        self.gc.copy_from(&fresh);
+        self.allocations_since_gc = 0;
         self.pc = 0;
         self.running = false;
+        self.halted = false;
         self.instruction_count = 0;
+        self.code_len = 0;
+        self.call_stack.clear();
+        self.hit_breakpoint = None;
+        self.hit_watchpoint = None;
+    }
+
+    /// Resize a heap block, keeping the garbage collector's object table in sync
+    pub fn reallocate(&mut self, address: u32, new_size: u32) -> VMResult<u32> {
+        let new_address = self.memory.reallocate(address, new_size)?;
+        self.gc.move_object(address, new_address, new_size);
+        Ok(new_address)
+    }
+
+    /// Grow memory by `additional` bytes, moving the stack up with it
+    ///
+    /// SP, FP and the frame pointers that `ENTER` saved on the stack are rebased
+    /// onto the moved stack. Other stack addresses the program holds are left as is.
+    pub fn grow_memory(&mut self, additional: u32) -> VMResult<()> {
+        let (old_base, old_sp) = self.memory.stack_region();
+        self.memory.grow(additional)?;
+
+        // Follow the chain of saved frame pointers, each one higher up the old stack
+        let fp = self.registers.read_u32(FRAME_POINTER_REGISTER)?;
+        let mut frame = fp;
+        while (old_sp..old_base).contains(&frame) {
+            let slot = frame + additional;
+            let saved = self.memory.read_word(slot)?;
+            if saved <= frame || saved > old_base {
+                break;
+            }
+            self.memory.write_word(slot, saved + additional)?;
+            frame = saved;
+        }
+
+        if (old_sp..=old_base).contains(&fp) {
+            self.registers.write_u32(FRAME_POINTER_REGISTER, fp + additional)?;
+        }
+        self.sync_stack_pointer()
     }
 
     /// Get current program counter
@@ -397,6 +1143,7 @@ impl VM {
             return Err(VMError::InvalidJumpAddress(pc));
         }
         self.pc = pc;
+        self.halted = false;
         Ok(())
     }
 
@@ -405,16 +1152,86 @@ impl VM {
         self.running
     }
 
+    /// Limit how deeply CALLs may nest (defaults to `DEFAULT_MAX_CALL_DEPTH`)
+    pub fn set_max_call_depth(&mut self, depth: u32) {
+        self.max_call_depth = depth;
+    }
+
+    /// Number of CALLs currently awaiting a RET
+    pub fn call_depth(&self) -> u32 {
+        self.call_stack.len() as u32
+    }
+
+    /// Return addresses of the active calls, innermost first
+    ///
+    /// Read from a shadow stack kept by CALL and RET, so values a program
+    /// pushes onto the data stack are never mistaken for return addresses.
+    pub fn call_stack(&self) -> Vec<u32> {
+        self.call_stack.iter().rev().copied().collect()
+    }
+
+    /// Reseed the generator behind the random syscall, making its sequence reproducible
+    pub fn seed_rng(&mut self, seed: u64) {
+        self.rng = XorShift64::new(seed);
+    }
+
+    /// Make the time syscall report a fixed number of milliseconds, or the real clock with `None`
+    pub fn set_clock_override(&mut self, millis: Option<u64>) {
+        self.clock_override = millis;
+    }
+
+    /// Turn per-opcode execution counting on or off (off by default)
+    ///
+    /// Counts gathered so far are kept when profiling is switched off.
+    pub fn set_profiling(&mut self, enabled: bool) {
+        self.profiling = enabled;
+    }
+
+    /// Execution counts per opcode mnemonic, e.g. `profile["ADD"]`
+    pub fn opcode_profile(&self) -> &HashMap<&'static str, u64> {
+        &self.opcode_profile
+    }
+
+    /// Discard the counts gathered so far
+    pub fn clear_opcode_profile(&mut self) {
+        self.opcode_profile.clear();
+    }
+
     /// Get instruction count
     pub fn get_instruction_count(&self) -> u64 {
         self.instruction_count
     }
 
+    /// Get the condition flags
+    pub fn get_flags(&self) -> Flags {
+        self.flags
+    }
+
     /// Get register file reference
     pub fn get_registers(&self) -> &RegisterFile {
         &self.registers
     }
 
+    /// Get mutable register file reference, e.g. to install a write observer
+    pub fn get_registers_mut(&mut self) -> &mut RegisterFile {
+        &mut self.registers
+    }
+
+    /// Hardwire R0 to zero (writes to it are ignored) or make it a normal register
+    pub fn set_zero_register(&mut self, enabled: bool) {
+        self.registers.set_zero_register(enabled);
+    }
+
+    /// Follow each new heap block with a guard word checked on free and during GC
+    pub fn set_heap_canaries(&mut self, enabled: bool) {
+        self.memory.set_heap_canaries(enabled);
+    }
+
+    /// Require program loads and stores in the heap to stay inside one allocated block
+    pub fn set_strict_bounds(&mut self, enabled: bool) {
+        self.memory.set_strict_bounds(enabled);
+    }
+
     /// Get memory reference
     pub fn get_memory(&self) -> &Memory {
         &self.memory
@@ -437,12 +1254,47 @@ impl VM {
 
     /// Force garbage collection
     pub fn force_gc(&mut self) -> VMResult<()> {
-        self.gc.force_collect(&mut self.memory, &self.registers)
+        self.collect_garbage(false)
     }
 
     /// Perform minor garbage collection (young generation only)
     pub fn minor_gc(&mut self) -> VMResult<()> {
-        self.gc.minor_collect(&mut self.memory, &self.registers)
+        self.collect_garbage(true)
+    }
+
+    /// Run automatic GC work if enabled and the heap is under pressure
+    ///
+    /// With an incremental budget configured, each call advances the current cycle
+    /// by at most that many objects instead of collecting everything at once.
+    fn maybe_collect(&mut self) -> VMResult<()> {
+        if !self.auto_gc {
+            return Ok(());
+        }
+
+        match self.gc.get_config().incremental_budget {
+            Some(budget) => {
+                if self.gc.is_collecting() || self.gc.should_collect(&self.memory) {
+                    self.gc.collect_incremental(&mut self.memory, &self.registers, budget)?;
+                }
+                Ok(())
+            }
+            None if self.gc.should_collect(&self.memory) => self.collect_garbage(false),
+            None => Ok(()),
+        }
+    }
+
+    /// Run a collection
+    ///
+    /// Compaction never moves an object whose address is in a register, so
+    /// registers need no fixing up afterwards.
+    fn collect_garbage(&mut self, minor: bool) -> VMResult<()> {
+        if minor {
+            self.gc.minor_collect(&mut self.memory, &self.registers)?;
+        } else {
+            self.gc.collect(&mut self.memory, &self.registers)?;
+        }
+        self.allocations_since_gc = 0;
+        Ok(())
     }
 
     /// Dump VM state for debugging
@@ -472,33 +1324,1362 @@ mod tests {
     use super::*;
     use crate::compiler::instruction_builder::{InstructionBuilder, registers::*};
     use crate::compiler::encode::encode;
+    use crate::vm::gc::GcStrategy;
 
-    #[test]
-    fn test_vm_creation() {
-        let vm = VM::new_default();
-        assert_eq!(vm.pc, 0);
-        assert!(!vm.running);
-        assert_eq!(vm.instruction_count, 0);
+    /// Writer that shares its buffer so tests can inspect VM output
+    #[derive(Clone, Default)]
+    struct SharedBuffer(std::rc::Rc<std::cell::RefCell<Vec<u8>>>);
+
+    impl SharedBuffer {
+        fn contents(&self) -> String {
+            String::from_utf8(self.0.borrow().clone()).unwrap()
+        }
     }
 
-    #[test]
-    fn test_program_loading() {
-        let mut vm = VM::new_default();
-        let program = vec![0x12345678, 0xABCDEF00];
-        
-        assert!(vm.load_program(&program).is_ok());
-        assert_eq!(vm.memory.read_word(0).unwrap(), 0x12345678);
-        assert_eq!(vm.memory.read_word(4).unwrap(), 0xABCDEF00);
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    struct FailingWriter;
+
+    impl Write for FailingWriter {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            Err(io::Error::new(io::ErrorKind::BrokenPipe, "output closed"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
     }
 
     #[test]
-    fn test_simple_execution() {
-        let mut vm = VM::new_default();
-        
-        // Create a simple program: LI R1, 42; HALT
-        let li_instr = InstructionBuilder::load_immediate(r1(), 42);
-        let halt_instr = InstructionBuilder::halt();
-        
+    fn test_io_failure_surfaces_as_vm_error() {
+        use std::error::Error;
+
+        let mut vm = VM::with_io(Box::new(FailingWriter), Box::new(io::empty()));
+        let program = vec![
+            encode(InstructionBuilder::load_immediate(r1(), 7)),
+            encode(InstructionBuilder::print(r1())),
+            encode(InstructionBuilder::halt()),
+        ];
+        vm.load_program(&program).unwrap();
+
+        let err = vm.run().unwrap_err().kind;
+        match &err {
+            VMError::Io(io_err) => assert_eq!(io_err.kind(), io::ErrorKind::BrokenPipe),
+            other => panic!("expected an I/O error, got {:?}", other),
+        }
+        assert_eq!(err.source().unwrap().to_string(), "output closed");
+    }
+
+    #[test]
+    fn test_zero_register_in_programs() {
+        let program = vec![
+            encode(InstructionBuilder::load_immediate(r0(), 99)),
+            encode(InstructionBuilder::load_immediate(r1(), 5)),
+            encode(InstructionBuilder::mov(r2(), r1())),
+            encode(InstructionBuilder::not(r3(), r1())),
+            encode(InstructionBuilder::sub(r4(), r0(), r1())),
+            encode(InstructionBuilder::halt()),
+        ];
+
+        let mut vm = VM::new_default();
+        vm.set_zero_register(true);
+        vm.load_program(&program).unwrap();
+        vm.run().unwrap();
+        assert_eq!(vm.registers.read(0).unwrap(), 0);
+        assert_eq!(vm.registers.read(2).unwrap(), 5);
+        assert_eq!(vm.registers.read(3).unwrap(), !5);
+        assert_eq!(vm.registers.read(4).unwrap(), -5);
+
+        let mut vm = VM::new_default();
+        vm.load_program(&program).unwrap();
+        vm.run().unwrap();
+        assert_eq!(vm.registers.read(0).unwrap(), 99);
+        assert_eq!(vm.registers.read(4).unwrap(), 94);
+    }
+
+    #[test]
+    fn test_neg_and_abs() {
+        let program = vec![
+            encode(InstructionBuilder::load_immediate(r1(), 5)),
+            encode(InstructionBuilder::neg(r2(), r1())),
+            encode(InstructionBuilder::neg(r3(), r2())),
+            encode(InstructionBuilder::abs(r4(), r2())),
+            encode(InstructionBuilder::halt()),
+        ];
+
+        let mut vm = VM::new_default();
+        vm.load_program(&program).unwrap();
+        vm.run().unwrap();
+        assert_eq!(vm.registers.read(2).unwrap(), -5);
+        assert_eq!(vm.registers.read(3).unwrap(), 5);
+        assert_eq!(vm.registers.read(4).unwrap(), 5);
+
+        // Both wrap rather than overflow on i32::MIN
+        let program = vec![
+            encode(InstructionBuilder::neg(r2(), r1())),
+            encode(InstructionBuilder::abs(r3(), r1())),
+            encode(InstructionBuilder::halt()),
+        ];
+        let mut vm = VM::new_default();
+        vm.get_registers_mut().write(1, i32::MIN).unwrap();
+        vm.load_program(&program).unwrap();
+        vm.run().unwrap();
+        assert_eq!(vm.registers.read(2).unwrap(), i32::MIN);
+        assert_eq!(vm.registers.read(3).unwrap(), i32::MIN);
+    }
+
+    #[test]
+    fn test_min_max_signedness() {
+        let program = vec![
+            encode(InstructionBuilder::load_immediate(r1(), 3)),
+            encode(InstructionBuilder::load_immediate(r2(), 4)),
+            encode(InstructionBuilder::neg(r2(), r2())),
+            encode(InstructionBuilder::min(r3(), r1(), r2())),
+            encode(InstructionBuilder::max(r4(), r1(), r2())),
+            encode(InstructionBuilder::min_unsigned(r5(), r1(), r2())),
+            encode(InstructionBuilder::max_unsigned(r6(), r1(), r2())),
+            encode(InstructionBuilder::halt()),
+        ];
+
+        let mut vm = VM::new_default();
+        vm.load_program(&program).unwrap();
+        vm.run().unwrap();
+        assert_eq!(vm.registers.read(3).unwrap(), -4);
+        assert_eq!(vm.registers.read(4).unwrap(), 3);
+        // As unsigned, -4 is 0xFFFFFFFC and so the larger of the pair
+        assert_eq!(vm.registers.read(5).unwrap(), 3);
+        assert_eq!(vm.registers.read(6).unwrap(), -4);
+    }
+
+    #[test]
+    fn test_register_write_observer_sees_program_writes() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        let writes = Rc::new(RefCell::new(Vec::new()));
+        let sink = Rc::clone(&writes);
+
+        let mut vm = VM::new_default();
+        vm.get_registers_mut()
+            .set_on_write(Box::new(move |reg, _old, new| sink.borrow_mut().push((reg, new))));
+
+        let program = vec![
+            encode(InstructionBuilder::load_immediate(r1(), 6)),
+            encode(InstructionBuilder::load_immediate(r2(), 7)),
+            encode(InstructionBuilder::mul(r3(), r1(), r2())),
+            encode(InstructionBuilder::compare(r3(), r1())),
+            encode(InstructionBuilder::add_immediate(r3(), r4(), 2)),
+            encode(InstructionBuilder::halt()),
+        ];
+        vm.load_program(&program).unwrap();
+        vm.run().unwrap();
+
+        // CMP only touches the flags, so it doesn't show up
+        assert_eq!(*writes.borrow(), vec![(1, 6), (2, 7), (3, 42), (3, 2)]);
+    }
+
+    #[test]
+    fn test_snapshot_restore_replays_identically() {
+        let program = vec![
+            encode(InstructionBuilder::load_immediate(r4(), 16)),
+            encode(InstructionBuilder::allocate(r8(), r4())),
+            encode(InstructionBuilder::load_immediate(r1(), 0)),
+            encode(InstructionBuilder::load_immediate(r12(), 10)),
+            encode(InstructionBuilder::load_immediate(r3(), 1)),
+            // loop:
+            encode(InstructionBuilder::add(r1(), r1(), r3())),
+            encode(InstructionBuilder::store(r1(), r8(), 0)),
+            encode(InstructionBuilder::sub(r13(), r1(), r12())),
+            encode(InstructionBuilder::branch_not_zero(r13(), (-16i16) as u16)),
+            encode(InstructionBuilder::halt()),
+        ];
+
+        let mut vm = VM::new_default();
+        vm.load_program(&program).unwrap();
+        // Pause on the STORE of the third iteration
+        vm.add_breakpoint(24);
+        for _ in 0..3 {
+            vm.run().unwrap();
+        }
+        vm.remove_breakpoint(24);
+        let snap = vm.snapshot();
+        let mid_r1 = vm.registers.read(1).unwrap();
+        assert_eq!(mid_r1, 3);
+
+        vm.run().unwrap();
+        let final_regs = *vm.registers.get_all();
+        let final_count = vm.instruction_count;
+        let buffer = vm.registers.read_u32(8).unwrap();
+        assert_eq!(vm.memory.read_word(buffer).unwrap(), 10);
+
+        vm.restore(&snap);
+        assert_eq!(vm.pc, snap.pc());
+        assert_eq!(vm.instruction_count, snap.instruction_count());
+        assert_eq!(vm.registers.read(1).unwrap(), mid_r1);
+        assert_eq!(vm.memory.read_word(buffer).unwrap(), 2);
+        assert_eq!(vm.gc.object_count(), 1);
+
+        vm.run().unwrap();
+        assert_eq!(*vm.registers.get_all(), final_regs);
+        assert_eq!(vm.instruction_count, final_count);
+        assert_eq!(vm.memory.read_word(buffer).unwrap(), 10);
+    }
+
+    #[test]
+    fn test_runaway_recursion_hits_call_depth_limit() {
+        // func: CALL func
+        let program = vec![encode(InstructionBuilder::call(0))];
+
+        let mut vm = VM::new_default();
+        vm.load_program(&program).unwrap();
+        let err = vm.run().unwrap_err();
+        assert_eq!(err.kind, VMError::CallStackOverflow(DEFAULT_MAX_CALL_DEPTH));
+        assert_eq!(err.instruction_count, DEFAULT_MAX_CALL_DEPTH as u64 + 1);
+        assert_eq!(vm.call_depth(), DEFAULT_MAX_CALL_DEPTH);
+
+        vm.set_max_call_depth(5);
+        vm.load_program(&program).unwrap();
+        assert_eq!(vm.run().unwrap_err().kind, VMError::CallStackOverflow(5));
+    }
+
+    #[test]
+    fn test_alloc_instructions_record_their_pc() {
+        let program = vec![
+            encode(InstructionBuilder::load_immediate(r1(), 8)),  // 0
+            encode(InstructionBuilder::allocate(r2(), r1())),     // 4
+            encode(InstructionBuilder::load_immediate(r1(), 16)), // 8
+            encode(InstructionBuilder::allocate(r3(), r1())),     // 12
+            encode(InstructionBuilder::halt()),
+        ];
+
+        let mut vm = VM::new_default();
+        vm.load_program(&program).unwrap();
+        vm.run().unwrap();
+
+        let first = vm.registers.read_u32(2).unwrap();
+        let second = vm.registers.read_u32(3).unwrap();
+        assert_eq!(vm.get_memory().live_allocations(), vec![(first, 8, 4), (second, 16, 12)]);
+    }
+
+    #[test]
+    fn test_strict_bounds_catch_store_past_allocation() {
+        let program = vec![
+            encode(InstructionBuilder::load_immediate(r2(), 8)),
+            encode(InstructionBuilder::allocate(r1(), r2())),
+            encode(InstructionBuilder::mov(r4(), r1())),
+            encode(InstructionBuilder::load_immediate(r3(), 42)),
+            encode(InstructionBuilder::store(r3(), r4(), 4)), // last word of the block
+            encode(InstructionBuilder::store(r3(), r4(), 8)), // one past the end
+            encode(InstructionBuilder::halt()),
+        ];
+
+        let mut vm = VM::new_default();
+        vm.load_program(&program).unwrap();
+        vm.run().unwrap();
+
+        let mut vm = VM::new_default();
+        vm.set_strict_bounds(true);
+        vm.load_program(&program).unwrap();
+        let err = vm.run().unwrap_err();
+        let block = vm.registers.read_u32(1).unwrap();
+        assert_eq!(err.kind, VMError::MemoryAccessViolation(block + 8));
+        assert_eq!(err.pc, 20);
+    }
+
+    #[test]
+    fn test_strict_bounds_catch_bulk_overrun() {
+        let setup = [
+            encode(InstructionBuilder::load_immediate(r2(), 8)),
+            encode(InstructionBuilder::allocate(r1(), r2())),
+            encode(InstructionBuilder::allocate(r3(), r2())),
+            encode(InstructionBuilder::load_immediate(r4(), 12)),
+        ];
+        let run = |op: InstructionType| {
+            let mut program = setup.to_vec();
+            program.push(encode(InstructionBuilder::mem_copy(r3(), r1(), r2()))); // exact fit
+            program.push(encode(op));
+            program.push(encode(InstructionBuilder::halt()));
+
+            let mut vm = VM::new_default();
+            vm.set_strict_bounds(true);
+            vm.load_program(&program).unwrap();
+            let err = vm.run().unwrap_err();
+            assert_eq!(err.pc, 20);
+            (err.kind, vm.registers.read_u32(1).unwrap(), vm.registers.read_u32(3).unwrap())
+        };
+
+        // Reading past the source block
+        let (kind, src, _) = run(InstructionBuilder::mem_copy(r3(), r1(), r4()));
+        assert_eq!(kind, VMError::MemoryAccessViolation(src));
+
+        // Writing past the destination block
+        let (kind, _, dst) = run(InstructionBuilder::mem_set(r3(), r0(), r4()));
+        assert_eq!(kind, VMError::MemoryAccessViolation(dst));
+    }
+
+    #[test]
+    fn test_compare_and_swap() {
+        let program = vec![
+            encode(InstructionBuilder::load_immediate(r2(), 4)),
+            encode(InstructionBuilder::allocate(r1(), r2())),
+            encode(InstructionBuilder::load_immediate(r3(), 10)),
+            encode(InstructionBuilder::array_store(r3(), r1(), r0())),
+            // Succeeds: memory holds the expected 10
+            encode(InstructionBuilder::load_immediate(r5(), 99)),
+            encode(InstructionBuilder::compare_and_swap(r5(), r1(), r3())),
+            // Fails: memory now holds 99, not 10
+            encode(InstructionBuilder::load_immediate(r7(), 55)),
+            encode(InstructionBuilder::compare_and_swap(r7(), r1(), r3())),
+            encode(InstructionBuilder::array_load(r8(), r1(), r0())),
+            encode(InstructionBuilder::halt()),
+        ];
+
+        let mut vm = VM::new_default();
+        vm.load_program(&program).unwrap();
+        vm.run().unwrap();
+
+        assert_eq!(vm.registers.read(5).unwrap(), 10);
+        assert_eq!(vm.registers.read(7).unwrap(), 99);
+        assert_eq!(vm.registers.read(8).unwrap(), 99);
+    }
+
+    #[test]
+    fn test_jump_register_dispatch() {
+        let program = vec![
+            encode(InstructionBuilder::load_immediate(r2(), 16)), // block A
+            encode(InstructionBuilder::branch_zero(r1(), 4)),
+            encode(InstructionBuilder::load_immediate(r2(), 24)), // block B
+            encode(InstructionBuilder::jump_register(r2())),
+            encode(InstructionBuilder::load_immediate(r3(), 111)),
+            encode(InstructionBuilder::halt()),
+            encode(InstructionBuilder::load_immediate(r3(), 222)),
+            encode(InstructionBuilder::halt()),
+        ];
+
+        for (selector, expected) in [(0, 111), (1, 222)] {
+            let mut vm = VM::new_default();
+            vm.get_registers_mut().write(1, selector).unwrap();
+            vm.load_program(&program).unwrap();
+            vm.run().unwrap();
+            assert_eq!(vm.registers.read(3).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_call_register() {
+        let program = vec![
+            encode(InstructionBuilder::load_immediate(r2(), 16)),
+            encode(InstructionBuilder::call_register(r2())),
+            encode(InstructionBuilder::load_immediate(r4(), 7)),
+            encode(InstructionBuilder::halt()),
+            encode(InstructionBuilder::load_immediate(r3(), 5)),
+            encode(InstructionBuilder::ret()),
+        ];
+
+        let mut vm = VM::new_default();
+        vm.load_program(&program).unwrap();
+        vm.run().unwrap();
+        assert_eq!(vm.registers.read(3).unwrap(), 5);
+        assert_eq!(vm.registers.read(4).unwrap(), 7);
+        assert_eq!(vm.call_depth(), 0);
+    }
+
+    #[test]
+    fn test_indirect_jump_outside_code_is_rejected() {
+        let mut vm = VM::new_default();
+        let code_size = vm.get_memory().layout().code_size;
+        vm.get_registers_mut().write_u32(1, code_size).unwrap();
+        vm.load_program(&[encode(InstructionBuilder::jump_register(r1()))]).unwrap();
+        assert_eq!(vm.run().unwrap_err().kind, VMError::InvalidJumpAddress(code_size));
+
+        let mut vm = VM::new_default();
+        vm.get_registers_mut().write(1, -4).unwrap();
+        vm.load_program(&[encode(InstructionBuilder::call_register(r1()))]).unwrap();
+        assert_eq!(vm.run().unwrap_err().kind, VMError::InvalidJumpAddress(-4i32 as u32));
+        assert_eq!(vm.call_depth(), 0);
+    }
+
+    #[test]
+    fn test_enter_and_leave_frame() {
+        let program = vec![
+            encode(InstructionBuilder::enter(8)),
+            // Locals live at [sp, fp)
+            encode(InstructionBuilder::mov(r4(), sp())),
+            encode(InstructionBuilder::load_immediate(r5(), 77)),
+            encode(InstructionBuilder::store(r5(), r4(), 4)),
+            encode(InstructionBuilder::load(r6(), r4(), 4)),
+            encode(InstructionBuilder::mov(r7(), fp())),
+            encode(InstructionBuilder::push(r5())), // LEAVE discards anything left on the stack
+            encode(InstructionBuilder::leave()),
+            encode(InstructionBuilder::halt()),
+        ];
+
+        let mut vm = VM::new_default();
+        let caller_fp = 0x1234;
+        vm.get_registers_mut().write(30, caller_fp).unwrap();
+        let sp0 = vm.get_memory().get_stack_pointer();
+        vm.load_program(&program).unwrap();
+        vm.run().unwrap();
+
+        assert_eq!(vm.registers.read(6).unwrap(), 77);
+        // Inside the frame, fp pointed at the saved fp with the locals below it
+        assert_eq!(vm.registers.read_u32(7).unwrap(), sp0 - 4);
+        assert_eq!(vm.registers.read_u32(4).unwrap(), sp0 - 12);
+        assert_eq!(vm.get_memory().read_word(sp0 - 4).unwrap(), caller_fp as u32);
+
+        // Afterwards everything is back where it started
+        assert_eq!(vm.registers.read(30).unwrap(), caller_fp);
+        assert_eq!(vm.get_memory().get_stack_pointer(), sp0);
+        assert_eq!(vm.registers.read_u32(29).unwrap(), sp0);
+    }
+
+    #[test]
+    fn test_sp_register_follows_stack() {
+        let program = vec![
+            encode(InstructionBuilder::push(r1())),   // 0x00
+            encode(InstructionBuilder::mov(r2(), sp())), // 0x04
+            encode(InstructionBuilder::call(0x1C)),   // 0x08
+            encode(InstructionBuilder::mov(r4(), sp())), // 0x0C
+            encode(InstructionBuilder::pop(r5())),    // 0x10
+            encode(InstructionBuilder::mov(r6(), sp())), // 0x14
+            encode(InstructionBuilder::halt()),       // 0x18
+            encode(InstructionBuilder::mov(r3(), sp())), // 0x1C
+            encode(InstructionBuilder::ret()),        // 0x20
+        ];
+
+        let mut vm = VM::new_default();
+        let sp0 = vm.get_memory().get_stack_pointer();
+        vm.load_program(&program).unwrap();
+        vm.run().unwrap();
+
+        let seen: Vec<u32> = [2, 3, 4, 6].iter().map(|&reg| vm.registers.read_u32(reg).unwrap()).collect();
+        assert_eq!(seen, vec![sp0 - 4, sp0 - 8, sp0 - 4, sp0]);
+    }
+
+    #[test]
+    fn test_grow_memory_rebases_frames() {
+        let program = vec![
+            encode(InstructionBuilder::enter(8)),
+            encode(InstructionBuilder::load_immediate(r5(), 42)),
+            encode(InstructionBuilder::store(r5(), fp(), -4i16 as u16)),
+            encode(InstructionBuilder::enter(4)),
+            encode(InstructionBuilder::leave()),
+            encode(InstructionBuilder::load(r6(), fp(), -4i16 as u16)),
+            encode(InstructionBuilder::leave()),
+            encode(InstructionBuilder::halt()),
+        ];
+
+        let mut vm = VM::new_default();
+        let sp0 = vm.get_memory().get_stack_pointer();
+        vm.load_program(&program).unwrap();
+        assert!(vm.run_with_limit(4).is_err());
+
+        // Grow with two frames on the stack, then unwind them
+        vm.grow_memory(4096).unwrap();
+        assert_eq!(vm.registers.read_u32(29).unwrap(), vm.get_memory().get_stack_pointer());
+        assert_eq!(vm.registers.read_u32(30).unwrap(), sp0 - 16 + 4096);
+        vm.run().unwrap();
+
+        assert_eq!(vm.registers.read(6).unwrap(), 42);
+        assert_eq!(vm.registers.read(30).unwrap(), 0);
+        assert_eq!(vm.get_memory().get_stack_pointer(), sp0 + 4096);
+        assert_eq!(vm.registers.read_u32(29).unwrap(), sp0 + 4096);
+    }
+
+    #[test]
+    fn test_enter_past_stack_limit_overflows() {
+        let mut vm = VM::new_default();
+        vm.load_program(&[encode(InstructionBuilder::enter(0xFFFF)); 64]).unwrap();
+        assert_eq!(vm.run().unwrap_err().kind, VMError::StackOverflow);
+    }
+
+    #[test]
+    fn test_call_depth_tracks_returns() {
+        let program = vec![
+            encode(InstructionBuilder::call(12)),   // 0x00
+            encode(InstructionBuilder::call(12)),   // 0x04
+            encode(InstructionBuilder::halt()),     // 0x08
+            encode(InstructionBuilder::ret()),      // 0x0C
+        ];
+
+        let mut vm = VM::new_default();
+        vm.set_max_call_depth(1);
+        vm.load_program(&program).unwrap();
+        vm.run().unwrap();
+        assert_eq!(vm.call_depth(), 0);
+    }
+
+    #[test]
+    fn test_call_stack_reports_return_chain() {
+        let program = vec![
+            encode(InstructionBuilder::call(0x10)),      // 0x00: main calls outer
+            encode(InstructionBuilder::halt()),          // 0x04
+            encode(InstructionBuilder::nop()),           // 0x08
+            encode(InstructionBuilder::nop()),           // 0x0C
+            encode(InstructionBuilder::load_immediate(r1(), 5)), // 0x10: outer
+            encode(InstructionBuilder::push(r1())),      // 0x14: data on the stack
+            encode(InstructionBuilder::call(0x24)),      // 0x18: outer calls inner
+            encode(InstructionBuilder::pop(r1())),       // 0x1C
+            encode(InstructionBuilder::ret()),           // 0x20
+            encode(InstructionBuilder::call(0x30)),      // 0x24: inner calls leaf
+            encode(InstructionBuilder::ret()),           // 0x28
+            encode(InstructionBuilder::nop()),           // 0x2C
+            encode(InstructionBuilder::halt()),          // 0x30: leaf stops here
+        ];
+
+        let mut vm = VM::new_default();
+        vm.load_program(&program).unwrap();
+        vm.run().unwrap();
+
+        // Stopped inside leaf: each entry is the address after a call site
+        assert_eq!(vm.call_stack(), vec![0x28, 0x1C, 0x04]);
+        assert_eq!(vm.call_depth(), 3);
+
+        let snap = vm.snapshot();
+        vm.load_program(&program).unwrap();
+        assert!(vm.call_stack().is_empty());
+        vm.restore(&snap);
+        assert_eq!(vm.call_stack(), vec![0x28, 0x1C, 0x04]);
+    }
+
+    #[test]
+    fn test_opcode_profile_counts_loop() {
+        // r1 counts down from 10; the loop body runs 10 times
+        let program = vec![
+            encode(InstructionBuilder::load_immediate(r1(), 10)),
+            encode(InstructionBuilder::load_immediate(r2(), 1)),
+            encode(InstructionBuilder::load_immediate(r3(), 0)),
+            // loop:
+            encode(InstructionBuilder::add(r3(), r3(), r1())),
+            encode(InstructionBuilder::sub(r1(), r1(), r2())),
+            encode(InstructionBuilder::branch_not_zero(r1(), (-12i16) as u16)),
+            encode(InstructionBuilder::halt()),
+        ];
+
+        let mut vm = VM::new_default();
+        vm.load_program(&program).unwrap();
+        vm.run().unwrap();
+        assert!(vm.opcode_profile().is_empty());
+
+        vm.set_profiling(true);
+        vm.load_program(&program).unwrap();
+        vm.run().unwrap();
+        assert_eq!(vm.registers.read(3).unwrap(), 55);
+
+        let profile = vm.opcode_profile();
+        assert_eq!(profile["LI"], 3);
+        assert_eq!(profile["ADD"], 10);
+        assert_eq!(profile["SUB"], 10);
+        assert_eq!(profile["BNZ"], 10);
+        assert_eq!(profile["HALT"], 1);
+        assert_eq!(profile.values().sum::<u64>(), vm.instruction_count);
+
+        vm.clear_opcode_profile();
+        assert!(vm.opcode_profile().is_empty());
+    }
+
+    #[test]
+    fn test_vm_creation() {
+        let vm = VM::new_default();
+        assert_eq!(vm.pc, 0);
+        assert!(!vm.running);
+        assert_eq!(vm.instruction_count, 0);
+    }
+
+    #[test]
+    fn test_program_loading() {
+        let mut vm = VM::new_default();
+        let program = vec![0x12345678, 0xABCDEF00];
+        
+        assert!(vm.load_program(&program).is_ok());
+        assert_eq!(vm.memory.read_word(0).unwrap(), 0x12345678);
+        assert_eq!(vm.memory.read_word(4).unwrap(), 0xABCDEF00);
+    }
+
+    #[test]
+    fn test_run_with_limit() {
+        let mut vm = VM::new_default();
+        vm.load_program(&[encode(InstructionBuilder::jump(0))]).unwrap();
+
+        assert_eq!(
+            vm.run_with_limit(100).map_err(|e| e.kind),
+            Err(VMError::InstructionLimitExceeded(100))
+        );
+        assert_eq!(vm.get_instruction_count(), 100);
+
+        // Programs that halt within the limit run to completion
+        let program = vec![
+            encode(InstructionBuilder::load_immediate(r1(), 7)),
+            encode(InstructionBuilder::halt()),
+        ];
+        vm.load_program(&program).unwrap();
+        assert!(vm.run_with_limit(2).is_ok());
+        assert_eq!(vm.registers.read(1).unwrap(), 7);
+    }
+
+    #[test]
+    fn test_trace_hook() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        let mut vm = VM::new_default();
+        let program = vec![
+            encode(InstructionBuilder::load_immediate(r1(), 1)), // 0
+            encode(InstructionBuilder::jump(12)),                // 4
+            encode(InstructionBuilder::nop()),                   // 8 (skipped)
+            encode(InstructionBuilder::halt()),                  // 12
+        ];
+        vm.load_program(&program).unwrap();
+
+        let trace = Rc::new(RefCell::new(Vec::new()));
+        let sink = Rc::clone(&trace);
+        vm.set_trace_hook(Box::new(move |pc, instr| {
+            sink.borrow_mut().push((pc, *instr));
+        }));
+        vm.run().unwrap();
+
+        let pcs: Vec<u32> = trace.borrow().iter().map(|(pc, _)| *pc).collect();
+        assert_eq!(pcs, vec![0, 4, 12]);
+        assert_eq!(trace.borrow()[2].1, InstructionBuilder::halt());
+    }
+
+    #[test]
+    fn test_watchpoints_pause_after_store() {
+        let program = vec![
+            encode(InstructionBuilder::load_immediate(r4(), 16)),   // 0x00
+            encode(InstructionBuilder::allocate(r8(), r4())),       // 0x04
+            encode(InstructionBuilder::load_immediate(r1(), 7)),    // 0x08
+            encode(InstructionBuilder::store(r1(), r8(), 0)),       // 0x0C: not watched
+            encode(InstructionBuilder::store_byte(r1(), r8(), 5)),  // 0x10: watched byte
+            encode(InstructionBuilder::load_immediate(r2(), 1)),    // 0x14
+            encode(InstructionBuilder::store(r2(), r8(), 4)),       // 0x18: word covers it
+            encode(InstructionBuilder::halt()),                     // 0x1C
+        ];
+
+        let mut vm = VM::new_default();
+        vm.load_program(&program).unwrap();
+        vm.add_breakpoint(0x0C);
+        vm.run().unwrap();
+        let buffer = vm.registers.read_u32(8).unwrap();
+        vm.remove_breakpoint(0x0C);
+        vm.add_watchpoint(buffer + 5);
+
+        // Pauses right after the byte store, with the new value visible
+        vm.run().unwrap();
+        assert!(vm.is_running());
+        assert_eq!(vm.hit_watchpoint(), Some(buffer + 5));
+        assert_eq!(vm.get_pc(), 0x14);
+        assert_eq!(vm.memory.read_byte(buffer + 5).unwrap(), 7);
+
+        // A wider store overlapping the address also triggers
+        vm.run().unwrap();
+        assert_eq!(vm.hit_watchpoint(), Some(buffer + 5));
+        assert_eq!(vm.get_pc(), 0x1C);
+
+        vm.run().unwrap();
+        assert!(!vm.is_running());
+        assert_eq!(vm.hit_watchpoint(), None);
+        assert!(vm.remove_watchpoint(buffer + 5));
+    }
+
+    #[test]
+    fn test_breakpoints() {
+        let mut vm = VM::new_default();
+        let program = vec![
+            encode(InstructionBuilder::load_immediate(r1(), 1)), // 0
+            encode(InstructionBuilder::load_immediate(r2(), 2)), // 4
+            encode(InstructionBuilder::load_immediate(r3(), 3)), // 8
+            encode(InstructionBuilder::halt()),                  // 12
+        ];
+        vm.load_program(&program).unwrap();
+        vm.add_breakpoint(8);
+
+        // Pauses before executing the instruction at the breakpoint
+        vm.run().unwrap();
+        assert!(vm.is_running());
+        assert_eq!(vm.hit_breakpoint(), Some(8));
+        assert_eq!(vm.get_pc(), 8);
+        assert_eq!(vm.registers.read(2).unwrap(), 2);
+        assert_eq!(vm.registers.read(3).unwrap(), 0);
+
+        // Resuming continues past it to completion
+        vm.run().unwrap();
+        assert!(!vm.is_running());
+        assert_eq!(vm.hit_breakpoint(), None);
+        assert_eq!(vm.registers.read(3).unwrap(), 3);
+
+        assert!(vm.remove_breakpoint(8));
+        assert!(!vm.remove_breakpoint(8));
+    }
+
+    #[test]
+    fn test_validate_program_reports_every_problem() {
+        let valid = vec![
+            encode(InstructionBuilder::load_immediate(r1(), 3)),
+            encode(InstructionBuilder::branch_zero(r1(), 4)),
+            encode(InstructionBuilder::call(0)),
+            encode(InstructionBuilder::halt()),
+        ];
+        assert_eq!(VM::validate_program(&valid), Ok(()));
+
+        let program = vec![
+            encode(InstructionBuilder::load_immediate(r1(), 3)),
+            0xFF00_0000,                                          // invalid opcode
+            encode(InstructionBuilder::jump(6)),                  // misaligned
+            encode(InstructionBuilder::branch_zero(r1(), 0x100)), // past the end
+            encode(InstructionBuilder::halt()),
+        ];
+        let errors = VM::validate_program(&program).unwrap_err();
+        assert_eq!(errors, vec![
+            ValidationError::InvalidInstruction { address: 4, word: 0xFF00_0000 },
+            ValidationError::MisalignedTarget { address: 8, target: 6 },
+            ValidationError::TargetOutOfRange { address: 12, target: 16 + 0x100 },
+        ]);
+    }
+
+    #[test]
+    fn test_running_off_the_end_is_an_error() {
+        let mut vm = VM::new_default();
+        vm.load_program(&[
+            encode(InstructionBuilder::load_immediate(r1(), 1)),
+            encode(InstructionBuilder::load_immediate(r2(), 2)),
+        ]).unwrap();
+
+        let err = vm.run().unwrap_err();
+        assert_eq!(err.kind, VMError::ExecutedPastProgram(8));
+        assert_eq!(err.pc, 8);
+        assert_eq!(vm.registers.read(2).unwrap(), 2);
+
+        // Jumping past the end is caught on the next fetch
+        let mut vm = VM::new_default();
+        vm.load_program(&[encode(InstructionBuilder::jump(0x100))]).unwrap();
+        assert_eq!(vm.run().unwrap_err().kind, VMError::ExecutedPastProgram(0x100));
+    }
+
+    #[test]
+    fn test_run_steps_yields_after_n_instructions() {
+        let mut vm = VM::new_default();
+        let mut program: Vec<u32> = (1..=10)
+            .map(|n| encode(InstructionBuilder::load_immediate(r1(), n)))
+            .collect();
+        program.push(encode(InstructionBuilder::halt()));
+        vm.load_program(&program).unwrap();
+
+        assert_eq!(vm.run_steps(3).unwrap(), StepOutcome::Ran(3));
+        assert_eq!(vm.get_pc(), 12);
+        assert_eq!(vm.get_instruction_count(), 3);
+        assert_eq!(vm.registers.read(1).unwrap(), 3);
+
+        vm.add_breakpoint(20);
+        assert_eq!(vm.run_steps(100).unwrap(), StepOutcome::Breakpoint(20));
+        assert_eq!(vm.get_instruction_count(), 5);
+
+        assert_eq!(vm.run_steps(100).unwrap(), StepOutcome::Halted);
+        assert_eq!(vm.get_instruction_count(), 11);
+        assert_eq!(vm.registers.read(1).unwrap(), 10);
+
+        // Halted programs stay halted instead of running past the HALT
+        assert_eq!(vm.run_steps(100).unwrap(), StepOutcome::Halted);
+        assert_eq!(vm.get_instruction_count(), 11);
+        assert_eq!(vm.get_pc(), 44);
+
+        vm.set_pc(0).unwrap();
+        assert_eq!(vm.run_steps(1).unwrap(), StepOutcome::Ran(1));
+        assert_eq!(vm.registers.read(1).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_injected_io() {
+        let out = SharedBuffer::default();
+        let inp = io::Cursor::new(b"17\n-4\n".to_vec());
+        let mut vm = VM::with_io(Box::new(out.clone()), Box::new(inp));
+
+        let program = vec![
+            encode(InstructionBuilder::read(r1())),
+            encode(InstructionBuilder::read(r2())),
+            encode(InstructionBuilder::add(r3(), r1(), r2())),
+            encode(InstructionBuilder::print(r3())),
+            encode(InstructionBuilder::print(r2())),
+            encode(InstructionBuilder::halt()),
+        ];
+        vm.load_program(&program).unwrap();
+        vm.run().unwrap();
+
+        assert_eq!(out.contents(), "Enter number: Enter number: 13\n-4\n");
+    }
+
+    #[test]
+    fn test_read_rejects_non_numeric_input() {
+        let inp = io::Cursor::new(b"seven\n".to_vec());
+        let mut vm = VM::with_io(Box::new(io::sink()), Box::new(inp));
+        let program = vec![
+            encode(InstructionBuilder::read(r1())),
+            encode(InstructionBuilder::halt()),
+        ];
+        vm.load_program(&program).unwrap();
+
+        let err = vm.run().unwrap_err().kind;
+        assert!(matches!(&err, VMError::InvalidInput(msg) if msg.starts_with("\"seven\"")), "{:?}", err);
+        assert!(err.to_string().starts_with("Invalid input: \"seven\""));
+    }
+
+    #[test]
+    fn test_print_hex() {
+        let out = SharedBuffer::default();
+        let mut vm = VM::with_io(Box::new(out.clone()), Box::new(io::empty()));
+
+        let program = vec![
+            encode(InstructionBuilder::print_hex(r1())),
+            encode(InstructionBuilder::print_hex(r2())),
+            encode(InstructionBuilder::print(r1())),
+            encode(InstructionBuilder::halt()),
+        ];
+        vm.get_registers_mut().write_u32(1, 0xDEAD_BEEF).unwrap();
+        vm.get_registers_mut().write(2, 42).unwrap();
+        vm.load_program(&program).unwrap();
+        vm.run().unwrap();
+
+        assert_eq!(out.contents(), format!("0xDEADBEEF\n0x0000002A\n{}\n", 0xDEAD_BEEFu32 as i32));
+    }
+
+    #[test]
+    fn test_seeded_random_syscall_is_reproducible() {
+        let program = vec![
+            encode(InstructionBuilder::load_immediate(r1(), SYSCALL_RANDOM as u16)),
+            encode(InstructionBuilder::syscall(Some(r2()), Some(r1()))),
+            encode(InstructionBuilder::print(r2())),
+            encode(InstructionBuilder::syscall(Some(r2()), Some(r1()))),
+            encode(InstructionBuilder::print(r2())),
+            encode(InstructionBuilder::syscall(Some(r2()), Some(r1()))),
+            encode(InstructionBuilder::print(r2())),
+            encode(InstructionBuilder::halt()),
+        ];
+        let run_with_seed = |seed: u64| {
+            let out = SharedBuffer::default();
+            let mut vm = VM::with_io(Box::new(out.clone()), Box::new(io::empty()));
+            vm.seed_rng(seed);
+            vm.load_program(&program).unwrap();
+            vm.run().unwrap();
+            out.contents()
+        };
+
+        let first = run_with_seed(42);
+        assert_eq!(first, run_with_seed(42));
+        assert_ne!(first, run_with_seed(43));
+
+        // Successive calls produce different values
+        let values: Vec<&str> = first.lines().collect();
+        assert_eq!(values.len(), 3);
+        assert!(values[0] != values[1] || values[1] != values[2]);
+    }
+
+    #[test]
+    fn test_random_syscall_needs_destination() {
+        let mut vm = VM::new_default();
+        let program = vec![
+            encode(InstructionBuilder::load_immediate(r1(), SYSCALL_RANDOM as u16)),
+            encode(InstructionBuilder::syscall(None, Some(r1()))),
+        ];
+        vm.load_program(&program).unwrap();
+        assert!(matches!(vm.run().unwrap_err().kind, VMError::SystemCallError(_)));
+    }
+
+    #[test]
+    fn test_write_string_syscall() {
+        let out = SharedBuffer::default();
+        let mut vm = VM::with_io(Box::new(out.clone()), Box::new(io::empty()));
+        let program = vec![
+            encode(InstructionBuilder::load_immediate(r1(), SYSCALL_WRITE_STRING as u16)),
+            encode(InstructionBuilder::syscall(Some(r2()), Some(r1()))),
+            encode(InstructionBuilder::halt()),
+        ];
+        vm.load_program(&program).unwrap();
+
+        let text = vm.memory.allocate(8).unwrap();
+        vm.memory.write_bytes(text, b"hello\0").unwrap();
+        vm.registers.write_u32(2, text).unwrap();
+        vm.run().unwrap();
+
+        assert_eq!(out.contents(), "hello");
+    }
+
+    #[test]
+    fn test_write_string_requires_terminator() {
+        let out = SharedBuffer::default();
+        let mut vm = VM::with_io(Box::new(out.clone()), Box::new(io::empty()));
+        let program = vec![
+            encode(InstructionBuilder::load_immediate(r1(), SYSCALL_WRITE_STRING as u16)),
+            encode(InstructionBuilder::syscall(Some(r2()), Some(r1()))),
+            encode(InstructionBuilder::halt()),
+        ];
+        vm.load_program(&program).unwrap();
+
+        let len = MAX_STRING_LENGTH + 16;
+        let text = vm.memory.allocate(len).unwrap();
+        vm.memory.mem_set(text, b'a', len).unwrap();
+        vm.registers.write_u32(2, text).unwrap();
+
+        assert!(matches!(vm.run().unwrap_err().kind, VMError::SystemCallError(_)));
+        assert_eq!(out.contents(), "");
+    }
+
+    #[test]
+    fn test_time_syscall() {
+        let program = vec![
+            encode(InstructionBuilder::load_immediate(r1(), SYSCALL_TIME as u16)),
+            encode(InstructionBuilder::syscall(Some(r2()), Some(r1()))),
+            encode(InstructionBuilder::halt()),
+        ];
+
+        let mut vm = VM::new_default();
+        vm.set_clock_override(Some(1234));
+        vm.load_program(&program).unwrap();
+        vm.run().unwrap();
+        assert_eq!(vm.registers.read(2).unwrap(), 1234);
+
+        // The real clock has barely moved since the VM was created
+        vm.set_clock_override(None);
+        vm.load_program(&program).unwrap();
+        vm.run().unwrap();
+        let elapsed = vm.registers.read(2).unwrap();
+        assert!((0..60_000).contains(&elapsed));
+    }
+
+    #[test]
+    fn test_put_char() {
+        let out = SharedBuffer::default();
+        let mut vm = VM::with_io(Box::new(out.clone()), Box::new(io::empty()));
+
+        let program = vec![
+            encode(InstructionBuilder::load_immediate(r1(), b'H' as u16)),
+            encode(InstructionBuilder::put_char(r1())),
+            encode(InstructionBuilder::load_immediate(r1(), 0x100 | b'i' as u16)), // Only the low byte is written
+            encode(InstructionBuilder::put_char(r1())),
+            encode(InstructionBuilder::halt()),
+        ];
+        vm.load_program(&program).unwrap();
+        vm.run().unwrap();
+
+        assert_eq!(out.contents(), "Hi");
+    }
+
+    #[test]
+    fn test_unsigned_branches() {
+        let mut vm = VM::new_default();
+        let program = vec![
+            encode(InstructionBuilder::load_immediate(r1(), 0xFFFF)),                 // 0: R1 = -1 (0xFFFFFFFF)
+            encode(InstructionBuilder::load_immediate(r4(), 1)),                      // 4: R4 = 1
+            encode(InstructionBuilder::branch_less_than_unsigned(r1(), r4(), 4)),     // 8: not taken
+            encode(InstructionBuilder::load_immediate(r2(), 1)),                      // 12: R2 = 1
+            encode(InstructionBuilder::branch_less_than(r1(), r4(), 4)),              // 16: taken (signed)
+            encode(InstructionBuilder::load_immediate(r3(), 1)),                      // 20: skipped
+            encode(InstructionBuilder::branch_greater_equal_unsigned(r1(), r4(), 4)), // 24: taken
+            encode(InstructionBuilder::load_immediate(r5(), 1)),                      // 28: skipped
+            encode(InstructionBuilder::halt()),                                       // 32
+        ];
+        vm.load_program(&program).unwrap();
+        vm.run().unwrap();
+
+        assert_eq!(vm.registers.read(2).unwrap(), 1);
+        assert_eq!(vm.registers.read(3).unwrap(), 0);
+        assert_eq!(vm.registers.read(5).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_byte_and_halfword_memory() {
+        let mut vm = VM::new_default();
+        let program = vec![
+            encode(InstructionBuilder::load_immediate(r1(), 16)),
+            encode(InstructionBuilder::allocate(r4(), r1())),              // R4 = buffer
+            encode(InstructionBuilder::load_immediate(r5(), 0xFFFF)),
+            encode(InstructionBuilder::store(r5(), r4(), 0)),              // word = 0xFFFFFFFF
+            encode(InstructionBuilder::load_immediate(r2(), 0xAB)),
+            encode(InstructionBuilder::store_byte(r2(), r4(), 0)),         // word = 0xFFFFFFAB
+            encode(InstructionBuilder::load_byte(r3(), r4(), 0)),          // R3 = sign_extend(0xAB)
+            encode(InstructionBuilder::load_immediate(r6(), 0x1234)),
+            encode(InstructionBuilder::store_halfword(r6(), r4(), 2)),     // word = 0x1234FFAB
+            encode(InstructionBuilder::load_halfword(r7(), r4(), 2)),      // R7 = 0x1234
+            encode(InstructionBuilder::load_halfword(r8(), r4(), 0)),      // R8 = sign_extend(0xFFAB)
+            encode(InstructionBuilder::halt()),
+        ];
+        vm.load_program(&program).unwrap();
+        vm.run().unwrap();
+
+        let buffer = vm.registers.read(4).unwrap() as u32;
+        assert_eq!(vm.memory.read_word(buffer).unwrap(), 0x1234FFAB);
+        assert_eq!(vm.registers.read(3).unwrap(), 0xABu8 as i8 as i32);
+        assert_eq!(vm.registers.read(7).unwrap(), 0x1234);
+        assert_eq!(vm.registers.read(8).unwrap(), 0xFFABu16 as i16 as i32);
+    }
+
+    #[test]
+    fn test_reallocate_tracks_gc_objects() {
+        let mut vm = VM::new_default();
+        let program = vec![
+            encode(InstructionBuilder::load_immediate(r1(), 16)),
+            encode(InstructionBuilder::allocate(r4(), r1())),
+            encode(InstructionBuilder::allocate(r5(), r1())),
+            encode(InstructionBuilder::load_immediate(r2(), 77)),
+            encode(InstructionBuilder::store(r2(), r4(), 12)),
+            encode(InstructionBuilder::halt()),
+        ];
+        vm.load_program(&program).unwrap();
+        vm.run().unwrap();
+
+        let block = vm.registers.read(4).unwrap() as u32;
+        let grown = vm.reallocate(block, 64).unwrap();
+
+        assert_ne!(grown, block);
+        assert_eq!(vm.memory.read_word(grown + 12).unwrap(), 77);
+        assert_eq!(vm.gc.object_count(), 2);
+        assert_eq!(vm.gc.total_object_size(), 16 + 64);
+    }
+
+    #[test]
+    fn test_reallocate_keeps_gc_metadata() {
+        let mut vm = VM::new_default();
+        let program = vec![
+            encode(InstructionBuilder::load_immediate(r1(), 16)),
+            encode(InstructionBuilder::allocate(r4(), r1())), // pinned
+            encode(InstructionBuilder::allocate(r5(), r1())), // parent
+            encode(InstructionBuilder::allocate(r6(), r1())), // child
+            encode(InstructionBuilder::allocate(r7(), r1())), // in the way of in-place growth
+            encode(InstructionBuilder::halt()),
+        ];
+        vm.load_program(&program).unwrap();
+        vm.run().unwrap();
+
+        let [pinned, parent, child] = [4, 5, 6].map(|reg| vm.registers.read(reg).unwrap() as u32);
+        vm.gc.pin(pinned);
+        vm.gc.add_reference(parent, child);
+
+        let moved_pinned = vm.reallocate(pinned, 64).unwrap();
+        let moved_child = vm.reallocate(child, 64).unwrap();
+        assert_ne!(moved_pinned, pinned);
+        assert_ne!(moved_child, child);
+
+        // Only the parent stays in a register
+        for reg in [4, 6, 7] {
+            vm.registers.write(reg, 0).unwrap();
+        }
+        vm.force_gc().unwrap();
+
+        assert!(vm.gc.is_pinned(moved_pinned));
+        assert!(vm.memory.is_valid_address(moved_pinned));
+        assert!(vm.memory.is_valid_address(moved_child));
+        assert_eq!(vm.gc.object_count(), 3);
+        assert_eq!(vm.gc.total_object_size(), 64 + 16 + 64);
+    }
+
+    #[test]
+    fn test_memcpy_memset_instructions() {
+        let mut vm = VM::new_default();
+        let program = vec![
+            encode(InstructionBuilder::load_immediate(r1(), 16)),
+            encode(InstructionBuilder::allocate(r4(), r1())),              // R4 = src
+            encode(InstructionBuilder::allocate(r5(), r1())),              // R5 = dst
+            encode(InstructionBuilder::load_immediate(r2(), 0x5A)),
+            encode(InstructionBuilder::load_immediate(r3(), 8)),
+            encode(InstructionBuilder::mem_set(r4(), r2(), r3())),         // src[0..8] = 0x5A
+            encode(InstructionBuilder::mem_copy(r5(), r4(), r1())),        // dst[0..16] = src[0..16]
+            encode(InstructionBuilder::halt()),
+        ];
+        vm.load_program(&program).unwrap();
+        vm.run().unwrap();
+
+        let dst = vm.registers.read(5).unwrap() as u32;
+        assert_eq!(vm.memory.read_word(dst).unwrap(), 0x5A5A5A5A);
+        assert_eq!(vm.memory.read_word(dst + 4).unwrap(), 0x5A5A5A5A);
+        assert_eq!(vm.memory.read_word(dst + 8).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_gc_follows_stored_pointers() {
+        let program = vec![
+            encode(InstructionBuilder::load_immediate(r1(), 16)),
+            encode(InstructionBuilder::allocate(r4(), r1())),   // A
+            encode(InstructionBuilder::allocate(r8(), r1())),   // B
+            encode(InstructionBuilder::allocate(r9(), r1())),   // C
+            encode(InstructionBuilder::store(r8(), r4(), 0)),   // A[0] = B
+            encode(InstructionBuilder::store(r9(), r8(), 4)),   // B[1] = C
+            encode(InstructionBuilder::load_immediate(r8(), 0)),
+            encode(InstructionBuilder::load_immediate(r9(), 0)),
+            encode(InstructionBuilder::halt()),
+        ];
+
+        // Only A is rooted; B and C are reachable through memory
+        let config = GCConfig { scan_memory: true, ..Default::default() };
+        let mut vm = VM::new_with_gc(16 * 1024 * 1024, config);
+        vm.load_program(&program).unwrap();
+        vm.run().unwrap();
+        vm.force_gc().unwrap();
+        assert_eq!(vm.gc.object_count(), 3);
+
+        // Without scanning, the stored pointers are invisible to the collector
+        let mut vm = VM::new_default();
+        vm.load_program(&program).unwrap();
+        vm.run().unwrap();
+        vm.force_gc().unwrap();
+        assert_eq!(vm.gc.object_count(), 1);
+    }
+
+    #[test]
+    fn test_store_during_incremental_marking() {
+        let config = GCConfig { scan_memory: true, incremental_budget: Some(1), ..Default::default() };
+        let mut vm = VM::new_with_gc(16 * 1024 * 1024, config);
+        vm.set_auto_gc(false);
+        let program = vec![
+            encode(InstructionBuilder::load_immediate(r1(), 32)),
+            encode(InstructionBuilder::allocate(r2(), r1())), // A, the only root
+            encode(InstructionBuilder::allocate(r3(), r1())), // W
+            encode(InstructionBuilder::allocate(r4(), r1())), // B
+            encode(InstructionBuilder::store(r3(), r2(), 0)), // A -> W
+            encode(InstructionBuilder::store(r4(), r3(), 0)), // W -> B
+            encode(InstructionBuilder::load_immediate(r3(), 0)),
+            encode(InstructionBuilder::load_immediate(r4(), 0)),
+            // Marking starts here: A is scanned, W is still gray
+            encode(InstructionBuilder::load(r5(), r2(), 0)),
+            encode(InstructionBuilder::load(r6(), r5(), 0)),
+            encode(InstructionBuilder::store(r6(), r2(), 4)), // A -> B, behind the collector
+            encode(InstructionBuilder::store(r0(), r5(), 0)), // drop W -> B
+            encode(InstructionBuilder::load_immediate(r5(), 0)),
+            encode(InstructionBuilder::load_immediate(r6(), 0)),
+            encode(InstructionBuilder::halt()),
+        ];
+        vm.load_program(&program).unwrap();
+        assert!(vm.run_with_limit(8).is_err());
+        let b = vm.memory.read_word(vm.memory.read_word(vm.registers.read_u32(2).unwrap()).unwrap()).unwrap();
+
+        assert!(!vm.gc.collect_incremental(&mut vm.memory, &vm.registers, 1).unwrap());
+        vm.run().unwrap();
+        while !vm.gc.collect_incremental(&mut vm.memory, &vm.registers, 1).unwrap() {}
+
+        // B is only reachable through A, which was scanned before the store
+        assert!(vm.memory.is_valid_address(b));
+    }
+
+    #[test]
+    fn test_memcpy_during_incremental_marking() {
+        let config = GCConfig { scan_memory: true, incremental_budget: Some(1), ..Default::default() };
+        let mut vm = VM::new_with_gc(16 * 1024 * 1024, config);
+        vm.set_auto_gc(false);
+        let program = vec![
+            encode(InstructionBuilder::load_immediate(r1(), 32)),
+            encode(InstructionBuilder::allocate(r2(), r1())), // A, the only root
+            encode(InstructionBuilder::allocate(r3(), r1())), // W
+            encode(InstructionBuilder::allocate(r4(), r1())), // B
+            encode(InstructionBuilder::store(r3(), r2(), 0)), // A -> W
+            encode(InstructionBuilder::store(r4(), r3(), 0)), // W -> B
+            encode(InstructionBuilder::load_immediate(r3(), 0)),
+            encode(InstructionBuilder::load_immediate(r4(), 0)),
+            // Marking starts here: A is scanned, W is still gray
+            encode(InstructionBuilder::load(r5(), r2(), 0)),
+            encode(InstructionBuilder::add_immediate(r6(), r2(), 4)),
+            encode(InstructionBuilder::load_immediate(r7(), 4)),
+            encode(InstructionBuilder::mem_copy(r6(), r5(), r7())), // A -> B, behind the collector
+            encode(InstructionBuilder::store(r0(), r5(), 0)), // drop W -> B
+            encode(InstructionBuilder::load_immediate(r5(), 0)),
+            encode(InstructionBuilder::load_immediate(r6(), 0)),
+            encode(InstructionBuilder::halt()),
+        ];
+        vm.load_program(&program).unwrap();
+        assert!(vm.run_with_limit(8).is_err());
+        let b = vm.memory.read_word(vm.memory.read_word(vm.registers.read_u32(2).unwrap()).unwrap()).unwrap();
+
+        assert!(!vm.gc.collect_incremental(&mut vm.memory, &vm.registers, 1).unwrap());
+        vm.run().unwrap();
+        while !vm.gc.collect_incremental(&mut vm.memory, &vm.registers, 1).unwrap() {}
+
+        assert_eq!(vm.memory.read_word(vm.registers.read_u32(2).unwrap() + 4).unwrap(), b);
+        assert!(vm.memory.is_valid_address(b));
+    }
+
+    #[test]
+    fn test_cas_during_incremental_marking() {
+        let config = GCConfig { scan_memory: true, incremental_budget: Some(1), ..Default::default() };
+        let mut vm = VM::new_with_gc(16 * 1024 * 1024, config);
+        vm.set_auto_gc(false);
+        let program = vec![
+            encode(InstructionBuilder::load_immediate(r1(), 32)),
+            encode(InstructionBuilder::allocate(r2(), r1())), // A, the only root
+            encode(InstructionBuilder::allocate(r3(), r1())), // W
+            encode(InstructionBuilder::allocate(r4(), r1())), // B
+            encode(InstructionBuilder::store(r3(), r2(), 0)), // A -> W
+            encode(InstructionBuilder::store(r4(), r3(), 0)), // W -> B
+            encode(InstructionBuilder::load_immediate(r3(), 0)),
+            encode(InstructionBuilder::load_immediate(r4(), 0)),
+            // Marking starts here: A is scanned, W is still gray
+            encode(InstructionBuilder::load(r5(), r2(), 0)),
+            encode(InstructionBuilder::load(r6(), r5(), 0)),
+            encode(InstructionBuilder::add_immediate(r7(), r2(), 4)),
+            encode(InstructionBuilder::compare_and_swap(r6(), r7(), r0())), // A -> B, behind the collector
+            encode(InstructionBuilder::store(r0(), r5(), 0)), // drop W -> B
+            encode(InstructionBuilder::load_immediate(r5(), 0)),
+            encode(InstructionBuilder::load_immediate(r7(), 0)),
+            encode(InstructionBuilder::halt()),
+        ];
+        vm.load_program(&program).unwrap();
+        assert!(vm.run_with_limit(8).is_err());
+        let b = vm.memory.read_word(vm.memory.read_word(vm.registers.read_u32(2).unwrap()).unwrap()).unwrap();
+
+        assert!(!vm.gc.collect_incremental(&mut vm.memory, &vm.registers, 1).unwrap());
+        vm.run().unwrap();
+        while !vm.gc.collect_incremental(&mut vm.memory, &vm.registers, 1).unwrap() {}
+
+        // CAS leaves the old value (0) in r6, so A is the only path to B
+        assert_eq!(vm.registers.read(6).unwrap(), 0);
+        assert_eq!(vm.memory.read_word(vm.registers.read_u32(2).unwrap() + 4).unwrap(), b);
+        assert!(vm.memory.is_valid_address(b));
+    }
+
+    #[test]
+    fn test_minor_gc_keeps_young_object_stored_into_old() {
+        let config = GCConfig { scan_memory: true, ..Default::default() };
+        let mut vm = VM::new_with_gc(16 * 1024 * 1024, config);
+        vm.set_auto_gc(false);
+        let program = vec![
+            encode(InstructionBuilder::load_immediate(r1(), 32)),
+            encode(InstructionBuilder::allocate(r2(), r1())), // A, aged below
+            encode(InstructionBuilder::allocate(r3(), r1())), // Y
+            encode(InstructionBuilder::store(r3(), r2(), 0)), // A -> Y
+            encode(InstructionBuilder::load_immediate(r3(), 0)),
+            encode(InstructionBuilder::halt()),
+        ];
+        vm.load_program(&program).unwrap();
+        assert!(vm.run_with_limit(2).is_err());
+        vm.force_gc().unwrap();
+        vm.force_gc().unwrap();
+        vm.run().unwrap();
+
+        let y = vm.memory.read_word(vm.registers.read_u32(2).unwrap()).unwrap();
+        vm.minor_gc().unwrap();
+
+        // Y is only reachable through the old object's memory
+        assert!(vm.memory.is_valid_address(y));
+        assert_eq!(vm.gc.object_count(), 2);
+    }
+
+    #[test]
+    fn test_failed_free_keeps_object_tracked() {
+        let mut vm = VM::new_default();
+        vm.set_heap_canaries(true);
+        let program = vec![
+            encode(InstructionBuilder::load_immediate(r1(), 8)),
+            encode(InstructionBuilder::allocate(r2(), r1())),
+            encode(InstructionBuilder::store(r1(), r2(), 8)), // clobber the guard word
+            encode(InstructionBuilder::free(r2())),
+            encode(InstructionBuilder::halt()),
+        ];
+        vm.load_program(&program).unwrap();
+
+        let block = match vm.run().map_err(|e| e.kind) {
+            Err(VMError::HeapCorruption(block)) => block,
+            other => panic!("expected heap corruption, got {:?}", other),
+        };
+        assert!(vm.memory.is_valid_address(block));
+        assert_eq!(vm.gc.object_count(), 1);
+    }
+
+    #[test]
+    fn test_allocation_count_trigger() {
+        // No heap limit, so only the allocation count can trigger a collection
+        let config = GCConfig {
+            max_heap_size: 0,
+            collect_every_n_allocations: Some(3),
+            ..Default::default()
+        };
+        let mut vm = VM::new_with_gc(16 * 1024 * 1024, config);
+        let mut program = vec![encode(InstructionBuilder::load_immediate(r1(), 4))];
+        program.extend((0..7).map(|_| encode(InstructionBuilder::allocate(r2(), r1()))));
+        program.push(encode(InstructionBuilder::halt()));
+
+        vm.load_program(&program).unwrap();
+        assert!(vm.run_with_limit(3).is_err());
+        assert_eq!(vm.gc.get_stats().collections_performed, 0);
+
+        vm.run().unwrap();
+        assert_eq!(vm.gc.get_stats().collections_performed, 2);
+
+        // Reset starts the count over instead of carrying the last allocation
+        vm.reset();
+        vm.load_program(&program).unwrap();
+        assert!(vm.run_with_limit(3).is_err());
+        assert_eq!(vm.gc.get_stats().collections_performed, 0);
+    }
+
+    #[test]
+    fn test_mark_compact_keeps_register_values() {
+        let config = GCConfig { strategy: GcStrategy::MarkCompact, ..Default::default() };
+        let mut vm = VM::new_with_gc(16 * 1024 * 1024, config);
+        let program = vec![
+            encode(InstructionBuilder::load_immediate(r1(), 32)),
+            encode(InstructionBuilder::allocate(r4(), r1())),   // garbage once R4 is cleared
+            encode(InstructionBuilder::allocate(r8(), r1())),   // survives
+            encode(InstructionBuilder::load_immediate(r2(), 99)),
+            encode(InstructionBuilder::store(r2(), r8(), 0)),
+            encode(InstructionBuilder::load_immediate(r4(), 0)),
+            encode(InstructionBuilder::halt()),
+        ];
+        vm.load_program(&program).unwrap();
+        vm.run().unwrap();
+
+        // R8 could hold an integer rather than a pointer, so its object stays put
+        let before = vm.registers.read(8).unwrap() as u32;
+        vm.force_gc().unwrap();
+        let after = vm.registers.read(8).unwrap() as u32;
+
+        assert_eq!(after, before);
+        assert_eq!(vm.memory.read_word(after).unwrap(), 99);
+        assert_eq!(vm.memory.get_stats().heap_used, 32);
+        assert!(vm.gc.take_relocations().is_empty());
+    }
+
+    #[test]
+    fn test_program_loading_from_bytes() {
+        let program = vec![
+            encode(InstructionBuilder::load_immediate(r1(), 42)),
+            encode(InstructionBuilder::halt()),
+        ];
+        let bytes: Vec<u8> = program.iter().flat_map(|word| word.to_le_bytes()).collect();
+
+        let mut from_words = VM::new_default();
+        from_words.load_program(&program).unwrap();
+        let mut from_bytes = VM::new_default();
+        from_bytes.load_program_bytes(&bytes).unwrap();
+
+        for address in [0, 4] {
+            assert_eq!(
+                from_bytes.memory.read_word(address).unwrap(),
+                from_words.memory.read_word(address).unwrap()
+            );
+        }
+
+        from_bytes.run().unwrap();
+        assert_eq!(from_bytes.registers.read(1).unwrap(), 42);
+
+        assert!(matches!(
+            from_bytes.load_program_bytes(&bytes[..7]),
+            Err(VMError::MalformedProgram(_))
+        ));
+    }
+
+    #[test]
+    fn test_simple_execution() {
+        let mut vm = VM::new_default();
+        
+        // Create a simple program: LI R1, 42; HALT
+        let li_instr = InstructionBuilder::load_immediate(r1(), 42);
+        let halt_instr = InstructionBuilder::halt();
+        
         let program = vec![
             encode(li_instr),
             encode(halt_instr),
@@ -555,6 +2736,333 @@ mod tests {
         assert_eq!(vm.registers.read(3).unwrap(), 42); // Should be 42 because branch was not taken
     }
 
+    #[test]
+    fn test_shift_operations() {
+        let mut vm = VM::new_default();
+        
+        let program = vec![
+            encode(InstructionBuilder::load_immediate(r1(), 1)),
+            encode(InstructionBuilder::load_immediate(r2(), 4)),
+            encode(InstructionBuilder::shift_left(r3(), r1(), r2())),   // 1 << 4
+            encode(InstructionBuilder::load_immediate(r4(), 0x80)),
+            encode(InstructionBuilder::load_immediate(r5(), 3)),
+            encode(InstructionBuilder::shift_right(r6(), r4(), r5())),  // 0x80 >> 3
+            encode(InstructionBuilder::load_immediate(r7(), -1i16 as u16)),
+            encode(InstructionBuilder::load_immediate(r8(), 28)),
+            encode(InstructionBuilder::shift_right(r9(), r7(), r8())),  // logical, not arithmetic
+            encode(InstructionBuilder::halt()),
+        ];
+        
+        vm.load_program(&program).unwrap();
+        vm.run().unwrap();
+        
+        assert_eq!(vm.registers.read(3).unwrap(), 16);
+        assert_eq!(vm.registers.read(6).unwrap(), 0x10);
+        assert_eq!(vm.registers.read(9).unwrap(), 0xF);
+    }
+
+    #[test]
+    fn test_divmod() {
+        let program = vec![
+            encode(InstructionBuilder::load_immediate(r1(), 17)),
+            encode(InstructionBuilder::load_immediate(r2(), 5)),
+            encode(InstructionBuilder::divmod(r3(), r1(), r2())),
+            encode(InstructionBuilder::neg(r1(), r1())),
+            encode(InstructionBuilder::divmod(r5(), r1(), r2())),
+            encode(InstructionBuilder::halt()),
+        ];
+
+        let mut vm = VM::new_default();
+        vm.load_program(&program).unwrap();
+        vm.run().unwrap();
+        assert_eq!(vm.registers.read(3).unwrap(), 3);
+        assert_eq!(vm.registers.read(4).unwrap(), 2);
+        // Truncating division, matching DIV
+        assert_eq!(vm.registers.read(5).unwrap(), -3);
+        assert_eq!(vm.registers.read(6).unwrap(), -2);
+    }
+
+    #[test]
+    fn test_divmod_errors() {
+        let mut vm = VM::new_default();
+        vm.load_program(&[
+            encode(InstructionBuilder::load_immediate(r1(), 17)),
+            encode(InstructionBuilder::divmod(r3(), r1(), r2())),
+        ]).unwrap();
+        assert_eq!(vm.run().unwrap_err().kind, VMError::DivisionByZero);
+
+        // There is no register after R31 to hold the remainder
+        let mut vm = VM::new_default();
+        vm.load_program(&[
+            encode(InstructionBuilder::load_immediate(r1(), 17)),
+            encode(InstructionBuilder::load_immediate(r2(), 5)),
+            encode(InstructionBuilder::divmod(r31(), r1(), r2())),
+        ]).unwrap();
+        assert_eq!(vm.run().unwrap_err().kind, VMError::InvalidRegister(32));
+        assert_eq!(vm.registers.read(31).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_float_arithmetic() {
+        let program = vec![
+            encode(InstructionBuilder::float_add(r3(), r1(), r2())),
+            encode(InstructionBuilder::float_sub(r4(), r1(), r2())),
+            encode(InstructionBuilder::float_mul(r5(), r1(), r2())),
+            encode(InstructionBuilder::float_div(r6(), r2(), r1())),
+            encode(InstructionBuilder::float_div(r7(), r1(), r0())),
+            encode(InstructionBuilder::halt()),
+        ];
+
+        let mut vm = VM::new_default();
+        let regs = vm.get_registers_mut();
+        regs.write_float(1, 1.5).unwrap();
+        regs.write_float(2, 2.25).unwrap();
+        vm.load_program(&program).unwrap();
+        vm.run().unwrap();
+
+        assert_eq!(vm.registers.read_float(3).unwrap(), 3.75);
+        assert_eq!(vm.registers.read_float(4).unwrap(), -0.75);
+        assert_eq!(vm.registers.read_float(5).unwrap(), 3.375);
+        assert_eq!(vm.registers.read_float(6).unwrap(), 1.5);
+        assert_eq!(vm.registers.read_float(7).unwrap(), f32::INFINITY);
+        // The integer bank is untouched
+        assert_eq!(vm.registers.read(3).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_float_conversions() {
+        let program = vec![
+            encode(InstructionBuilder::load_immediate(r1(), 3)),
+            encode(InstructionBuilder::int_to_float(r1(), r1())),
+            encode(InstructionBuilder::float_to_int(r2(), r1())),
+            encode(InstructionBuilder::float_to_int(r3(), r4())),
+            encode(InstructionBuilder::float_to_int(r5(), r6())),
+            encode(InstructionBuilder::halt()),
+        ];
+
+        let mut vm = VM::new_default();
+        let regs = vm.get_registers_mut();
+        regs.write_float(4, -7.9).unwrap();
+        regs.write_float(6, 1e20).unwrap();
+        vm.load_program(&program).unwrap();
+        vm.run().unwrap();
+
+        assert_eq!(vm.registers.read_float(1).unwrap(), 3.0);
+        assert_eq!(vm.registers.read(2).unwrap(), 3);
+        assert_eq!(vm.registers.read(3).unwrap(), -7);
+        assert_eq!(vm.registers.read(5).unwrap(), i32::MAX);
+    }
+
+    #[test]
+    fn test_mul_high() {
+        let program = vec![
+            encode(InstructionBuilder::mul(r3(), r1(), r2())),
+            encode(InstructionBuilder::mul_high(r4(), r1(), r2())),
+            encode(InstructionBuilder::mul_high_unsigned(r5(), r1(), r2())),
+            encode(InstructionBuilder::mul_high(r6(), r1(), r7())),
+            encode(InstructionBuilder::mul_high_unsigned(r8(), r1(), r7())),
+            encode(InstructionBuilder::halt()),
+        ];
+
+        let mut vm = VM::new_default();
+        let regs = vm.get_registers_mut();
+        regs.write(1, 0x1234_5678).unwrap();
+        regs.write(2, 0x100).unwrap();
+        regs.write(7, -2).unwrap();
+        vm.load_program(&program).unwrap();
+        vm.run().unwrap();
+
+        // 0x1234_5678 * 0x100 = 0x12_3456_7800
+        assert_eq!(vm.registers.read_u32(3).unwrap(), 0x3456_7800);
+        assert_eq!(vm.registers.read(4).unwrap(), 0x12);
+        assert_eq!(vm.registers.read(5).unwrap(), 0x12);
+        // Signed, the product is negative; unsigned, -2 is 0xFFFF_FFFE
+        let product = 0x1234_5678i64 * -2;
+        assert_eq!(vm.registers.read(6).unwrap(), (product >> 32) as i32);
+        let product = 0x1234_5678u64 * 0xFFFF_FFFE;
+        assert_eq!(vm.registers.read_u32(8).unwrap(), (product >> 32) as u32);
+    }
+
+    #[test]
+    fn test_rotate_operations() {
+        let program = vec![
+            encode(InstructionBuilder::load_immediate(r2(), 1)),
+            encode(InstructionBuilder::rotate_left(r3(), r1(), r2())),
+            encode(InstructionBuilder::rotate_right(r4(), r3(), r2())),
+            encode(InstructionBuilder::load_immediate(r5(), 33)),
+            encode(InstructionBuilder::rotate_left(r6(), r1(), r5())), // only the low 5 bits count
+            encode(InstructionBuilder::halt()),
+        ];
+
+        let mut vm = VM::new_default();
+        vm.get_registers_mut().write_u32(1, 0x8000_0001).unwrap();
+        vm.load_program(&program).unwrap();
+        vm.run().unwrap();
+
+        assert_eq!(vm.registers.read_u32(3).unwrap(), 0x0000_0003);
+        assert_eq!(vm.registers.read_u32(4).unwrap(), 0x8000_0001);
+        assert_eq!(vm.registers.read_u32(6).unwrap(), 0x0000_0003);
+    }
+
+    #[test]
+    fn test_bit_counting() {
+        let program = vec![
+            encode(InstructionBuilder::count_leading_zeros(r3(), r1())),
+            encode(InstructionBuilder::pop_count(r4(), r2())),
+            encode(InstructionBuilder::count_leading_zeros(r5(), r0())),
+            encode(InstructionBuilder::pop_count(r6(), r7())),
+            encode(InstructionBuilder::halt()),
+        ];
+
+        let mut vm = VM::new_default();
+        let regs = vm.get_registers_mut();
+        regs.write_u32(1, 0x00FF_0000).unwrap();
+        regs.write(2, 0b1011).unwrap();
+        regs.write(7, -1).unwrap();
+        vm.load_program(&program).unwrap();
+        vm.run().unwrap();
+
+        assert_eq!(vm.registers.read(3).unwrap(), 8);
+        assert_eq!(vm.registers.read(4).unwrap(), 3);
+        assert_eq!(vm.registers.read(5).unwrap(), 32);
+        assert_eq!(vm.registers.read(6).unwrap(), 32);
+    }
+
+    #[test]
+    fn test_sign_and_zero_extension() {
+        let program = vec![
+            encode(InstructionBuilder::load_immediate(r1(), 0xFF)),
+            encode(InstructionBuilder::sign_extend_byte(r2(), r1())),
+            encode(InstructionBuilder::zero_extend_byte(r3(), r1())),
+            encode(InstructionBuilder::sign_extend_half(r5(), r4())),
+            encode(InstructionBuilder::zero_extend_half(r6(), r4())),
+            encode(InstructionBuilder::zero_extend_byte(r7(), r4())),
+            encode(InstructionBuilder::halt()),
+        ];
+
+        let mut vm = VM::new_default();
+        vm.get_registers_mut().write_u32(4, 0x1234_8001).unwrap();
+        vm.load_program(&program).unwrap();
+        vm.run().unwrap();
+
+        assert_eq!(vm.registers.read(2).unwrap(), -1);
+        assert_eq!(vm.registers.read(3).unwrap(), 255);
+        assert_eq!(vm.registers.read(5).unwrap(), 0x8001u16 as i16 as i32);
+        assert_eq!(vm.registers.read(6).unwrap(), 0x8001);
+        assert_eq!(vm.registers.read(7).unwrap(), 0x01);
+    }
+
+    #[test]
+    fn test_set_less_than() {
+        let mut vm = VM::new_default();
+        
+        // R1 = -1, R2 = 1: signed -1 < 1, but unsigned 0xFFFFFFFF > 1
+        let program = vec![
+            encode(InstructionBuilder::load_immediate(r1(), -1i16 as u16)),
+            encode(InstructionBuilder::load_immediate(r2(), 1)),
+            encode(InstructionBuilder::set_less_than(r3(), r1(), r2())),
+            encode(InstructionBuilder::set_less_than_unsigned(r4(), r1(), r2())),
+            encode(InstructionBuilder::set_less_than(r5(), r2(), r1())),
+            encode(InstructionBuilder::set_less_than_unsigned(r6(), r2(), r1())),
+            encode(InstructionBuilder::halt()),
+        ];
+        
+        vm.load_program(&program).unwrap();
+        vm.run().unwrap();
+        
+        assert_eq!(vm.registers.read(3).unwrap(), 1);
+        assert_eq!(vm.registers.read(4).unwrap(), 0);
+        assert_eq!(vm.registers.read(5).unwrap(), 0);
+        assert_eq!(vm.registers.read(6).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_push_pop() {
+        let mut vm = VM::new_default();
+        
+        let program = vec![
+            encode(InstructionBuilder::load_immediate(r1(), 10)),
+            encode(InstructionBuilder::load_immediate(r2(), 20)),
+            encode(InstructionBuilder::load_immediate(r3(), 30)),
+            encode(InstructionBuilder::push(r1())),
+            encode(InstructionBuilder::push(r2())),
+            encode(InstructionBuilder::push(r3())),
+            encode(InstructionBuilder::pop(r4())),
+            encode(InstructionBuilder::pop(r5())),
+            encode(InstructionBuilder::pop(r6())),
+            encode(InstructionBuilder::halt()),
+        ];
+        
+        vm.load_program(&program).unwrap();
+        vm.run().unwrap();
+        
+        assert_eq!(vm.registers.read(4).unwrap(), 30);
+        assert_eq!(vm.registers.read(5).unwrap(), 20);
+        assert_eq!(vm.registers.read(6).unwrap(), 10);
+    }
+
+    #[test]
+    fn test_pop_empty_stack() {
+        let mut vm = VM::new_default();
+        
+        let program = vec![
+            encode(InstructionBuilder::pop(r1())),
+            encode(InstructionBuilder::halt()),
+        ];
+        
+        vm.load_program(&program).unwrap();
+        assert!(matches!(vm.run().map_err(|e| e.kind), Err(VMError::StackUnderflow)));
+    }
+
+    /// Run CMP a, b followed by a three-way flag dispatch; returns -1, 0 or 1 in R3
+    fn run_compare(a: i16, b: i16) -> (i32, Flags) {
+        let mut vm = VM::new_default();
+        
+        let program = vec![
+            encode(InstructionBuilder::load_immediate(r1(), a as u16)),  // 0
+            encode(InstructionBuilder::load_immediate(r2(), b as u16)),  // 4
+            encode(InstructionBuilder::compare(r1(), r2())),             // 8
+            encode(InstructionBuilder::branch_flags_zero(12)),           // 12: -> 28
+            encode(InstructionBuilder::branch_flags_less_than(16)),      // 16: -> 36
+            encode(InstructionBuilder::load_immediate(r3(), 1)),         // 20: greater
+            encode(InstructionBuilder::halt()),                          // 24
+            encode(InstructionBuilder::load_immediate(r3(), 0)),         // 28: equal
+            encode(InstructionBuilder::halt()),                          // 32
+            encode(InstructionBuilder::load_immediate(r3(), -1i16 as u16)), // 36: less
+            encode(InstructionBuilder::halt()),                          // 40
+        ];
+        
+        vm.load_program(&program).unwrap();
+        vm.run().unwrap();
+        (vm.registers.read(3).unwrap(), vm.get_flags())
+    }
+
+    #[test]
+    fn test_compare_and_flag_branches() {
+        let (result, flags) = run_compare(7, 7);
+        assert_eq!(result, 0);
+        assert!(flags.zero);
+        
+        let (result, flags) = run_compare(-5, 3);
+        assert_eq!(result, -1);
+        assert!(flags.negative && !flags.zero);
+        assert!(!flags.carry); // 0xFFFFFFFB is not below 3 unsigned
+        
+        let (result, flags) = run_compare(9, 2);
+        assert_eq!(result, 1);
+        assert!(!flags.negative && !flags.zero);
+    }
+
+    #[test]
+    fn test_compare_overflow_flags() {
+        let flags = Flags::from_compare(i32::MIN, 1);
+        assert!(flags.overflow);
+        assert!(!flags.negative);
+        
+        let flags = Flags::from_compare(1, 2);
+        assert!(flags.carry && flags.negative);
+    }
+
     #[test]
     fn test_division_by_zero() {
         let mut vm = VM::new_default();
@@ -570,6 +3078,45 @@ mod tests {
         vm.load_program(&program).unwrap();
         
         let result = vm.run();
-        assert!(matches!(result, Err(VMError::DivisionByZero)));
+        assert!(matches!(result.map_err(|e| e.kind), Err(VMError::DivisionByZero)));
+    }
+
+    #[test]
+    fn test_runtime_error_reports_pc() {
+        let mut vm = VM::new_default();
+        let program = vec![
+            encode(InstructionBuilder::load_immediate(r1(), 10)),
+            encode(InstructionBuilder::nop()),
+            encode(InstructionBuilder::nop()),
+            encode(InstructionBuilder::div(r3(), r1(), r0())),
+            encode(InstructionBuilder::halt()),
+        ];
+        vm.load_program(&program).unwrap();
+
+        let err = vm.run().unwrap_err();
+        assert_eq!(err.kind, VMError::DivisionByZero);
+        assert_eq!(err.pc, 0x0C);
+        assert_eq!(err.instruction_count, 4);
+        assert_eq!(err.to_string(), "Division by zero at PC 0x0000000C (after 4 instructions)");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_reset_keeps_gc_event_listener() {
+        use crate::vm::gc::GcEvent;
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        let mut vm = VM::new_default();
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let sink = Rc::clone(&events);
+        vm.get_gc_mut().set_event_listener(Box::new(move |event| sink.borrow_mut().push(event)));
+
+        let block = vm.memory.allocate(16).unwrap();
+        vm.gc.register_object(block, 16);
+        vm.reset();
+        assert_eq!(vm.get_gc().object_count(), 0);
+
+        vm.force_gc().unwrap();
+        assert_eq!(events.borrow().first(), Some(&GcEvent::CollectionStarted));
+    }
+}